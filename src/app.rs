@@ -1,52 +1,227 @@
-use egui::{Button, Color32, Response, RichText};
-use log::{log, Level};
-
-use crate::card_search_view::CardSearchView;
+use crate::card_search_view::{CardAction, CardSearchView};
+use crate::collection::Finish;
+use crate::collection_view::CollectionView;
+use crate::command_palette::CommandPalette;
+use crate::commands::{Command, PaletteItem};
+use crate::comparison_view::ComparisonView;
+use crate::dashboard_view::{DashboardAction, DashboardView};
+use crate::deck::DeckZone;
+use crate::deck_builder_view::DeckBuilderView;
+use crate::local_cache::prune_cache;
+use crate::prices::PriceSource;
+use crate::prices_view::PricesView;
+use crate::router::{Router, View};
+use crate::settings::Settings;
+use crate::settings_view::SettingsView;
+use crate::storage_view::StorageView;
+use crate::symbol_cache::SymbolCache;
+use crate::tags::{known_tags, TagIndex};
+use crate::trade_view::TradeView;
 
 /// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(Default)]
 pub struct TemplateApp {
-    // Example stuff:
-    main_panel: String,
-
-    value: f32,
+    router: Router,
+    palette: CommandPalette,
+    dashboard_view: DashboardView,
     card_search_view: CardSearchView,
-}
-
-impl Default for TemplateApp {
-    fn default() -> Self {
-        Self {
-            // Example stuff:
-            value: 2.7,
-            main_panel: "none".to_owned(),
-            card_search_view: CardSearchView::default(),
-        }
-    }
+    deck_builder_view: DeckBuilderView,
+    collection_view: CollectionView,
+    prices_view: PricesView,
+    comparison_view: ComparisonView,
+    storage_view: StorageView,
+    trade_view: TradeView,
+    symbols: SymbolCache,
+    settings_view: SettingsView,
+    /// The settings as last applied, to notice changes.
+    applied_settings: Settings,
 }
 
 impl TemplateApp {
     /// Called once before the first frame.
-    pub fn new(cc: &eframe::CreationContext) -> Self {
+    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
         // This is also where you can customize the look and feel of egui using
         // `cc.egui_ctx.set_visuals` and `cc.egui_ctx.set_fonts`.
 
         // Load previous app state (if any).
         // Note that you must enable the `persistence` feature for this to work.
-/*        if let Some(storage) = cc.storage {
-            return eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
+        /*        if let Some(storage) = cc.storage {
+                    return eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
+                }
+        */
+        // Needed to draw the svg mana symbols.
+        egui_extras::install_image_loaders(&cc.egui_ctx);
+
+        let mut app: TemplateApp = Default::default();
+        if let Some(storage) = cc.storage {
+            app.palette.load(storage);
+            app.card_search_view.load(storage);
+            app.deck_builder_view.load(storage);
+            app.collection_view.load(storage);
+            app.prices_view.load(storage);
+            app.comparison_view.load(storage);
+            app.trade_view.load(storage);
+            app.settings_view.load(storage);
+        }
+        app.apply_settings(&cc.egui_ctx, None);
+        app
+    }
+
+    /// Make every part of the app use the current settings. `previous` is None on startup.
+    fn apply_settings(&mut self, ctx: &egui::Context, previous: Option<&Settings>) {
+        let settings = self.settings_view.settings().clone();
+        settings.client_settings().apply();
+        self.card_search_view.apply_settings(&settings, previous);
+        self.deck_builder_view.apply_settings(&settings);
+        self.comparison_view.apply_settings(&settings);
+        self.storage_view.apply_settings(&settings);
+        ctx.set_theme(settings.theme);
+        if previous.map_or(true, |p| p.disk_cache_mb != settings.disk_cache_mb) {
+            let limit = settings.disk_cache_mb * 1_000_000;
+            std::thread::spawn(move || prune_cache(limit));
+        }
+        self.applied_settings = settings;
+    }
+
+    /// Every card name the app knows of, for the command palette.
+    fn card_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .card_search_view
+            .search_results()
+            .iter()
+            .chain(self.collection_view.collection().known_cards())
+            .map(|card| card.name.as_str())
+            .chain(self.deck_builder_view.card_names())
+            .map(str::to_string)
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// Let the card searcher know every tag when one may have changed, and filter its results
+    /// by tag when it asks.
+    fn update_tags(&mut self) {
+        // Both flags are taken, so no short-circuiting.
+        let changed =
+            self.collection_view.take_tags_changed() | self.deck_builder_view.take_tags_changed();
+        let collection = self.collection_view.collection();
+        let decks = self.deck_builder_view.decks();
+        if changed {
+            let tags = collection
+                .entries
+                .iter()
+                .map(|e| &e.tags)
+                .chain(decks.iter().flat_map(|d| d.entries.iter().map(|e| &e.tags)));
+            self.card_search_view.set_known_tags(known_tags(tags));
+        }
+        if self.card_search_view.tags_pending() {
+            let index = TagIndex::new(collection, decks);
+            self.card_search_view.apply_tags(&index);
+        }
+    }
+
+    fn handle_dashboard_action(&mut self, ctx: &egui::Context, action: DashboardAction) {
+        match action {
+            DashboardAction::Search(query) => {
+                self.router.navigate(View::CardSearch);
+                self.card_search_view.search(ctx, &query);
+            }
+            DashboardAction::OpenCard(name) => {
+                self.router.navigate(View::CardSearch);
+                self.card_search_view.open_card(ctx, &name);
+            }
+            DashboardAction::OpenDeck(index) => {
+                self.deck_builder_view.select_deck(index);
+                self.router.navigate(View::DeckBuilder);
+            }
+            DashboardAction::NewDeck => {
+                self.deck_builder_view.new_deck();
+                self.router.navigate(View::DeckBuilder);
+            }
+            DashboardAction::ImportDeck => self.run_command(ctx, Command::ImportDeck),
+            DashboardAction::Show(view) => self.router.navigate(view),
+        }
+    }
+
+    fn handle_action(&mut self, action: CardAction) {
+        match action {
+            CardAction::AddToDeck(card, zone) => self.deck_builder_view.add_card(card, zone),
+            CardAction::AddToCollection(card, finish) => {
+                self.collection_view.add_card(card, finish)
+            }
+            CardAction::Compare(card) => self.comparison_view.pin(card),
+            CardAction::AddToWishlist(card) => self.trade_view.add_to_wishlist(card),
+        }
+    }
+
+    fn run_command(&mut self, ctx: &egui::Context, command: Command) {
+        match command {
+            Command::OpenPalette => self.palette.toggle(),
+            Command::Show(view) => self.router.navigate(view),
+            Command::Back => self.router.go_back(),
+            Command::Forward => self.router.go_forward(),
+            Command::FocusSearch => {
+                self.router.navigate(View::CardSearch);
+                self.card_search_view.focus_search();
+            }
+            Command::AddToDeck => {
+                if let Some(card) = self.card_search_view.selected_card().cloned() {
+                    self.deck_builder_view.add_card(card, DeckZone::Main);
+                }
+            }
+            Command::AddToCollection => {
+                if let Some(card) = self.card_search_view.selected_card().cloned() {
+                    self.collection_view.add_card(card, Finish::Nonfoil);
+                }
+            }
+            Command::ImportDeck => {
+                self.router.navigate(View::DeckBuilder);
+                self.deck_builder_view.open_import();
+            }
+            Command::ExportDeck => self.deck_builder_view.export_active_deck(ctx),
+            Command::ToggleTheme => ctx.set_theme(match ctx.theme() {
+                egui::Theme::Dark => egui::Theme::Light,
+                egui::Theme::Light => egui::Theme::Dark,
+            }),
+            Command::EditShortcuts => self.palette.show_shortcuts(),
         }
-*/
-        Default::default()
     }
 }
 
 impl eframe::App for TemplateApp {
     /// Called by the frame work to save state before shutdown.
-/*    fn save(&mut self, storage: &mut dyn eframe::Storage) {
-        eframe::set_value(storage, eframe::APP_KEY, self);
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        self.palette.save(storage);
+        self.card_search_view.save(storage);
+        self.deck_builder_view.save(storage);
+        self.collection_view.save(storage);
+        self.prices_view
+            .record_collection_value(self.collection_view.collection());
+        self.prices_view.save(storage);
+        self.comparison_view.save(storage);
+        self.trade_view.save(storage);
+        self.settings_view.save(storage);
     }
-*/
+
     /// Called each time the UI needs repainting, which may be many times per second.
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        // The theme can also be changed from the top bar or with a command.
+        let theme = ctx.options(|o| o.theme_preference);
+        if theme != self.applied_settings.theme {
+            self.settings_view.settings_mut().theme = theme;
+            self.applied_settings.theme = theme;
+        }
+        if self.settings_view.settings() != &self.applied_settings {
+            let previous = self.applied_settings.clone();
+            self.apply_settings(ctx, Some(&previous));
+        }
+        let price_source = self.settings_view.settings().price_source;
+
+        let mut commands = self.palette.pressed_commands(ctx);
+        self.router.handle_input(ctx);
+        self.router.sync_url();
+
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
             egui::menu::bar(ui, |ui| {
                 // NOTE: no File->Quit on web pages!
@@ -60,7 +235,18 @@ impl eframe::App for TemplateApp {
                     ui.add_space(16.0);
                 }
 
+                self.router.history_buttons(ui);
+                ui.add_space(16.0);
                 egui::widgets::global_theme_preference_buttons(ui);
+                ui.add_space(16.0);
+                let settings = self.settings_view.settings_mut();
+                egui::ComboBox::from_id_salt("price_source")
+                    .selected_text(format!("Prices in {}", settings.price_source.label()))
+                    .show_ui(ui, |ui| {
+                        for source in PriceSource::ALL {
+                            ui.selectable_value(&mut settings.price_source, source, source.label());
+                        }
+                    });
             });
         });
 
@@ -68,32 +254,116 @@ impl eframe::App for TemplateApp {
             .resizable(true)
             .show(ctx, |ui| {
                 ui.add_space(16.0);
-                let button_builder =
-                    Button::new(RichText::new("Card searcher").color(Color32::WHITE));
-                let button: Response;
-                if self.main_panel == "card_searcher" {
-                    button = ui.add(button_builder.fill(Color32::from_rgb(0, 120, 215)));
-                } else {
-                    button = ui.add(button_builder);
-                }
-                if button.clicked() {
-                    if self.main_panel != "card_searcher" {
-                        self.main_panel = "card_searcher".to_string();
-                        log!(Level::Info, "changed main to card_searcher");
-                    } else {
-                        self.main_panel = "none".to_string();
-                    }
-                };
+                self.router.sidebar(ui);
             });
 
+        let card_names = if self.palette.is_open() {
+            self.card_names()
+        } else {
+            vec![]
+        };
+        match self.palette.draw(ctx, &card_names) {
+            Some(PaletteItem::Command(command)) => commands.push(command),
+            Some(PaletteItem::SearchCards(query)) => {
+                self.router.navigate(View::CardSearch);
+                self.card_search_view.search(ctx, &query);
+            }
+            Some(PaletteItem::OpenCard(name)) => {
+                self.router.navigate(View::CardSearch);
+                self.card_search_view.open_card(ctx, &name);
+            }
+            None => {}
+        }
+        for command in commands {
+            self.run_command(ctx, command);
+        }
+
+        let compared = self
+            .deck_builder_view
+            .take_compared()
+            .into_iter()
+            .chain(self.collection_view.take_compared());
+        for card in compared {
+            self.comparison_view.pin(card);
+        }
+        for action in self.card_search_view.take_actions() {
+            self.handle_action(action);
+        }
+
+        self.deck_builder_view.receive_cards(ctx);
+        self.update_tags();
+
+        let fetched_cards = self.card_search_view.take_fetched_cards();
+        if !fetched_cards.is_empty() {
+            self.prices_view.record(&fetched_cards);
+            self.collection_view.refresh_prices(&fetched_cards);
+        }
+
         egui::CentralPanel::default().show(ctx, |ui| {
             // The central panel the region left after adding TopPanel's and SidePanel's
-            match self.main_panel.as_str() {
-                "card_searcher" => {
-                    self.card_search_view.draw(ui, ctx);
+            match self.router.current() {
+                View::CardSearch => {
+                    self.card_search_view.draw(
+                        ui,
+                        ctx,
+                        &self.symbols,
+                        self.collection_view.collection(),
+                        price_source,
+                    );
+                }
+                View::DeckBuilder => {
+                    self.deck_builder_view.draw(
+                        ui,
+                        ctx,
+                        &mut self.card_search_view,
+                        self.collection_view.collection(),
+                        &self.symbols,
+                        price_source,
+                    );
+                }
+                View::Collection => {
+                    let known_tags = self.card_search_view.known_tags().to_vec();
+                    self.collection_view.draw(
+                        ui,
+                        &self.symbols,
+                        price_source,
+                        &known_tags,
+                        self.prices_view.collection_values(),
+                    );
                 }
-                _ => {
-                    ui.heading("Welcome to eMTG");
+                View::Prices => {
+                    self.prices_view.draw(ui, price_source);
+                }
+                View::Compare => {
+                    if let Some(action) =
+                        self.comparison_view
+                            .draw(ui, ctx, &self.symbols, price_source)
+                    {
+                        self.handle_action(action);
+                    }
+                }
+                View::Storage => {
+                    self.storage_view
+                        .draw(ui, ctx, self.collection_view.collection_mut());
+                }
+                View::Trades => {
+                    self.trade_view
+                        .draw(ui, ctx, self.collection_view.collection(), price_source);
+                }
+                View::Settings => {
+                    self.settings_view.draw(ui, &mut self.palette);
+                }
+                View::Home => {
+                    if let Some(action) = self.dashboard_view.draw(
+                        ui,
+                        self.collection_view.collection(),
+                        &self.deck_builder_view,
+                        &self.prices_view,
+                        &mut self.card_search_view,
+                        price_source,
+                    ) {
+                        self.handle_dashboard_action(ctx, action);
+                    }
                 }
             };
 
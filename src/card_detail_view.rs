@@ -0,0 +1,469 @@
+use crate::card_search_view::CardAction;
+use crate::collection::{Collection, Finish};
+use crate::deck::DeckZone;
+use crate::format_validation::Format;
+use crate::prices::{card_price, PriceSource};
+use crate::rulings::{source_label, RulingsCache};
+use crate::scryfall_models::{Card, CardFace, ClientSettings, Ruling};
+use crate::settings::DataSource;
+use crate::symbol_cache::SymbolCache;
+use egui::{Color32, Image, RichText, TextureHandle};
+
+/// Everything we know about one printing: its faces, set, legalities, prices, related cards,
+/// links to other sites and how many copies we own.
+pub struct SingleCardView {
+    pub card: Option<Card>,
+    pub image_width: f32,
+    add_to_zone: DeckZone,
+    finish: Finish,
+    /// The uri of a related card the user clicked, loaded by the card searcher.
+    open_related: Option<String>,
+    /// Whether the user asked to see the image full screen.
+    open_viewer: bool,
+}
+
+impl Default for SingleCardView {
+    fn default() -> Self {
+        SingleCardView {
+            card: None,
+            image_width: 250.0,
+            add_to_zone: DeckZone::Main,
+            finish: Finish::Nonfoil,
+            open_related: None,
+            open_viewer: false,
+        }
+    }
+}
+
+impl SingleCardView {
+    /// Draw the card details. Returns what the user asked to do with the card, if anything.
+    pub fn draw(
+        &mut self,
+        ui: &mut egui::Ui,
+        image: Option<TextureHandle>,
+        symbols: &SymbolCache,
+        rulings: &mut RulingsCache,
+        collection: &Collection,
+        price_source: PriceSource,
+    ) -> Option<CardAction> {
+        let mut action = None;
+        let Some(card) = &self.card else {
+            return None;
+        };
+        ui.horizontal(|ui| {
+            ui.vertical(|ui| {
+                if let Some(txtr_ref) = &image {
+                    let response = ui
+                        .add(
+                            Image::new(txtr_ref)
+                                .rounding(15.0)
+                                .max_width(self.image_width)
+                                .maintain_aspect_ratio(true)
+                                .fit_to_original_size(1.0)
+                                .bg_fill(egui::Color32::WHITE)
+                                .sense(egui::Sense::click()),
+                        )
+                        .on_hover_text("Click to enlarge")
+                        .on_hover_cursor(egui::CursorIcon::ZoomIn);
+                    if response.clicked() {
+                        self.open_viewer = true;
+                    }
+                }
+                if ui.button("🔍 Enlarge").clicked() {
+                    self.open_viewer = true;
+                }
+                if ui.button("📌 Compare").clicked() {
+                    action = Some(CardAction::Compare(card.clone()));
+                }
+                if ui.button("☆ Wishlist").clicked() {
+                    action = Some(CardAction::AddToWishlist(card.clone()));
+                }
+            });
+            egui::ScrollArea::vertical()
+                .id_salt("card_details")
+                .show(ui, |ui| {
+                    ui.vertical(|ui| {
+                        for (i, face) in faces(card).iter().enumerate() {
+                            if i > 0 {
+                                ui.separator();
+                            }
+                            show_face(ui, face, symbols);
+                        }
+                        ui.separator();
+                        show_printing(ui, card, symbols);
+                        show_owned(ui, card, collection);
+                        show_prices(ui, card, price_source);
+                        show_legalities(ui, card);
+                        if let Some(uri) = show_related(ui, card) {
+                            self.open_related = Some(uri);
+                        }
+                        show_rulings(ui, card, symbols, rulings);
+                        show_links(ui, card);
+                        ui.separator();
+                        ui.horizontal(|ui| {
+                            egui::ComboBox::from_id_salt("add_to_zone")
+                                .selected_text(self.add_to_zone.label())
+                                .show_ui(ui, |ui| {
+                                    for zone in DeckZone::ALL {
+                                        ui.selectable_value(
+                                            &mut self.add_to_zone,
+                                            zone,
+                                            zone.label(),
+                                        );
+                                    }
+                                });
+                            if ui.button("Add to deck").clicked() {
+                                action =
+                                    Some(CardAction::AddToDeck(card.clone(), self.add_to_zone));
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            egui::ComboBox::from_id_salt("add_finish")
+                                .selected_text(self.finish.label())
+                                .show_ui(ui, |ui| {
+                                    for finish in Finish::ALL {
+                                        ui.selectable_value(
+                                            &mut self.finish,
+                                            finish,
+                                            finish.label(),
+                                        );
+                                    }
+                                });
+                            if ui.button("Add to collection").clicked() {
+                                action =
+                                    Some(CardAction::AddToCollection(card.clone(), self.finish));
+                            }
+                        });
+                    });
+                });
+        });
+        action
+    }
+
+    pub fn is_loaded(&self) -> bool {
+        self.card.is_some()
+    }
+
+    pub fn load(&mut self, card: Card) {
+        self.card = Some(card);
+    }
+
+    pub fn clear(&mut self) {
+        self.card = None;
+    }
+
+    /// Whether the user asked to see the image full screen.
+    pub fn take_open_viewer(&mut self) -> bool {
+        std::mem::take(&mut self.open_viewer)
+    }
+
+    /// The related card the user asked to see, if any.
+    pub fn take_open_related(&mut self) -> Option<String> {
+        self.open_related.take()
+    }
+}
+
+/// The faces of a multi-faced card, or the card itself as its only face.
+fn faces(card: &Card) -> Vec<CardFace> {
+    if !card.card_faces.is_empty() {
+        return card.card_faces.clone();
+    }
+    vec![CardFace {
+        name: card.name.clone(),
+        mana_cost: card.mana_cost.clone(),
+        type_line: card.type_line.clone(),
+        oracle_text: card.oracle_text.clone(),
+        flavor_text: card.flavor_text.clone(),
+        power: card.power.clone(),
+        toughness: card.toughness.clone(),
+        loyalty: card.loyalty.clone(),
+        image_uris: card.image_uris.clone(),
+    }]
+}
+
+fn show_face(ui: &mut egui::Ui, face: &CardFace, symbols: &SymbolCache) {
+    ui.horizontal(|ui| {
+        ui.heading(&face.name);
+        if let Some(mana_cost) = face.mana_cost.as_deref().filter(|c| !c.is_empty()) {
+            symbols.mana_cost(ui, mana_cost);
+        }
+    });
+    if let Some(type_line) = &face.type_line {
+        ui.label(type_line);
+    }
+    if let Some(oracle_text) = &face.oracle_text {
+        symbols.rules_text(ui, oracle_text);
+    }
+    if let Some(flavor_text) = &face.flavor_text {
+        ui.label(RichText::new(flavor_text).italics());
+    }
+    if let (Some(power), Some(toughness)) = (&face.power, &face.toughness) {
+        ui.strong(format!("{}/{}", power, toughness));
+    }
+    if let Some(loyalty) = &face.loyalty {
+        ui.strong(format!("Loyalty: {}", loyalty));
+    }
+}
+
+/// Set, rarity, collector number, release date and artist.
+fn show_printing(ui: &mut egui::Ui, card: &Card, symbols: &SymbolCache) {
+    ui.horizontal(|ui| {
+        symbols.set_icon(ui, &card.set, 20.0);
+        ui.label(format!("{} ({})", card.set_name, card.set.to_uppercase()));
+    });
+    ui.label(format!(
+        "{} · #{} · {}",
+        capitalize(&card.rarity),
+        card.collector_number,
+        card.released_at
+    ));
+    if let Some(artist) = &card.artist {
+        ui.label(format!("Illustrated by {}", artist));
+    }
+}
+
+fn show_owned(ui: &mut egui::Ui, card: &Card, collection: &Collection) {
+    let printing: Vec<String> = Finish::ALL
+        .iter()
+        .filter_map(|&finish| {
+            let copies = collection.copies(&card.id, finish);
+            (copies > 0).then(|| format!("{} {}", copies, finish.label().to_lowercase()))
+        })
+        .collect();
+    let any_printing = collection.copies_of_any_printing(card);
+    if any_printing == 0 {
+        ui.label("Not in your collection.");
+    } else if printing.is_empty() {
+        ui.label(format!(
+            "You own {} of other printings of this card.",
+            any_printing
+        ));
+    } else {
+        ui.label(format!(
+            "You own {} of this printing, {} across all printings.",
+            printing.join(", "),
+            any_printing
+        ));
+    }
+}
+
+/// Prices in every currency for the finishes this printing exists in.
+fn show_prices(ui: &mut egui::Ui, card: &Card, price_source: PriceSource) {
+    let finishes: Vec<Finish> = Finish::ALL
+        .into_iter()
+        .filter(|&finish| {
+            PriceSource::ALL
+                .iter()
+                .any(|&source| card_price(card, finish, source).is_some())
+        })
+        .collect();
+    if finishes.is_empty() {
+        return;
+    }
+    ui.strong("Prices");
+    egui::Grid::new("card_prices").striped(true).show(ui, |ui| {
+        ui.label("");
+        for source in PriceSource::ALL {
+            if source == price_source {
+                ui.strong(source.label());
+            } else {
+                ui.label(source.label());
+            }
+        }
+        ui.end_row();
+        for finish in finishes {
+            ui.label(finish.label());
+            for source in PriceSource::ALL {
+                match card_price(card, finish, source) {
+                    Some(price) => ui.label(source.format(price)),
+                    None => ui.label("-"),
+                };
+            }
+            ui.end_row();
+        }
+    });
+}
+
+/// Every format scryfall knows, with the ones we validate decks for first.
+fn show_legalities(ui: &mut egui::Ui, card: &Card) {
+    if card.legalities.is_empty() {
+        return;
+    }
+    let mut formats: Vec<(String, &str)> = Format::ALL
+        .iter()
+        .filter_map(|format| {
+            let status = card.legalities.get(format.scryfall_key())?;
+            Some((format.label().to_string(), status.as_str()))
+        })
+        .collect();
+    let mut others: Vec<(String, &str)> = card
+        .legalities
+        .iter()
+        .filter(|(key, _)| !Format::ALL.iter().any(|f| f.scryfall_key() == key.as_str()))
+        .map(|(key, status)| (capitalize(key), status.as_str()))
+        .collect();
+    others.sort();
+    formats.extend(others);
+
+    egui::CollapsingHeader::new("Legalities")
+        .default_open(true)
+        .show(ui, |ui| {
+            egui::Grid::new("card_legalities")
+                .striped(true)
+                .show(ui, |ui| {
+                    for row in formats.chunks(2) {
+                        for (format, status) in row {
+                            ui.label(format);
+                            ui.colored_label(legality_color(status), legality_label(status));
+                        }
+                        ui.end_row();
+                    }
+                });
+        });
+}
+
+pub fn legality_label(status: &str) -> &str {
+    match status {
+        "legal" => "Legal",
+        "not_legal" => "Not legal",
+        "restricted" => "Restricted",
+        "banned" => "Banned",
+        other => other,
+    }
+}
+
+pub fn legality_color(status: &str) -> Color32 {
+    match status {
+        "legal" => Color32::GREEN,
+        "restricted" => Color32::YELLOW,
+        "banned" => Color32::LIGHT_RED,
+        _ => Color32::GRAY,
+    }
+}
+
+/// Tokens, meld parts and combo pieces. Returns the uri of the one clicked, if any.
+fn show_related(ui: &mut egui::Ui, card: &Card) -> Option<String> {
+    let parts: Vec<_> = card.all_parts.iter().filter(|p| p.id != card.id).collect();
+    if parts.is_empty() {
+        return None;
+    }
+    let mut clicked = None;
+    egui::CollapsingHeader::new("Related cards")
+        .default_open(true)
+        .show(ui, |ui| {
+            for part in parts {
+                ui.horizontal(|ui| {
+                    if ui.link(&part.name).on_hover_text(&part.type_line).clicked() {
+                        clicked = Some(part.uri.clone());
+                    }
+                    ui.weak(capitalize(&part.component));
+                });
+            }
+        });
+    clicked
+}
+
+/// Official rulings and scryfall's notes, with where and when they were published.
+fn show_rulings(ui: &mut egui::Ui, card: &Card, symbols: &SymbolCache, cache: &mut RulingsCache) {
+    let rulings = cache.get(ui.ctx(), card).map(<[Ruling]>::to_vec);
+    let error = cache.error(card).map(str::to_string);
+    let offline = ClientSettings::current().data_source == DataSource::Offline;
+    let title = match &rulings {
+        Some(rulings) => format!("Rulings ({})", rulings.len()),
+        None => "Rulings".to_string(),
+    };
+    egui::CollapsingHeader::new(title)
+        .id_salt("card_rulings")
+        .show(ui, |ui| {
+            match rulings.as_deref() {
+                None => match &error {
+                    Some(error) => {
+                        ui.horizontal(|ui| {
+                            ui.colored_label(Color32::LIGHT_RED, error);
+                            if ui.button("Retry").clicked() {
+                                cache.retry(card);
+                            }
+                        });
+                    }
+                    None => {
+                        ui.spinner();
+                    }
+                },
+                Some([]) => {
+                    ui.label("No rulings.");
+                }
+                Some(rulings) => {
+                    for ruling in rulings {
+                        ui.weak(format!(
+                            "{} · {}",
+                            ruling.published_at,
+                            source_label(&ruling.source)
+                        ));
+                        symbols.rules_text(ui, &ruling.comment);
+                        ui.add_space(4.0);
+                    }
+                }
+            }
+            if cache.bulk_downloading() {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label("Downloading every ruling…");
+                });
+            } else if !cache.bulk_loaded()
+                && !offline
+                && ui
+                    .button("Download every ruling")
+                    .on_hover_text("Keep all rulings on disk to read them offline.")
+                    .clicked()
+            {
+                cache.download_bulk(ui.ctx());
+            }
+        });
+}
+
+/// The card on scryfall, and scryfall's links to other sites and stores.
+fn show_links(ui: &mut egui::Ui, card: &Card) {
+    let mut links: Vec<(&str, &str)> = card
+        .related_uris
+        .iter()
+        .chain(card.purchase_uris.iter())
+        .map(|(key, uri)| (key.as_str(), uri.as_str()))
+        .collect();
+    links.sort();
+    if !card.scryfall_uri.is_empty() {
+        links.insert(0, ("scryfall", card.scryfall_uri.as_str()));
+    }
+    if links.is_empty() {
+        return;
+    }
+    ui.strong("Links");
+    ui.horizontal_wrapped(|ui| {
+        for (key, uri) in links {
+            ui.hyperlink_to(link_label(key), uri);
+        }
+    });
+}
+
+fn link_label(key: &str) -> String {
+    match key {
+        "scryfall" => "Scryfall".to_string(),
+        "gatherer" => "Gatherer".to_string(),
+        "edhrec" => "EDHREC".to_string(),
+        "tcgplayer" => "TCGplayer".to_string(),
+        "tcgplayer_infinite_articles" => "TCGplayer articles".to_string(),
+        "tcgplayer_infinite_decks" => "TCGplayer decks".to_string(),
+        "cardmarket" => "Cardmarket".to_string(),
+        "cardhoarder" => "Cardhoarder".to_string(),
+        other => capitalize(other),
+    }
+}
+
+/// "meld_part" reads better as "Meld part".
+pub fn capitalize(text: &str) -> String {
+    let words = text.replace('_', " ");
+    let mut chars = words.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
@@ -0,0 +1,112 @@
+use crate::local_cache::{cached_at, read_kept, write_cached, CARDS_KIND};
+use crate::scryfall_models::{Card, CardIdentifier, ScryfallApiClient};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+/// A card kept on disk by id, so saved decks only need to store the id. It is read even when
+/// the app always downloads fresh data, as decks are saved without it.
+pub fn cached_card(id: &str) -> Option<Card> {
+    let bytes = read_kept(CARDS_KIND, &format!("{}.json", id))?;
+    serde_json::from_slice(&bytes).ok()
+}
+
+/// Keep a card on disk, unless it already is.
+pub fn cache_card(card: &Card) {
+    let file_name = format!("{}.json", card.id);
+    if cached_at(CARDS_KIND, &file_name).is_some() {
+        return;
+    }
+    match serde_json::to_vec(card) {
+        Ok(json) => write_cached(CARDS_KIND, &file_name, &json),
+        Err(e) => log::warn!("Could not cache {}: {}", card.name, e),
+    }
+}
+
+enum LookupProgress {
+    /// How many identifiers were looked up, and the cards found for them.
+    Found(usize, Vec<Card>),
+    Failed(String),
+}
+
+/// Cards looked up on scryfall by a background thread, a chunk at a time, so long lists do
+/// not freeze the window. Found cards are also kept on disk.
+pub struct CardLookup {
+    total: usize,
+    done: usize,
+    error: Option<String>,
+    progress_rx: Receiver<LookupProgress>,
+}
+
+impl CardLookup {
+    pub fn start(ctx: &egui::Context, identifiers: Vec<CardIdentifier>) -> Self {
+        let (progress_tx, progress_rx) = mpsc::channel();
+        let total = identifiers.len();
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            let client = ScryfallApiClient::new();
+            for chunk_result in client.get_cards_collection_chunks(&identifiers) {
+                let progress = match chunk_result {
+                    Ok(response) => {
+                        response.data.iter().for_each(cache_card);
+                        LookupProgress::Found(
+                            response.data.len() + response.not_found.len(),
+                            response.data,
+                        )
+                    }
+                    Err(e) => LookupProgress::Failed(e.to_string()),
+                };
+                let failed = matches!(progress, LookupProgress::Failed(_));
+                if progress_tx.send(progress).is_err() || failed {
+                    return;
+                }
+                ctx.request_repaint();
+            }
+        });
+        Self {
+            total,
+            done: 0,
+            error: None,
+            progress_rx,
+        }
+    }
+
+    /// The cards found since the last call.
+    pub fn receive(&mut self) -> Vec<Card> {
+        let mut found = vec![];
+        loop {
+            match self.progress_rx.try_recv() {
+                Ok(LookupProgress::Found(looked_up, mut cards)) => {
+                    self.done = (self.done + looked_up).min(self.total);
+                    found.append(&mut cards);
+                }
+                Ok(LookupProgress::Failed(error)) => self.error = Some(error),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    if !self.is_finished() {
+                        self.error = Some("the lookup stopped".to_string());
+                    }
+                    break;
+                }
+            }
+        }
+        found
+    }
+
+    /// Between 0 and 1.
+    pub fn progress(&self) -> f32 {
+        if self.total == 0 {
+            1.0
+        } else {
+            self.done as f32 / self.total as f32
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.done >= self.total || self.error.is_some()
+    }
+
+    /// Why the lookup stopped early.
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+}
@@ -1,280 +1,1045 @@
-use crate::scryfall_models::{Card, ScryfallApiClient};
-use bytes::Bytes;
-use egui::{Image, TextureHandle};
-use egui::{ImageButton, Response, Sense};
+use crate::card_detail_view::SingleCardView;
+use crate::collection::{Collection, Finish};
+use crate::deck::DeckZone;
+use crate::image_viewer::ImageViewer;
+use crate::prices::PriceSource;
+use crate::print_selection::{distinct_values, select_prints, PrintFilter, PrintSort};
+use crate::rulings::RulingsCache;
+use crate::scryfall_models::{Card, ScryfallApiClient, ScryfallError};
+use crate::search_history::{searches_to_text, SearchHistory};
+use crate::search_table::{
+    display_price, visible_rows, ColumnSetting, SearchColumn, SearchTableSettings,
+};
+use crate::settings::Settings;
+use crate::symbol_cache::SymbolCache;
+use crate::tags::{TagIndex, TagQuery};
+use crate::texture_cache::TextureCache;
+use egui::Image;
+use egui::{ImageButton, Response, RichText, Sense, UiBuilder};
 use egui_extras::{Column, TableBuilder};
-use std::sync::mpsc;
-use std::sync::mpsc::{Receiver, Sender};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
 
-pub const CELL_WIDTH: f32 = 250.;
+const GROUP_HEADER_HEIGHT: f32 = 28.0;
+/// Card images are 488x680 pixels.
+const CARD_HEIGHT_RATIO: f32 = 680.0 / 488.0;
+pub const SEARCH_TABLE_KEY: &str = "search_table_columns";
+pub const SEARCH_HISTORY_KEY: &str = "search_history";
+
+/// A request to scryfall made by a background thread, so the window doesn't freeze.
+struct Download<T> {
+    result_rx: Receiver<Result<T, String>>,
+}
+
+impl<T: Send + 'static> Download<T> {
+    fn start(
+        ctx: &egui::Context,
+        request: impl FnOnce(&ScryfallApiClient) -> Result<T, ScryfallError> + Send + 'static,
+    ) -> Self {
+        let (result_tx, result_rx) = mpsc::channel();
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            let result = request(&ScryfallApiClient::new()).map_err(|e| e.to_string());
+            let _ = result_tx.send(result);
+            ctx.request_repaint();
+        });
+        Self { result_rx }
+    }
+
+    /// The result, once the request is done.
+    fn try_take(&self) -> Option<Result<T, String>> {
+        match self.result_rx.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err("the download stopped".to_string())),
+        }
+    }
+}
+
+/// The printings of a card, downloaded in the background as that takes a request per page.
+struct PrintsRequest {
+    name: String,
+    download: Download<Vec<Card>>,
+}
+
+impl PrintsRequest {
+    fn start(ctx: &egui::Context, card: &Card) -> Self {
+        let name = card.name.clone();
+        let card = card.clone();
+        let download = Download::start(ctx, move |client| client.get_card_prints(&card));
+        Self { name, download }
+    }
+}
+
+/// A search running in the background.
+struct SearchRequest {
+    /// Show the first result in detail, for a card opened by its name.
+    open_first: bool,
+    download: Download<Vec<Card>>,
+}
 
 pub struct CardSearchView {
     card_search_spot: String,
+    focus_search: bool,
+    history: SearchHistory,
+    /// The name typed for the search about to be saved.
+    saved_search_name: String,
+    /// The text pasted to import saved searches, while the import window is open.
+    import_searches: Option<String>,
+    /// The `tag:` terms of the last search.
+    tag_query: TagQuery,
+    /// Whether the results still have to be filtered by `tag_query`, which the app does as it
+    /// knows every tag.
+    tags_pending: bool,
+    /// Every tag in the collection and the decks, set by the app.
+    known_tags: Vec<String>,
+    /// Scryfall's code of the language searched for.
+    language: String,
     single_card_view: SingleCardView,
+    /// The id of the selected printing.
     selected_card_in_table: Option<String>,
-    are_cards_loading: bool,
+    result_filter: String,
+    /// The column the results are sorted by, and whether it is ascending.
+    sort: Option<(SearchColumn, bool)>,
+    table_settings: SearchTableSettings,
     card_search_result: Vec<Card>,
-    cards_in_display: u16,
-    client: ScryfallApiClient,
-    card_display: Vec<Card>,
-    rx: Option<Receiver<(Card, Bytes)>>,
-    tx: Option<Sender<(Card, Bytes)>>,
+    search_request: Option<SearchRequest>,
+    /// Every printing of the selected card.
+    prints: Vec<Card>,
+    prints_request: Option<PrintsRequest>,
+    /// Why the printings of the selected card could not be downloaded.
+    prints_error: Option<String>,
+    /// A token, meld part or combo piece of the selected card, being downloaded.
+    related_request: Option<Download<Card>>,
+    textures: TextureCache,
+    viewer: ImageViewer,
+    rulings: RulingsCache,
+    thumbnail_width: f32,
+    print_filter: PrintFilter,
+    print_sort: PrintSort,
+    print_sort_ascending: bool,
+    group_prints: bool,
+    /// What the user asked to do with cards, picked up by the app every frame.
+    actions: Vec<CardAction>,
+    /// Cards freshly downloaded from scryfall, to record their prices.
+    fetched_cards: Vec<Card>,
+}
+
+/// Something to do with a card outside of the card searcher.
+pub enum CardAction {
+    AddToDeck(Card, DeckZone),
+    AddToCollection(Card, Finish),
+    /// Pin the card to compare it with others.
+    Compare(Card),
+    /// Put the card on the wishlist of the current trader.
+    AddToWishlist(Card),
 }
 
 impl Default for CardSearchView {
     fn default() -> Self {
         Self {
             card_search_spot: "angel".to_string(),
+            focus_search: false,
+            history: SearchHistory::default(),
+            saved_search_name: String::new(),
+            import_searches: None,
+            tag_query: TagQuery::default(),
+            tags_pending: false,
+            known_tags: vec![],
+            language: "en".to_string(),
             single_card_view: SingleCardView::default(),
             selected_card_in_table: None,
-            are_cards_loading: false,
+            result_filter: String::new(),
+            sort: None,
+            table_settings: SearchTableSettings::default(),
             card_search_result: vec![],
-            client: ScryfallApiClient::new(),
-            card_display: vec![],
-            cards_in_display: 0,
-            rx: None,
-            tx: None,
+            search_request: None,
+            prints: vec![],
+            prints_request: None,
+            prints_error: None,
+            related_request: None,
+            textures: TextureCache::default(),
+            viewer: ImageViewer::default(),
+            rulings: RulingsCache::default(),
+            thumbnail_width: Settings::default().card_width,
+            print_filter: PrintFilter::default(),
+            print_sort: PrintSort::Default,
+            print_sort_ascending: false,
+            group_prints: false,
+            actions: vec![],
+            fetched_cards: vec![],
         }
     }
 }
 
 impl CardSearchView {
-    pub fn draw(&mut self, ui: &mut egui::Ui, ctx: &egui::Context) {
+    pub fn draw(
+        &mut self,
+        ui: &mut egui::Ui,
+        ctx: &egui::Context,
+        symbols: &SymbolCache,
+        collection: &Collection,
+        price_source: PriceSource,
+    ) {
+        self.receive_prints();
+        self.receive_downloads();
         self.show_search_bar(ui);
         ui.separator();
         ui.with_layout(
             egui::Layout::left_to_right(egui::Align::Min).with_cross_justify(true),
             |ui| {
-                self.show_card_list(ui);
+                self.show_card_list(ui, symbols, price_source);
                 if self.single_card_view.is_loaded() {
-                    self.single_card_view.draw(ui);
+                    let image = self
+                        .single_card_view
+                        .card
+                        .as_ref()
+                        .and_then(|card| self.textures.get(ctx, card));
+                    if let Some(action) = self.single_card_view.draw(
+                        ui,
+                        image,
+                        symbols,
+                        &mut self.rulings,
+                        collection,
+                        price_source,
+                    ) {
+                        self.actions.push(action);
+                    }
+                    if let Some(uri) = self.single_card_view.take_open_related() {
+                        self.open_related(ctx, uri);
+                    }
+                    if self.single_card_view.take_open_viewer() {
+                        self.open_viewer();
+                    }
                 } else {
-                    self.show_card_versions(ui, ctx);
+                    self.show_card_versions(ui, ctx, price_source);
                 }
             },
         );
+        self.viewer.draw(ctx);
     }
 
-    fn img_bytes_to_texture(&self, img_bytes: Bytes, ctx: &egui::Context, id: String) -> TextureHandle {
-        let dyn_image = image::load_from_memory(&img_bytes).unwrap();
-        let size = [dyn_image.width() as usize, dyn_image.height() as usize];
-        let image_buffer = dyn_image.to_rgba8(); // Convert to RGBA8 format.
-        let pixels = image_buffer.into_raw();
-        let egui_cpu_image =
-            egui::ColorImage::from_rgba_unmultiplied(size, &pixels);
-        // This sends the image to the gpu for faster render and extra
-        // memory
-        ctx.load_texture(
-            format!("{}", id),
-            egui_cpu_image,
-            Default::default(),
-        )
+    /// Show the selected card full screen, with its other printings a key press away.
+    fn open_viewer(&mut self) {
+        let Some(card) = &self.single_card_view.card else {
+            return;
+        };
+        let prints = if self.prints.iter().any(|p| p.id == card.id) {
+            self.prints.clone()
+        } else {
+            vec![card.clone()]
+        };
+        self.viewer.open(prints, &card.id);
     }
 
-    fn show_search_bar(&mut self, ui: &mut egui::Ui) {
-        ui.horizontal(|ui| {
-            // let mut line_text = "type card name here";
-            ui.text_edit_singleline(&mut self.card_search_spot);
-            if ui.button("Search").clicked() {
-                let result = self.client.search(self.card_search_spot.to_string());
-                match result {
-                    Ok(info) => {
-                        self.card_search_result = info.data;
-                    }
-                    Err(e) => {
-                        panic!("Error with the search reqwest: {}", e)
+    /// Take the settings that concern the card searcher. `previous` is None on startup, when
+    /// the default query is used too.
+    pub fn apply_settings(&mut self, settings: &Settings, previous: Option<&Settings>) {
+        if previous.is_none() {
+            self.card_search_spot = settings.default_query.clone();
+        }
+        if previous.map_or(true, |p| p.default_sort != settings.default_sort) {
+            self.sort = settings.default_sort;
+        }
+        if previous.map_or(true, |p| p.card_width != settings.card_width) {
+            self.thumbnail_width = settings.card_width;
+            self.single_card_view.image_width = settings.card_width;
+        }
+        self.language = settings.language.clone();
+        self.textures
+            .configure(settings.image_size, settings.images_in_memory);
+        self.viewer.apply_settings(settings);
+    }
+
+    /// Restore the table columns and searches saved by a previous session.
+    pub fn load(&mut self, storage: &dyn eframe::Storage) {
+        if let Some(json) = storage.get_string(SEARCH_HISTORY_KEY) {
+            match serde_json::from_str(&json) {
+                Ok(history) => self.history = history,
+                Err(e) => log::error!("Could not read the search history: {}", e),
+            }
+        }
+        if let Some(json) = storage.get_string(SEARCH_TABLE_KEY) {
+            match serde_json::from_str(&json) {
+                Ok(settings) => {
+                    self.table_settings = settings;
+                    self.table_settings.complete();
+                }
+                Err(e) => log::error!("Could not read the search table columns: {}", e),
+            }
+        }
+    }
+
+    pub fn save(&self, storage: &mut dyn eframe::Storage) {
+        match serde_json::to_string(&self.table_settings) {
+            Ok(json) => storage.set_string(SEARCH_TABLE_KEY, json),
+            Err(e) => log::error!("Could not save the search table columns: {}", e),
+        }
+        match serde_json::to_string(&self.history) {
+            Ok(json) => storage.set_string(SEARCH_HISTORY_KEY, json),
+            Err(e) => log::error!("Could not save the search history: {}", e),
+        }
+    }
+
+    pub fn search_results(&self) -> &[Card] {
+        &self.card_search_result
+    }
+
+    pub fn take_actions(&mut self) -> Vec<CardAction> {
+        std::mem::take(&mut self.actions)
+    }
+
+    fn receive_prints(&mut self) {
+        let Some(request) = &self.prints_request else {
+            return;
+        };
+        match request.download.try_take() {
+            Some(Ok(prints)) => {
+                self.fetched_cards.extend(prints.iter().cloned());
+                self.prints = prints;
+            }
+            Some(Err(e)) => {
+                log::error!("Could not get the printings of {}: {}", request.name, e);
+                self.prints_error = Some(format!(
+                    "Could not get the printings of {}: {}",
+                    request.name, e
+                ));
+            }
+            None => return,
+        }
+        self.prints_request = None;
+    }
+
+    /// Take in the results of a search, and a related card, once they are downloaded.
+    fn receive_downloads(&mut self) {
+        if let Some(request) = &self.search_request {
+            match request.download.try_take() {
+                Some(Ok(cards)) => {
+                    if request.open_first {
+                        if let Some(card) = cards.first() {
+                            self.selected_card_in_table = Some(card.id.clone());
+                            self.single_card_view.load(card.clone());
+                        }
                     }
+                    self.fetched_cards.extend(cards.iter().cloned());
+                    self.card_search_result = cards;
+                    self.search_request = None;
+                }
+                Some(Err(e)) => {
+                    log::error!("Error with the search reqwest: {}", e);
+                    self.search_request = None;
+                }
+                None => {}
+            }
+        }
+        if let Some(request) = &self.related_request {
+            match request.try_take() {
+                Some(Ok(card)) => {
+                    self.fetched_cards.push(card.clone());
+                    self.single_card_view.load(card);
+                    self.related_request = None;
+                }
+                Some(Err(e)) => {
+                    log::error!("Could not get the related card: {}", e);
+                    self.related_request = None;
                 }
+                None => {}
             }
+        }
+    }
+
+    pub fn take_fetched_cards(&mut self) -> Vec<Card> {
+        std::mem::take(&mut self.fetched_cards)
+    }
+
+    /// Show a token, meld part or combo piece of the selected card.
+    fn open_related(&mut self, ctx: &egui::Context, uri: String) {
+        self.related_request = Some(Download::start(ctx, move |client| client.get_card(&uri)));
+    }
+
+    pub fn show_search_bar(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let response = ui.text_edit_singleline(&mut self.card_search_spot);
+            if std::mem::take(&mut self.focus_search) {
+                response.request_focus();
+            }
+            let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            if ui.button("Search").clicked() || submitted {
+                self.search(ui.ctx(), &self.card_search_spot.clone());
+            }
+            if self.search_request.is_some() {
+                ui.spinner();
+            }
+            self.show_history_menu(ui);
+            self.show_saved_menu(ui);
+            self.show_tag_menu(ui);
         });
+        self.show_import_window(ui.ctx());
+        if self.tags_pending {
+            // The app filters the results by tag at the start of the next frame.
+            ui.ctx().request_repaint();
+        }
+    }
+
+    /// The known tags, to add a `tag:` term to the query.
+    fn show_tag_menu(&mut self, ui: &mut egui::Ui) {
+        ui.menu_button("🏷", |ui| {
+            if self.known_tags.is_empty() {
+                ui.weak("Tag cards in the collection or a deck to search by tag.");
+            }
+            for tag in &self.known_tags {
+                if ui.button(tag).clicked() {
+                    self.card_search_spot = format!("{} tag:{}", self.card_search_spot.trim(), tag)
+                        .trim()
+                        .to_string();
+                    ui.close_menu();
+                }
+            }
+        })
+        .response
+        .on_hover_text("Search by tag");
+    }
+
+    pub fn known_tags(&self) -> &[String] {
+        &self.known_tags
+    }
+
+    pub fn set_known_tags(&mut self, tags: Vec<String>) {
+        self.known_tags = tags;
     }
 
-    fn show_card_versions(&mut self, ui: &mut egui::Ui, ctx: &egui::Context) {
-        // Define the desired minimum cell width.
-        // Calculate how many cells we can fit in the available width.
-        let available_width = ui.available_size().x;
-        let mut num_columns = (available_width / CELL_WIDTH).floor() as usize;
-        if num_columns == 0 {
-            num_columns = 1;
+    /// Whether the last search has `tag:` terms the results were not filtered by yet. Not
+    /// until its results are in.
+    pub fn tags_pending(&self) -> bool {
+        self.tags_pending && self.search_request.is_none()
+    }
+
+    /// Keep the results that fit the `tag:` terms of the last search. A search of only tags
+    /// lists the tagged cards instead.
+    pub fn apply_tags(&mut self, index: &TagIndex) {
+        if !self.tags_pending() {
+            return;
+        }
+        self.tags_pending = false;
+        if self.tag_query.query.is_empty() {
+            self.card_search_result = index
+                .cards_matching(&self.tag_query)
+                .into_iter()
+                .cloned()
+                .collect();
+        } else {
+            let query = &self.tag_query;
+            self.card_search_result
+                .retain(|card| query.matches(index.tags_of(&card.name)));
         }
+    }
 
-        // Below here is code for receiving card img bytes from channel and painting them. For the
-        // card info view this needs to be inside a match for a selected card.
-        ui.vertical(|ui| {
-            if let Some(rx) = self.rx.as_ref() {
-                if let Ok((mut card, img_bytes)) = rx.try_recv() {
-                    let texture = self.img_bytes_to_texture(img_bytes, ctx, card.id.clone());
-                    card.image_texture = Some(texture);
-                    self.card_display.push(card);
+    /// The queries searched for before, to search again.
+    fn show_history_menu(&mut self, ui: &mut egui::Ui) {
+        let mut query = None;
+        ui.menu_button("🕘", |ui| {
+            if self.history.recent.is_empty() {
+                ui.weak("No searches yet");
+            }
+            for recent in &self.history.recent {
+                if ui.button(recent).clicked() {
+                    query = Some(recent.clone());
+                    ui.close_menu();
                 }
             }
+            ui.separator();
+            if ui.button("Clear history").clicked() {
+                self.history.recent.clear();
+                ui.close_menu();
+            }
+        })
+        .response
+        .on_hover_text("Recent searches");
+        if let Some(query) = query {
+            self.search(ui.ctx(), &query);
+        }
+    }
+
+    /// The searches saved by name, with ways to save, share and import them.
+    fn show_saved_menu(&mut self, ui: &mut egui::Ui) {
+        let mut query = None;
+        ui.menu_button("⭐", |ui| {
             ui.horizontal(|ui| {
-                if !self.card_display.is_empty() {
-                    ui.heading("Card Versions");
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.saved_search_name)
+                        .hint_text("Name")
+                        .desired_width(180.0),
+                );
+                if ui.button("Save this search").clicked() {
+                    self.history
+                        .save(&self.saved_search_name, &self.card_search_spot);
+                    self.saved_search_name.clear();
                 }
-                let progress =
-                    self.card_display.len() as f32 / self.cards_in_display as f32;
-                if self.are_cards_loading && progress < 1.0 {
-                    ui.add(
-                        egui::ProgressBar::new(progress)
-                            .show_percentage()
-                            .animate(true),
-                    );
+            });
+            ui.separator();
+            if let Some(clicked) = self.show_saved_searches(ui) {
+                query = Some(clicked);
+                ui.close_menu();
+            }
+            ui.separator();
+            ui.horizontal(|ui| {
+                if ui.button("📋 Copy all as text").clicked() {
+                    ui.ctx().copy_text(searches_to_text(&self.history.saved));
+                    ui.close_menu();
                 }
-                if progress == 1.0 {
-                    self.are_cards_loading = false;
-                    self.rx = None;
-                    self.tx = None;
+                if ui.button("Import…").clicked() {
+                    self.import_searches = Some(String::new());
+                    ui.close_menu();
                 }
             });
-            egui::ScrollArea::vertical().show(ui, |ui| {
-                egui::Grid::new("cards_grid")
-                    .num_columns(num_columns)
-                    .spacing([10.0, 10.0])
-                    .show(ui, |ui| {
-                        for (i, card) in self.card_display.iter().enumerate() {
-                            if let Some(img_texture) = &card.image_texture {
-                                let response: Response;
-                                response = ui.add(
-                                    ImageButton::new(
-                                        Image::new(img_texture)
-                                            .rounding(15.0)
-                                            .max_width(CELL_WIDTH)
-                                            .maintain_aspect_ratio(true)
-                                            .fit_to_original_size(1.0)
-                                            .bg_fill(egui::Color32::WHITE),
-                                    )
-                                        .frame(true)
-                                        .sense(Sense::click()),
-                                );
-                                if response.clicked() {
-                                    self.single_card_view.load(card.clone());
-                                }
-                            };
-                            // End the row after filling a row with the computed number of columns.
-                            if (i + 1) % (num_columns) == 0 {
-                                ui.end_row();
+        })
+        .response
+        .on_hover_text("Saved searches");
+        if let Some(query) = query {
+            self.search(ui.ctx(), &query);
+        }
+    }
+
+    /// One line per saved search, with buttons to copy or delete it. Returns the query of the
+    /// one clicked, if any.
+    pub fn show_saved_searches(&mut self, ui: &mut egui::Ui) -> Option<String> {
+        if self.history.saved.is_empty() {
+            ui.weak("No saved searches");
+            return None;
+        }
+        let mut query = None;
+        let mut removed = None;
+        for search in &self.history.saved {
+            ui.horizontal(|ui| {
+                if ui
+                    .button(&search.name)
+                    .on_hover_text(&search.query)
+                    .clicked()
+                {
+                    query = Some(search.query.clone());
+                }
+                if ui
+                    .small_button("📋")
+                    .on_hover_text("Copy as text")
+                    .clicked()
+                {
+                    ui.ctx()
+                        .copy_text(searches_to_text(std::slice::from_ref(search)));
+                }
+                if ui.small_button("🗑").on_hover_text("Delete").clicked() {
+                    removed = Some(search.name.clone());
+                }
+            });
+        }
+        if let Some(name) = removed {
+            self.history.remove_saved(&name);
+        }
+        query
+    }
+
+    fn show_import_window(&mut self, ctx: &egui::Context) {
+        let Some(text) = &mut self.import_searches else {
+            return;
+        };
+        let mut open = true;
+        let mut import = false;
+        egui::Window::new("Import saved searches")
+            .open(&mut open)
+            .show(ctx, |ui| {
+                ui.label("Paste searches copied from eMTG, or one query per line.");
+                ui.add(
+                    egui::TextEdit::multiline(text)
+                        .hint_text("# Blue counterspells under $2\nc:u o:counter usd<2")
+                        .desired_rows(8),
+                );
+                import = ui.button("Import").clicked();
+            });
+        if import {
+            let count = self.history.import(text);
+            log::info!("Imported {} saved searches", count);
+            open = false;
+        }
+        if !open {
+            self.import_searches = None;
+        }
+    }
+
+    /// Search scryfall and list the results, remembering the query. `tag:` terms are left for
+    /// `apply_tags`.
+    pub fn search(&mut self, ctx: &egui::Context, query: &str) {
+        self.history.record(query);
+        let tag_query = TagQuery::parse(query);
+        if tag_query.query.is_empty() && tag_query.has_tags() {
+            self.card_search_result.clear();
+            self.search_request = None;
+        } else {
+            self.fetch_results(ctx, &tag_query.query, false);
+        }
+        self.card_search_spot = query.to_string();
+        self.tags_pending = tag_query.has_tags();
+        self.tag_query = tag_query;
+    }
+
+    /// Start downloading the results of a query. A search still running is dropped.
+    fn fetch_results(&mut self, ctx: &egui::Context, query: &str, open_first: bool) {
+        self.card_search_spot = query.to_string();
+        let query = if self.language == "en" || query.contains("lang:") {
+            query.to_string()
+        } else {
+            format!("{} lang:{}", query, self.language)
+        };
+        let download = Download::start(ctx, move |client| Ok(client.search(&query)?.data));
+        self.search_request = Some(SearchRequest {
+            open_first,
+            download,
+        });
+    }
+
+    /// Show a card by its exact name.
+    pub fn open_card(&mut self, ctx: &egui::Context, name: &str) {
+        self.tags_pending = false;
+        self.fetch_results(ctx, &format!("!\"{}\"", name), true);
+    }
+
+    /// Put the cursor in the search bar the next time it is drawn.
+    pub fn focus_search(&mut self) {
+        self.focus_search = true;
+    }
+
+    /// The card shown in detail, if any.
+    pub fn selected_card(&self) -> Option<&Card> {
+        self.single_card_view.card.as_ref()
+    }
+
+    /// Every printing of the selected card as a grid of images. Only the visible rows are laid
+    /// out, and their images downloaded.
+    fn show_card_versions(
+        &mut self,
+        ui: &mut egui::Ui,
+        ctx: &egui::Context,
+        price_source: PriceSource,
+    ) {
+        if let Some(request) = &self.prints_request {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label(format!("Loading the printings of {}", request.name));
+            });
+            return;
+        }
+        if let Some(error) = &self.prints_error {
+            ui.colored_label(egui::Color32::LIGHT_RED, error);
+            return;
+        }
+        if self.prints.is_empty() {
+            return;
+        }
+        ui.vertical(|ui| {
+            self.show_print_controls(ui);
+            let groups = select_prints(
+                &self.prints,
+                &self.print_filter,
+                self.print_sort,
+                self.print_sort_ascending,
+                self.group_prints,
+                price_source,
+            );
+            let shown: usize = groups.iter().map(|(_, group)| group.len()).sum();
+            ui.label(format!("{} of {} printings", shown, self.prints.len()));
+
+            let cell_size = egui::vec2(
+                self.thumbnail_width,
+                self.thumbnail_width * CARD_HEIGHT_RATIO,
+            );
+            let spacing = ui.spacing().item_spacing;
+            let num_columns =
+                ((ui.available_width() + spacing.x) / (cell_size.x + spacing.x)).max(1.0) as usize;
+            let mut rows: Vec<GridRow<'_>> = vec![];
+            for (name, group) in &groups {
+                if self.group_prints {
+                    rows.push(GridRow::Header(name, group.len()));
+                }
+                rows.extend(group.chunks(num_columns).map(GridRow::Cards));
+            }
+            let heights: Vec<f32> = rows
+                .iter()
+                .map(|row| match row {
+                    GridRow::Header(..) => GROUP_HEADER_HEIGHT,
+                    GridRow::Cards(_) => cell_size.y,
+                })
+                .collect();
+
+            egui::ScrollArea::vertical()
+                .id_salt("card_versions")
+                .show_viewport(ui, |ui, viewport| {
+                    let total_height: f32 = heights.iter().map(|h| h + spacing.y).sum();
+                    ui.set_height((total_height - spacing.y).max(0.0));
+                    let mut first = rows.len();
+                    let mut first_top = 0.0;
+                    for (i, height) in heights.iter().enumerate() {
+                        if first_top + height >= viewport.min.y {
+                            first = i;
+                            break;
+                        }
+                        first_top += height + spacing.y;
+                    }
+                    let top = ui.max_rect().top();
+                    let rect = egui::Rect::from_x_y_ranges(
+                        ui.max_rect().x_range(),
+                        top + first_top..=top + viewport.max.y.max(first_top),
+                    );
+                    ui.allocate_new_ui(UiBuilder::new().max_rect(rect), |ui| {
+                        ui.skip_ahead_auto_ids(first);
+                        let mut row_top = first_top;
+                        for (row, &height) in rows.iter().zip(&heights).skip(first) {
+                            if row_top > viewport.max.y {
+                                break;
                             }
+                            let size = egui::vec2(ui.available_width(), height);
+                            let (row_rect, _) = ui.allocate_exact_size(size, Sense::hover());
+                            ui.allocate_new_ui(
+                                UiBuilder::new().max_rect(row_rect),
+                                |ui| match row {
+                                    GridRow::Header(name, count) => {
+                                        ui.heading(format!("{} ({})", name, count));
+                                    }
+                                    GridRow::Cards(indices) => {
+                                        self.show_print_row(ui, ctx, indices, cell_size)
+                                    }
+                                },
+                            );
+                            row_top += height + spacing.y;
                         }
-                        ui.end_row();
-                    })
-            });
+                    });
+                });
+        });
+    }
+
+    fn show_print_row(
+        &mut self,
+        ui: &mut egui::Ui,
+        ctx: &egui::Context,
+        indices: &[usize],
+        cell_size: egui::Vec2,
+    ) {
+        ui.horizontal(|ui| {
+            for &i in indices {
+                let card = &self.prints[i];
+                let response = match self.textures.get(ctx, card) {
+                    Some(texture) => ui.add(
+                        ImageButton::new(
+                            Image::new(&texture)
+                                .rounding(15.0)
+                                .fit_to_exact_size(cell_size),
+                        )
+                        .frame(false),
+                    ),
+                    None => card_placeholder(ui, card, cell_size),
+                };
+                let response = response.on_hover_text(format!(
+                    "{} #{}, {}",
+                    card.set_name, card.collector_number, card.released_at
+                ));
+                if response.clicked() {
+                    self.single_card_view.load(card.clone());
+                }
+            }
+        });
+    }
+
+    /// Sorting, filtering and grouping of the printings.
+    fn show_print_controls(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal_wrapped(|ui| {
+            ui.heading("Card Versions");
+            ui.label("Sort by");
+            egui::ComboBox::from_id_salt("print_sort")
+                .selected_text(self.print_sort.label())
+                .show_ui(ui, |ui| {
+                    for sort in PrintSort::ALL {
+                        ui.selectable_value(&mut self.print_sort, sort, sort.label());
+                    }
+                });
+            let direction = if self.print_sort_ascending {
+                "⏶"
+            } else {
+                "⏷"
+            };
+            if ui.button(direction).clicked() {
+                self.print_sort_ascending = !self.print_sort_ascending;
+            }
+            ui.checkbox(&mut self.group_prints, "Group by set type");
+            ui.add(egui::Slider::new(&mut self.thumbnail_width, 100.0..=400.0).text("Size"));
+        });
+        ui.horizontal_wrapped(|ui| {
+            let filter = &mut self.print_filter;
+            egui::ComboBox::from_id_salt("print_finish")
+                .selected_text(filter.finish.map_or("Any finish", |f| f.label()))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut filter.finish, None, "Any finish");
+                    for finish in Finish::ALL {
+                        ui.selectable_value(&mut filter.finish, Some(finish), finish.label());
+                    }
+                });
+            let frames = distinct_values(&self.prints, |c| &c.frame);
+            value_filter(ui, "print_frame", "Any frame", &mut filter.frame, &frames);
+            let borders = distinct_values(&self.prints, |c| &c.border_color);
+            value_filter(
+                ui,
+                "print_border",
+                "Any border",
+                &mut filter.border_color,
+                &borders,
+            );
+            let languages = distinct_values(&self.prints, |c| &c.lang);
+            value_filter(
+                ui,
+                "print_language",
+                "Any language",
+                &mut filter.language,
+                &languages,
+            );
+            flag_filter(
+                ui,
+                "print_promo",
+                &mut filter.promo,
+                ["Promos or not", "Promos only", "No promos"],
+            );
+            flag_filter(
+                ui,
+                "print_digital",
+                &mut filter.digital,
+                ["Paper and digital", "Digital only", "Paper only"],
+            );
+            if ui.button("Clear filters").clicked() {
+                *filter = PrintFilter::default();
+            }
         });
     }
-    fn show_card_list(&mut self, ui: &mut egui::Ui) {
+
+    fn show_card_list(
+        &mut self,
+        ui: &mut egui::Ui,
+        symbols: &SymbolCache,
+        price_source: PriceSource,
+    ) {
         if self.card_search_result.is_empty() {
             return;
         }
+        let ctx = ui.ctx().clone();
         ui.vertical(|ui| {
-            TableBuilder::new(ui)
+            ui.horizontal(|ui| {
+                ui.label("Filter results:");
+                ui.text_edit_singleline(&mut self.result_filter);
+                ui.menu_button("Columns", |ui| self.show_column_settings(ui));
+            });
+            let rows = visible_rows(
+                &self.card_search_result,
+                &self.result_filter,
+                self.sort,
+                price_source,
+            );
+            let columns: Vec<ColumnSetting> =
+                self.table_settings.visible_columns().copied().collect();
+            // Reordering columns starts a new table state, seeded with the saved widths.
+            let order: Vec<SearchColumn> = columns.iter().map(|c| c.column).collect();
+            let mut table = TableBuilder::new(ui)
+                .id_salt(("search_results", &order))
                 .striped(true)
+                .resizable(true)
                 .cell_layout(egui::Layout::left_to_right(egui::Align::Max))
-                .columns(Column::auto(), 3)
-                .sense(Sense::click())
+                .sense(Sense::click());
+            for column in &columns {
+                table = table.column(Column::initial(column.width).at_least(30.0).clip(true));
+            }
+            let mut widths = vec![];
+            table
                 .header(18.0, |mut header| {
-                    header.col(|ui| {
-                        ui.strong("Name");
-                    });
-                    header.col(|ui| {
-                        ui.strong("Type");
-                    });
-                    header.col(|ui| {
-                        ui.strong("Set");
-                    });
-                })
-                .body(|mut body| {
-                    for card in &self.card_search_result {
-                        body.row(20.0, |mut row| {
-                            row.col(|ui| {
-                                ui.label(&card.name);
-                            });
-                            row.col(|ui| {
-                                ui.label(card.type_line.as_deref().unwrap_or("Unknown"));
-                            });
-                            row.col(|ui| {
-                                ui.label(&card.set.to_ascii_uppercase());
-                            });
-                            match &self.selected_card_in_table {
-                                Some(selected_card) => {
-                                    if *selected_card == card.name {
-                                        row.set_selected(true)
-                                    }
+                    for setting in &columns {
+                        let column = setting.column;
+                        let (rect, _) = header.col(|ui| {
+                            let text = match self.sort {
+                                Some((sorted, true)) if sorted == column => {
+                                    format!("{} ⏶", column.label())
                                 }
-                                _ => {
-                                    row.set_selected(false);
+                                Some((sorted, false)) if sorted == column => {
+                                    format!("{} ⏷", column.label())
                                 }
-                            }
-                            if row.response().clicked() {
-                                let (tx, rx) = mpsc::channel();
-                                self.tx = Some(tx);
-                                self.rx = Some(rx);
-                                self.card_display.clear();
-                                self.cards_in_display = self
-                                    .client
-                                    .get_card_versions(self.tx.clone().unwrap(), card)
-                                    .expect("Error getting card versions")
-                                    as u16;
-                                self.are_cards_loading = true;
-                                self.selected_card_in_table = Some(card.name.clone());
-                                self.single_card_view.clear();
+                                _ => column.label().to_string(),
+                            };
+                            let sorted = self.sort.is_some_and(|(sorted, _)| sorted == column);
+                            if ui
+                                .selectable_label(sorted, RichText::new(text).strong())
+                                .clicked()
+                            {
+                                self.sort = match self.sort {
+                                    Some((sorted, ascending)) if sorted == column => {
+                                        Some((column, !ascending))
+                                    }
+                                    _ => Some((column, true)),
+                                };
                             }
                         });
+                        widths.push((column, rect.width()));
                     }
+                })
+                .body(|body| {
+                    body.rows(20.0, rows.len(), |mut row| {
+                        let card = &self.card_search_result[rows[row.index()]];
+                        for setting in &columns {
+                            row.col(|ui| {
+                                show_cell(ui, card, setting.column, symbols, price_source)
+                            });
+                        }
+                        row.set_selected(self.selected_card_in_table.as_ref() == Some(&card.id));
+                        if row.response().clicked() {
+                            // A request still running for another card is dropped.
+                            self.prints_request = Some(PrintsRequest::start(&ctx, card));
+                            self.prints_error = None;
+                            self.prints.clear();
+                            self.selected_card_in_table = Some(card.id.clone());
+                            self.single_card_view.clear();
+                        }
+                    });
                 });
+            for (column, width) in widths {
+                if let Some(setting) = self
+                    .table_settings
+                    .columns
+                    .iter_mut()
+                    .find(|c| c.column == column)
+                {
+                    setting.width = width;
+                }
+            }
         });
     }
+
+    /// Show, hide and reorder the columns of the results table.
+    fn show_column_settings(&mut self, ui: &mut egui::Ui) {
+        let mut move_up = None;
+        for (i, setting) in self.table_settings.columns.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(i > 0, egui::Button::new("⏶").small())
+                    .clicked()
+                {
+                    move_up = Some(i);
+                }
+                ui.checkbox(&mut setting.visible, setting.column.label());
+            });
+        }
+        if let Some(i) = move_up {
+            self.table_settings.move_up(i);
+        }
+        if ui.button("Reset").clicked() {
+            self.table_settings = SearchTableSettings::default();
+        }
+    }
 }
 
-struct SingleCardView {
-    card: Option<Card>,
+/// A line of the card versions grid.
+enum GridRow<'a> {
+    /// A set type and how many printings it has.
+    Header(&'a str, usize),
+    /// Indices into the printings.
+    Cards(&'a [usize]),
 }
 
-impl Default for SingleCardView {
-    fn default() -> Self {
-        SingleCardView { card: None }
-    }
+/// A combo box to keep only the printings with one value of a field.
+fn value_filter(
+    ui: &mut egui::Ui,
+    id: &str,
+    any: &str,
+    selected: &mut Option<String>,
+    values: &[String],
+) {
+    egui::ComboBox::from_id_salt(id)
+        .selected_text(selected.as_deref().unwrap_or(any))
+        .show_ui(ui, |ui| {
+            ui.selectable_value(selected, None, any);
+            for value in values {
+                ui.selectable_value(selected, Some(value.clone()), value);
+            }
+        });
 }
 
-impl SingleCardView {
-    pub fn draw(&mut self, ui: &mut egui::Ui) {
-        if let Some(card) = &self.card {
-            ui.horizontal(|ui| {
-                ui.vertical(|ui| {
-                    if let Some(txtr_ref) = &card.image_texture {
-                        ui.add(Image::new(txtr_ref)
-                            .rounding(15.0)
-                            .max_width(CELL_WIDTH)
-                            .maintain_aspect_ratio(true)
-                            .fit_to_original_size(1.0)
-                            .bg_fill(egui::Color32::WHITE));
-                    }
-                });
-                ui.vertical(|ui| {
-                    ui.heading("Name:".to_string());
-                    ui.label(&card.name);
-                    if let Some(card_type) = &card.type_line {
-                        ui.heading("Type:".to_string());
-                        ui.label(card_type);
-                    }
-                    if let Some(oracle_text) = &card.oracle_text {
-                        ui.heading("Oracle Text:".to_string());
-                        ui.label(oracle_text);
-                    }
-                });
-            });
+/// A combo box for a yes/no property, with the labels for either, yes and no.
+fn flag_filter(ui: &mut egui::Ui, id: &str, selected: &mut Option<bool>, labels: [&str; 3]) {
+    let text = match selected {
+        None => labels[0],
+        Some(true) => labels[1],
+        Some(false) => labels[2],
+    };
+    egui::ComboBox::from_id_salt(id)
+        .selected_text(text)
+        .show_ui(ui, |ui| {
+            ui.selectable_value(selected, None, labels[0]);
+            ui.selectable_value(selected, Some(true), labels[1]);
+            ui.selectable_value(selected, Some(false), labels[2]);
+        });
+}
 
+/// Stands in for a card while its image downloads.
+fn card_placeholder(ui: &mut egui::Ui, card: &Card, size: egui::Vec2) -> Response {
+    let (rect, response) = ui.allocate_exact_size(size, Sense::click());
+    let visuals = ui.visuals();
+    ui.painter()
+        .rect_filled(rect, 15.0, visuals.widgets.inactive.bg_fill);
+    ui.painter().text(
+        rect.center(),
+        egui::Align2::CENTER_CENTER,
+        format!("{}\n{}", card.name, card.set.to_uppercase()),
+        egui::FontId::proportional(14.0),
+        visuals.text_color(),
+    );
+    response
+}
+
+fn show_cell(
+    ui: &mut egui::Ui,
+    card: &Card,
+    column: SearchColumn,
+    symbols: &SymbolCache,
+    price_source: PriceSource,
+) {
+    match column {
+        SearchColumn::Name => {
+            ui.label(&card.name);
+        }
+        SearchColumn::ManaCost => {
+            if let Some(cost) = card.mana_cost.as_deref() {
+                symbols.mana_cost(ui, cost);
+            }
+        }
+        SearchColumn::Type => {
+            ui.label(card.type_line.as_deref().unwrap_or("Unknown"));
+        }
+        SearchColumn::Set => {
+            ui.label(card.set.to_ascii_uppercase());
+        }
+        SearchColumn::CollectorNumber => {
+            ui.label(&card.collector_number);
+        }
+        SearchColumn::Rarity => {
+            ui.label(&card.rarity);
+        }
+        SearchColumn::Colors => {
+            let colors: String = card
+                .colors
+                .iter()
+                .flatten()
+                .map(|c| format!("{{{}}}", c))
+                .collect();
+            if colors.is_empty() {
+                ui.label("Colorless");
+            } else {
+                symbols.mana_cost(ui, &colors);
+            }
+        }
+        SearchColumn::Price => {
+            match display_price(card, price_source) {
+                Some(price) => ui.label(price_source.format(price)),
+                None => ui.label("-"),
+            };
         }
-    }
-    
-    pub fn is_loaded(&self) -> bool {
-        self.card.is_some()
-    }
-    
-    pub fn load(&mut self, card: Card) {
-        self.card = Some(card);
-    }
-    
-    pub fn clear(&mut self) {
-        self.card = None;
     }
 }
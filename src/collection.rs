@@ -0,0 +1,124 @@
+use crate::locations::{Containers, Location};
+use crate::prices::now;
+use crate::scryfall_models::Card;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum Finish {
+    #[default]
+    Nonfoil,
+    Foil,
+    Etched,
+}
+
+impl Finish {
+    pub const ALL: [Finish; 3] = [Finish::Nonfoil, Finish::Foil, Finish::Etched];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Finish::Nonfoil => "Nonfoil",
+            Finish::Foil => "Foil",
+            Finish::Etched => "Etched",
+        }
+    }
+}
+
+/// Copies of one printing, in one finish, that we own.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CollectionEntry {
+    pub card: Card,
+    pub quantity: u32,
+    #[serde(default)]
+    pub finish: Finish,
+    /// When copies were last added, as a unix timestamp.
+    #[serde(default)]
+    pub added: u64,
+    /// The user's tags, e.g. "ramp" or "trade".
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Where the copies are kept, if recorded.
+    #[serde(default)]
+    pub location: Option<Location>,
+    /// How many of the copies are up for trade.
+    #[serde(default)]
+    pub tradeable: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct Collection {
+    pub entries: Vec<CollectionEntry>,
+    /// The binders and boxes the cards are kept in.
+    #[serde(default)]
+    pub containers: Containers,
+}
+
+impl Collection {
+    /// Add copies of a printing, merging them with the copies we already have.
+    pub fn add(&mut self, card: Card, quantity: u32, finish: Finish) {
+        match self
+            .entries
+            .iter_mut()
+            .find(|e| e.card.id == card.id && e.finish == finish)
+        {
+            Some(entry) => {
+                entry.quantity += quantity;
+                entry.added = now();
+            }
+            None => self.entries.push(CollectionEntry {
+                card,
+                quantity,
+                finish,
+                added: now(),
+                tags: vec![],
+                location: None,
+                tradeable: 0,
+            }),
+        }
+    }
+
+    /// The entries copies were added to most recently, latest first.
+    pub fn recently_added(&self, count: usize) -> Vec<&CollectionEntry> {
+        let mut entries: Vec<&CollectionEntry> =
+            self.entries.iter().filter(|e| e.added > 0).collect();
+        entries.sort_by_key(|e| std::cmp::Reverse(e.added));
+        entries.truncate(count);
+        entries
+    }
+
+    /// Take the prices of a freshly downloaded card for every copy of its printing.
+    pub fn refresh_prices(&mut self, card: &Card) {
+        for entry in self.entries.iter_mut().filter(|e| e.card.id == card.id) {
+            entry.card.prices = card.prices.clone();
+        }
+    }
+
+    pub fn total_cards(&self) -> u32 {
+        self.entries.iter().map(|e| e.quantity).sum()
+    }
+
+    /// How many copies of a printing we own in a finish.
+    pub fn copies(&self, card_id: &str, finish: Finish) -> u32 {
+        self.entries
+            .iter()
+            .filter(|e| e.card.id == card_id && e.finish == finish)
+            .map(|e| e.quantity)
+            .sum()
+    }
+
+    /// How many copies of a card we own across all its printings.
+    pub fn copies_of_any_printing(&self, card: &Card) -> u32 {
+        self.entries
+            .iter()
+            .filter(|e| match (&e.card.oracle_id, &card.oracle_id) {
+                (Some(a), Some(b)) => a == b,
+                _ => e.card.name == card.name,
+            })
+            .map(|e| e.quantity)
+            .sum()
+    }
+
+    /// Every card we own, one per entry, to resolve decklists without going online.
+    pub fn known_cards(&self) -> impl Iterator<Item = &Card> {
+        self.entries.iter().map(|e| &e.card)
+    }
+}
@@ -0,0 +1,382 @@
+use crate::collection::{Collection, Finish};
+use crate::deck::{Deck, DeckEntry, DeckZone};
+use crate::scryfall_models::Card;
+
+/// How strictly owned cards have to match the cards of a deck.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MatchLevel {
+    /// Any printing of the card counts.
+    Oracle,
+    /// Only the printing (and finish) the deck asks for counts. Entries without a printing
+    /// accept any of them.
+    Printing,
+}
+
+impl MatchLevel {
+    pub fn label(&self) -> &'static str {
+        match self {
+            MatchLevel::Oracle => "Any printing",
+            MatchLevel::Printing => "Exact printing",
+        }
+    }
+}
+
+/// What identifies a deck entry at a given match level.
+#[derive(Clone, Debug)]
+struct SlotKey {
+    name: String,
+    oracle_id: Option<String>,
+    /// Only set at printing level, and only when the entry names a printing.
+    set: Option<String>,
+    collector_number: Option<String>,
+    foil: bool,
+}
+
+impl SlotKey {
+    fn new(entry: &DeckEntry, level: MatchLevel) -> Self {
+        let exact = level == MatchLevel::Printing;
+        Self {
+            name: entry.name.clone(),
+            oracle_id: entry.card.as_ref().and_then(|c| c.oracle_id.clone()),
+            set: entry
+                .set
+                .as_ref()
+                .filter(|_| exact)
+                .map(|s| s.to_lowercase()),
+            collector_number: entry.collector_number.clone().filter(|_| exact),
+            foil: exact && entry.foil,
+        }
+    }
+
+    fn same_oracle(&self, name: &str, oracle_id: Option<&str>) -> bool {
+        match (self.oracle_id.as_deref(), oracle_id) {
+            (Some(a), Some(b)) => a == b,
+            _ => self.name.eq_ignore_ascii_case(name),
+        }
+    }
+
+    fn matches_owned(&self, card: &Card, finish: Finish) -> bool {
+        self.same_oracle(&card.name, card.oracle_id.as_deref())
+            && (!self.foil || finish == Finish::Foil)
+            && self
+                .set
+                .as_ref()
+                .map_or(true, |s| card.set.eq_ignore_ascii_case(s))
+            && self
+                .collector_number
+                .as_ref()
+                .map_or(true, |n| card.collector_number == *n)
+    }
+
+    /// Whether only some printings or finishes of the card match.
+    fn is_exact(&self) -> bool {
+        self.set.is_some() || self.collector_number.is_some() || self.foil
+    }
+
+    /// Whether two entries of the same deck are one line of the diff.
+    fn same_slot(&self, other: &SlotKey) -> bool {
+        self.same_oracle(&other.name, other.oracle_id.as_deref())
+            && self.set == other.set
+            && self.collector_number == other.collector_number
+            && self.foil == other.foil
+    }
+
+    /// Whether an entry of another deck uses copies this entry could use.
+    fn matches_slot(&self, other: &SlotKey) -> bool {
+        let compatible = |a: &Option<String>, b: &Option<String>| match (a, b) {
+            (Some(a), Some(b)) => a == b,
+            _ => true,
+        };
+        self.same_oracle(&other.name, other.oracle_id.as_deref())
+            && compatible(&self.set, &other.set)
+            && compatible(&self.collector_number, &other.collector_number)
+    }
+}
+
+/// How one card of the deck compares with the collection.
+#[derive(Clone)]
+pub struct DiffLine {
+    /// The first entry of the deck for this card, used to export the missing copies.
+    pub entry: DeckEntry,
+    pub needed: u32,
+    pub owned: u32,
+    pub missing: u32,
+    /// Other decks that use the same card, with how many copies they use.
+    pub committed: Vec<(String, u32)>,
+}
+
+impl DiffLine {
+    pub fn committed_count(&self) -> u32 {
+        self.committed.iter().map(|(_, quantity)| quantity).sum()
+    }
+
+    /// We own enough copies, but not once the other decks keep theirs.
+    pub fn short_because_committed(&self) -> bool {
+        self.missing == 0 && self.owned.saturating_sub(self.committed_count()) < self.needed
+    }
+}
+
+/// Compare a deck with the collection, card by card. Maybeboard entries are not needed to
+/// play the deck and are left out.
+pub fn deck_diff(
+    deck: &Deck,
+    collection: &Collection,
+    other_decks: &[&Deck],
+    level: MatchLevel,
+) -> Vec<DiffLine> {
+    let mut lines: Vec<(SlotKey, DiffLine)> = vec![];
+    for entry in &deck.entries {
+        if entry.zone == DeckZone::Maybeboard {
+            continue;
+        }
+        let key = SlotKey::new(entry, level);
+        match lines.iter_mut().find(|(k, _)| k.same_slot(&key)) {
+            Some((_, line)) => line.needed += entry.quantity,
+            None => lines.push((
+                key,
+                DiffLine {
+                    entry: entry.clone(),
+                    needed: entry.quantity,
+                    owned: 0,
+                    missing: 0,
+                    committed: vec![],
+                },
+            )),
+        }
+    }
+
+    // Copies of the collection not yet counted for a line, so two lines of the same card never
+    // count the same copy twice. Lines asking for a printing pick first, as lines that take
+    // any printing can make do with the other copies.
+    let mut remaining: Vec<u32> = collection.entries.iter().map(|e| e.quantity).collect();
+    let mut order: Vec<usize> = (0..lines.len()).collect();
+    order.sort_by_key(|&i| !lines[i].0.is_exact());
+    for i in order {
+        let (key, line) = &mut lines[i];
+        for (i, owned) in collection.entries.iter().enumerate() {
+            if line.owned == line.needed {
+                break;
+            }
+            if remaining[i] > 0 && key.matches_owned(&owned.card, owned.finish) {
+                let taken = remaining[i].min(line.needed - line.owned);
+                remaining[i] -= taken;
+                line.owned += taken;
+            }
+        }
+        line.missing = line.needed - line.owned;
+    }
+
+    for (key, line) in &mut lines {
+        for other in other_decks {
+            let used: u32 = other
+                .entries
+                .iter()
+                .filter(|e| e.zone != DeckZone::Maybeboard)
+                .filter(|e| key.matches_slot(&SlotKey::new(e, level)))
+                .map(|e| e.quantity)
+                .sum();
+            if used > 0 {
+                line.committed.push((other.name.clone(), used));
+            }
+        }
+    }
+    lines.into_iter().map(|(_, line)| line).collect()
+}
+
+/// The missing cards as "quantity name" lines, the format most card shops accept for mass
+/// entry.
+pub fn buy_list(lines: &[DiffLine]) -> String {
+    let mut missing: Vec<(&str, u32)> = vec![];
+    for line in lines.iter().filter(|line| line.missing > 0) {
+        let name = line.entry.name.as_str();
+        match missing
+            .iter_mut()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        {
+            Some((_, quantity)) => *quantity += line.missing,
+            None => missing.push((name, line.missing)),
+        }
+    }
+    missing
+        .iter()
+        .map(|(name, quantity)| format!("{} {}", quantity, name))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The missing cards as a deck, keeping the zone and printing of each card.
+pub fn missing_deck(deck_name: &str, lines: &[DiffLine]) -> Deck {
+    let mut deck = Deck::new(format!("{} (missing)", deck_name));
+    for line in lines.iter().filter(|line| line.missing > 0) {
+        let mut entry = line.entry.clone();
+        entry.quantity = line.missing;
+        deck.add_entry(entry);
+    }
+    deck
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn printing(name: &str, set: &str, number: &str) -> Card {
+        Card::test(
+            &format!("{} {}", name, set),
+            json!({ "name": name, "oracle_id": name, "set": set, "collector_number": number }),
+        )
+    }
+
+    fn entry(card: Card, quantity: u32, zone: DeckZone) -> DeckEntry {
+        DeckEntry::from_card(card, quantity, zone)
+    }
+
+    /// An entry that accepts any printing of the card.
+    fn any_printing(name: &str, quantity: u32) -> DeckEntry {
+        let mut entry = DeckEntry::new(name.to_string(), quantity, DeckZone::Main);
+        entry.card = Some(Card::test(name, json!({ "oracle_id": name })));
+        entry
+    }
+
+    fn collection(cards: &[(Card, u32, Finish)]) -> Collection {
+        let mut collection = Collection::default();
+        for (card, quantity, finish) in cards {
+            collection.add(card.clone(), *quantity, *finish);
+        }
+        collection
+    }
+
+    fn counts(lines: &[DiffLine]) -> Vec<(&str, u32, u32, u32)> {
+        lines
+            .iter()
+            .map(|l| (l.entry.name.as_str(), l.needed, l.owned, l.missing))
+            .collect()
+    }
+
+    #[test]
+    fn match_levels() {
+        let mut deck = Deck::new("Burn".to_string());
+        deck.entries = vec![
+            entry(printing("Lightning Bolt", "m10", "146"), 4, DeckZone::Main),
+            entry(
+                printing("Lightning Bolt", "m10", "146"),
+                2,
+                DeckZone::Sideboard,
+            ),
+            entry(printing("Mountain", "m10", "242"), 20, DeckZone::Maybeboard),
+        ];
+        let owned = collection(&[
+            (printing("Lightning Bolt", "m10", "146"), 1, Finish::Nonfoil),
+            (printing("Lightning Bolt", "2xm", "129"), 3, Finish::Nonfoil),
+        ]);
+
+        // Both zones are one line, and the maybeboard is left out.
+        let lines = deck_diff(&deck, &owned, &[], MatchLevel::Oracle);
+        assert_eq!(counts(&lines), [("Lightning Bolt", 6, 4, 2)]);
+
+        let lines = deck_diff(&deck, &owned, &[], MatchLevel::Printing);
+        assert_eq!(counts(&lines), [("Lightning Bolt", 6, 1, 5)]);
+
+        // A foil entry only takes foil copies.
+        deck.entries[0].foil = true;
+        let lines = deck_diff(&deck, &owned, &[], MatchLevel::Printing);
+        assert_eq!(
+            counts(&lines),
+            [("Lightning Bolt", 4, 0, 4), ("Lightning Bolt", 2, 1, 1)]
+        );
+    }
+
+    #[test]
+    fn exact_printings_pick_their_copies_first() {
+        let mut deck = Deck::new("Burn".to_string());
+        deck.entries = vec![
+            any_printing("Lightning Bolt", 2),
+            entry(
+                printing("Lightning Bolt", "m10", "146"),
+                2,
+                DeckZone::Sideboard,
+            ),
+        ];
+        let owned = collection(&[
+            (printing("Lightning Bolt", "m10", "146"), 2, Finish::Nonfoil),
+            (printing("Lightning Bolt", "2xm", "129"), 2, Finish::Nonfoil),
+        ]);
+        let lines = deck_diff(&deck, &owned, &[], MatchLevel::Printing);
+        assert_eq!(
+            counts(&lines),
+            [("Lightning Bolt", 2, 2, 0), ("Lightning Bolt", 2, 2, 0)]
+        );
+    }
+
+    #[test]
+    fn copies_committed_to_other_decks() {
+        let mut deck = Deck::new("Burn".to_string());
+        deck.entries = vec![any_printing("Lightning Bolt", 4)];
+        let mut other = Deck::new("Jund".to_string());
+        other.entries = vec![
+            entry(printing("Lightning Bolt", "m10", "146"), 3, DeckZone::Main),
+            entry(
+                printing("Lightning Bolt", "m10", "146"),
+                1,
+                DeckZone::Maybeboard,
+            ),
+        ];
+        let owned = collection(&[(printing("Lightning Bolt", "m10", "146"), 4, Finish::Foil)]);
+
+        let lines = deck_diff(&deck, &owned, &[&other], MatchLevel::Oracle);
+        assert_eq!(lines[0].missing, 0);
+        assert_eq!(lines[0].committed, [("Jund".to_string(), 3)]);
+        assert!(lines[0].short_because_committed());
+
+        // A different printing asked for by the other deck doesn't take these copies.
+        other.entries[0] = entry(printing("Lightning Bolt", "2xm", "129"), 3, DeckZone::Main);
+        deck.entries = vec![entry(
+            printing("Lightning Bolt", "m10", "146"),
+            4,
+            DeckZone::Main,
+        )];
+        let lines = deck_diff(&deck, &owned, &[&other], MatchLevel::Printing);
+        assert!(lines[0].committed.is_empty());
+        assert!(!lines[0].short_because_committed());
+    }
+
+    #[test]
+    fn buy_list_and_missing_deck() {
+        let mut deck = Deck::new("Burn".to_string());
+        deck.entries = vec![
+            entry(printing("Lightning Bolt", "m10", "146"), 4, DeckZone::Main),
+            entry(
+                printing("Lightning Bolt", "2xm", "129"),
+                2,
+                DeckZone::Sideboard,
+            ),
+            entry(printing("Goblin Guide", "zen", "126"), 4, DeckZone::Main),
+            entry(printing("Mountain", "m10", "242"), 2, DeckZone::Main),
+        ];
+        let owned = collection(&[
+            (printing("Lightning Bolt", "m10", "146"), 1, Finish::Nonfoil),
+            (printing("Mountain", "m10", "242"), 2, Finish::Nonfoil),
+        ]);
+        let lines = deck_diff(&deck, &owned, &[], MatchLevel::Printing);
+
+        // Printings of the same card are bought together.
+        assert_eq!(buy_list(&lines), "5 Lightning Bolt\n4 Goblin Guide");
+
+        let missing = missing_deck("Burn", &lines);
+        assert_eq!(missing.name, "Burn (missing)");
+        let entries: Vec<(&str, Option<&str>, u32, DeckZone)> = missing
+            .entries
+            .iter()
+            .map(|e| (e.name.as_str(), e.set.as_deref(), e.quantity, e.zone))
+            .collect();
+        assert_eq!(
+            entries,
+            [
+                ("Lightning Bolt", Some("m10"), 3, DeckZone::Main),
+                ("Lightning Bolt", Some("2xm"), 2, DeckZone::Sideboard),
+                ("Goblin Guide", Some("zen"), 4, DeckZone::Main),
+            ]
+        );
+        assert_eq!(buy_list(&[]), "");
+    }
+}
@@ -0,0 +1,259 @@
+use crate::collection::{Collection, Finish};
+use crate::locations::move_entry;
+use crate::prices::{card_price, collection_value, date_string, PriceSource, ValueSnapshot};
+use crate::scryfall_models::Card;
+use crate::storage_view::container_combo;
+use crate::symbol_cache::SymbolCache;
+use crate::tag_editor::edit_tags;
+
+pub const COLLECTION_KEY: &str = "collection";
+
+/// The cards we own, with a filter and quantity controls.
+#[derive(Default)]
+pub struct CollectionView {
+    collection: Collection,
+    filter: String,
+    /// Only show the entries with this tag.
+    tag_filter: Option<String>,
+    /// Cards the user pinned for comparison, picked up by the app every frame.
+    compared: Vec<Card>,
+    /// Whether a tag may have been added or removed since the app last asked.
+    tags_changed: bool,
+}
+
+impl CollectionView {
+    /// Restore the collection saved by a previous session.
+    pub fn load(&mut self, storage: &dyn eframe::Storage) {
+        if let Some(json) = storage.get_string(COLLECTION_KEY) {
+            match serde_json::from_str(&json) {
+                Ok(collection) => self.collection = collection,
+                Err(e) => log::error!("Could not read the saved collection: {}", e),
+            }
+        }
+        self.tags_changed = true;
+    }
+
+    pub fn save(&self, storage: &mut dyn eframe::Storage) {
+        match serde_json::to_string(&self.collection) {
+            Ok(json) => storage.set_string(COLLECTION_KEY, json),
+            Err(e) => log::error!("Could not save the collection: {}", e),
+        }
+    }
+
+    pub fn collection(&self) -> &Collection {
+        &self.collection
+    }
+
+    pub fn collection_mut(&mut self) -> &mut Collection {
+        &mut self.collection
+    }
+
+    pub fn add_card(&mut self, card: Card, finish: Finish) {
+        self.collection.add(card, 1, finish);
+    }
+
+    pub fn take_tags_changed(&mut self) -> bool {
+        std::mem::take(&mut self.tags_changed)
+    }
+
+    pub fn take_compared(&mut self) -> Vec<Card> {
+        std::mem::take(&mut self.compared)
+    }
+
+    pub fn refresh_prices(&mut self, cards: &[Card]) {
+        for card in cards {
+            self.collection.refresh_prices(card);
+        }
+    }
+
+    pub fn draw(
+        &mut self,
+        ui: &mut egui::Ui,
+        symbols: &SymbolCache,
+        price_source: PriceSource,
+        known_tags: &[String],
+        value_history: &[ValueSnapshot],
+    ) {
+        ui.horizontal(|ui| {
+            ui.label("Filter:");
+            ui.text_edit_singleline(&mut self.filter);
+            ui.label("Tag:");
+            egui::ComboBox::from_id_salt("collection_tag_filter")
+                .selected_text(self.tag_filter.as_deref().unwrap_or("Any"))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.tag_filter, None, "Any");
+                    for tag in known_tags {
+                        ui.selectable_value(&mut self.tag_filter, Some(tag.clone()), tag);
+                    }
+                });
+            ui.label(format!(
+                "{} cards, {} different printings",
+                self.collection.total_cards(),
+                self.collection.entries.len()
+            ));
+        });
+        if self.collection.entries.is_empty() {
+            ui.separator();
+            ui.label("Add cards to the collection from the card searcher.");
+            return;
+        }
+        self.show_value(ui, value_history);
+        ui.separator();
+
+        let filter = self.filter.to_lowercase();
+        let mut removed = None;
+        let mut moved = None;
+        egui::ScrollArea::vertical()
+            .id_salt("collection_entries")
+            .show(ui, |ui| {
+                egui::Grid::new("collection_grid")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.strong("Quantity");
+                        ui.strong("Name");
+                        ui.strong("Cost");
+                        ui.strong("Set");
+                        ui.strong("Finish");
+                        ui.strong("Price");
+                        ui.strong("For trade");
+                        ui.strong("Tags");
+                        ui.strong("Location");
+                        ui.end_row();
+                        for (i, entry) in self.collection.entries.iter_mut().enumerate() {
+                            if !entry.card.name.to_lowercase().contains(&filter) {
+                                continue;
+                            }
+                            if let Some(tag) = &self.tag_filter {
+                                if !entry.tags.contains(tag) {
+                                    continue;
+                                }
+                            }
+                            ui.horizontal(|ui| {
+                                if ui.small_button("-").clicked() {
+                                    entry.quantity -= 1;
+                                    entry.tradeable = entry.tradeable.min(entry.quantity);
+                                    if entry.quantity == 0 {
+                                        removed = Some(i);
+                                    }
+                                }
+                                ui.label(entry.quantity.to_string());
+                                if ui.small_button("+").clicked() {
+                                    entry.quantity += 1;
+                                }
+                            });
+                            ui.horizontal(|ui| {
+                                ui.label(&entry.card.name);
+                                if ui.small_button("📌").on_hover_text("Compare").clicked() {
+                                    self.compared.push(entry.card.clone());
+                                }
+                            });
+                            match entry.card.mana_cost.as_deref() {
+                                Some(cost) => symbols.mana_cost(ui, cost),
+                                None => {
+                                    ui.label("");
+                                }
+                            }
+                            ui.label(format!(
+                                "{} #{}",
+                                entry.card.set.to_uppercase(),
+                                entry.card.collector_number
+                            ));
+                            egui::ComboBox::from_id_salt(("collection_finish", i))
+                                .selected_text(entry.finish.label())
+                                .show_ui(ui, |ui| {
+                                    for finish in Finish::ALL {
+                                        ui.selectable_value(
+                                            &mut entry.finish,
+                                            finish,
+                                            finish.label(),
+                                        );
+                                    }
+                                });
+                            match card_price(&entry.card, entry.finish, price_source) {
+                                Some(price) => ui.label(price_source.format(price)),
+                                None => ui.label("-"),
+                            };
+                            ui.add(
+                                egui::DragValue::new(&mut entry.tradeable)
+                                    .range(0..=entry.quantity),
+                            )
+                            .on_hover_text("Copies up for trade");
+                            ui.horizontal(|ui| {
+                                if edit_tags(ui, ("collection", i), &mut entry.tags, known_tags) {
+                                    self.tags_changed = true;
+                                }
+                            });
+                            let containers = &self.collection.containers;
+                            let container = entry.location.map(|l| l.container);
+                            let mut selected = container;
+                            let position = entry.location.map(|l| l.position.label());
+                            ui.horizontal(|ui| {
+                                container_combo(
+                                    ui,
+                                    ("collection_location", i),
+                                    containers,
+                                    &mut selected,
+                                    None,
+                                );
+                                if let Some(position) = position.filter(|p| !p.is_empty()) {
+                                    ui.weak(position);
+                                }
+                            });
+                            if selected != container {
+                                moved = Some((i, selected));
+                            }
+                            ui.end_row();
+                        }
+                    });
+            });
+        if let Some((i, container)) = moved {
+            move_entry(&mut self.collection, i, container);
+        }
+        if let Some(i) = removed {
+            self.collection.entries.remove(i);
+            self.tags_changed = true;
+        }
+    }
+
+    /// What the collection is worth in every currency, and what it was worth before.
+    fn show_value(&self, ui: &mut egui::Ui, value_history: &[ValueSnapshot]) {
+        ui.horizontal(|ui| {
+            ui.label("Value:");
+            for source in PriceSource::ALL {
+                let valuation = collection_value(&self.collection, source);
+                let label = ui.label(source.format(valuation.total));
+                if valuation.unpriced_cards > 0 {
+                    label.on_hover_text(format!(
+                        "{} cards have no {} price",
+                        valuation.unpriced_cards,
+                        source.label()
+                    ));
+                }
+            }
+        });
+        if value_history.is_empty() {
+            return;
+        }
+        egui::CollapsingHeader::new("Value history")
+            .id_salt("collection_value_history")
+            .show(ui, |ui| {
+                egui::Grid::new("collection_value_history_grid")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.strong("Date");
+                        for source in PriceSource::ALL {
+                            ui.strong(source.label());
+                        }
+                        ui.end_row();
+                        for snapshot in value_history.iter().rev() {
+                            ui.label(date_string(snapshot.timestamp));
+                            for source in PriceSource::ALL {
+                                let total = snapshot.totals.get(&source).copied().unwrap_or(0.0);
+                                ui.label(source.format(total));
+                            }
+                            ui.end_row();
+                        }
+                    });
+            });
+    }
+}
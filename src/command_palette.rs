@@ -0,0 +1,187 @@
+use crate::commands::{palette_items, Command, PaletteItem, Shortcuts};
+use egui::{Event, Id, KeyboardShortcut, RichText};
+
+pub const SHORTCUTS_KEY: &str = "shortcuts";
+
+/// How many matches the palette lists.
+const ITEMS_SHOWN: usize = 12;
+
+/// A Ctrl+K popup to run commands and jump to cards by typing, and the keyboard shortcuts.
+#[derive(Default)]
+pub struct CommandPalette {
+    open: bool,
+    query: String,
+    /// The highlighted item, moved with the arrow keys.
+    selected: usize,
+    shortcuts: Shortcuts,
+    show_shortcuts: bool,
+    /// The command waiting for the user to press its new shortcut.
+    recording: Option<Command>,
+}
+
+impl CommandPalette {
+    /// Restore the shortcuts saved by a previous session.
+    pub fn load(&mut self, storage: &dyn eframe::Storage) {
+        if let Some(json) = storage.get_string(SHORTCUTS_KEY) {
+            match serde_json::from_str(&json) {
+                Ok(shortcuts) => self.shortcuts = shortcuts,
+                Err(e) => log::error!("Could not read the keyboard shortcuts: {}", e),
+            }
+        }
+    }
+
+    pub fn save(&self, storage: &mut dyn eframe::Storage) {
+        match serde_json::to_string(&self.shortcuts) {
+            Ok(json) => storage.set_string(SHORTCUTS_KEY, json),
+            Err(e) => log::error!("Could not save the keyboard shortcuts: {}", e),
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        self.query.clear();
+        self.selected = 0;
+    }
+
+    pub fn show_shortcuts(&mut self) {
+        self.show_shortcuts = true;
+    }
+
+    /// The commands whose shortcut was pressed. While the palette is open or a shortcut is
+    /// being recorded, keys go there instead.
+    pub fn pressed_commands(&self, ctx: &egui::Context) -> Vec<Command> {
+        if self.recording.is_some() {
+            return vec![];
+        }
+        let commands = self.shortcuts.pressed(ctx);
+        if self.open {
+            commands
+                .into_iter()
+                .filter(|&c| c == Command::OpenPalette)
+                .collect()
+        } else {
+            commands
+        }
+    }
+
+    /// Draw the palette and the shortcuts window when they are open. Returns the item picked.
+    pub fn draw(&mut self, ctx: &egui::Context, card_names: &[String]) -> Option<PaletteItem> {
+        if self.show_shortcuts {
+            self.show_shortcuts_window(ctx);
+        }
+        if !self.open {
+            return None;
+        }
+        let items = palette_items(&self.query, card_names, ITEMS_SHOWN);
+        self.selected = self.selected.min(items.len().saturating_sub(1));
+        let (up, down, enter) = ctx.input_mut(|i| {
+            (
+                i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::Enter),
+            )
+        });
+        if up {
+            self.selected = self.selected.saturating_sub(1);
+        }
+        if down && self.selected + 1 < items.len() {
+            self.selected += 1;
+        }
+        let mut picked = enter.then(|| items.get(self.selected).cloned()).flatten();
+
+        let modal = egui::Modal::new(Id::new("command_palette")).show(ctx, |ui| {
+            ui.set_width(420.0);
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut self.query)
+                    .hint_text("Type a command or a card name")
+                    .desired_width(f32::INFINITY),
+            );
+            response.request_focus();
+            if response.changed() {
+                self.selected = 0;
+            }
+            ui.separator();
+            for (i, item) in items.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    let label = ui.selectable_label(i == self.selected, item.label());
+                    if label.clicked() {
+                        picked = Some(item.clone());
+                    }
+                    if let PaletteItem::Command(command) = item {
+                        if let Some(shortcut) = self.shortcuts.get(*command) {
+                            ui.weak(ctx.format_shortcut(&shortcut));
+                        }
+                    }
+                });
+            }
+        });
+        if picked.is_some() || modal.should_close() {
+            self.toggle();
+        }
+        picked
+    }
+
+    /// Every command with its shortcut, which can be changed by clicking it and pressing keys.
+    pub fn show_shortcuts_editor(&mut self, ui: &mut egui::Ui) {
+        if let Some(command) = self.recording {
+            if let Some(shortcut) = pressed_shortcut(ui.ctx()) {
+                if shortcut.logical_key != egui::Key::Escape {
+                    self.shortcuts.set(command, Some(shortcut));
+                }
+                self.recording = None;
+            }
+        }
+        egui::Grid::new("shortcuts").striped(true).show(ui, |ui| {
+            for command in Command::all() {
+                ui.label(command.label());
+                let text = if self.recording == Some(command) {
+                    RichText::new("Press keys…").italics()
+                } else {
+                    match self.shortcuts.get(command) {
+                        Some(shortcut) => RichText::new(ui.ctx().format_shortcut(&shortcut)),
+                        None => RichText::new("None").weak(),
+                    }
+                };
+                if ui.button(text).clicked() {
+                    self.recording = Some(command);
+                }
+                if ui.small_button("Clear").clicked() {
+                    self.shortcuts.set(command, None);
+                }
+                ui.end_row();
+            }
+        });
+        if ui.button("Restore defaults").clicked() {
+            self.shortcuts = Shortcuts::default();
+        }
+    }
+
+    fn show_shortcuts_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_shortcuts;
+        egui::Window::new("Keyboard shortcuts")
+            .open(&mut open)
+            .show(ctx, |ui| self.show_shortcuts_editor(ui));
+        self.show_shortcuts = open;
+        if !open {
+            self.recording = None;
+        }
+    }
+}
+
+/// A key pressed this frame, with the modifiers held, ignoring lone modifier presses.
+fn pressed_shortcut(ctx: &egui::Context) -> Option<KeyboardShortcut> {
+    ctx.input_mut(|i| {
+        let index = i
+            .events
+            .iter()
+            .position(|event| matches!(event, Event::Key { pressed: true, .. }))?;
+        match i.events.remove(index) {
+            Event::Key { key, modifiers, .. } => Some(KeyboardShortcut::new(modifiers, key)),
+            _ => None,
+        }
+    })
+}
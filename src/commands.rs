@@ -0,0 +1,291 @@
+use crate::router::View;
+use egui::{Key, KeyboardShortcut, Modifiers};
+use serde::{Deserialize, Serialize};
+
+/// Something the user can do from the command palette or with a keyboard shortcut.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Command {
+    OpenPalette,
+    Show(View),
+    Back,
+    Forward,
+    FocusSearch,
+    /// Add the card shown in the card searcher to the active deck.
+    AddToDeck,
+    /// Add the card shown in the card searcher to the collection, nonfoil.
+    AddToCollection,
+    ImportDeck,
+    /// Copy the active deck as a decklist.
+    ExportDeck,
+    ToggleTheme,
+    EditShortcuts,
+}
+
+impl Command {
+    pub fn all() -> Vec<Command> {
+        let mut commands = vec![Command::OpenPalette];
+        commands.extend(View::ALL.into_iter().map(Command::Show));
+        commands.extend([
+            Command::Back,
+            Command::Forward,
+            Command::FocusSearch,
+            Command::AddToDeck,
+            Command::AddToCollection,
+            Command::ImportDeck,
+            Command::ExportDeck,
+            Command::ToggleTheme,
+            Command::EditShortcuts,
+        ]);
+        commands
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            Command::OpenPalette => "Open the command palette".to_string(),
+            Command::Show(view) => format!("Go to {}", view.label()),
+            Command::Back => "Go back".to_string(),
+            Command::Forward => "Go forward".to_string(),
+            Command::FocusSearch => "Search cards".to_string(),
+            Command::AddToDeck => "Add the selected card to the deck".to_string(),
+            Command::AddToCollection => "Add the selected card to the collection".to_string(),
+            Command::ImportDeck => "Import a decklist".to_string(),
+            Command::ExportDeck => "Copy the deck as a decklist".to_string(),
+            Command::ToggleTheme => "Toggle dark and light theme".to_string(),
+            Command::EditShortcuts => "Edit keyboard shortcuts".to_string(),
+        }
+    }
+}
+
+/// How well `query` matches `text`: every character of the query must appear in order, and
+/// matches at the start of words or right after the previous match score higher. None when
+/// the text doesn't match at all.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<u32> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous_match: Option<usize> = None;
+    for wanted in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = (position..text.len()).find(|&i| text[i] == wanted)?;
+        score += 1;
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 8;
+        }
+        if previous_match.is_some_and(|previous| previous + 1 == found) {
+            score += 4;
+        }
+        previous_match = Some(found);
+        position = found + 1;
+    }
+    // Prefer short texts, which match more of what was typed.
+    Some(score * 100 / (text.len() as u32 + 10))
+}
+
+/// Something offered by the command palette.
+#[derive(Clone, PartialEq, Debug)]
+pub enum PaletteItem {
+    Command(Command),
+    /// Search scryfall for what was typed.
+    SearchCards(String),
+    /// Show a card by its name.
+    OpenCard(String),
+}
+
+impl PaletteItem {
+    pub fn label(&self) -> String {
+        match self {
+            PaletteItem::Command(command) => command.label(),
+            PaletteItem::SearchCards(query) => format!("Search cards for \"{}\"", query),
+            PaletteItem::OpenCard(name) => format!("Show {}", name),
+        }
+    }
+}
+
+/// The commands and card names matching a query, best first. Searching for the query itself
+/// is always offered last.
+pub fn palette_items(query: &str, card_names: &[String], limit: usize) -> Vec<PaletteItem> {
+    let mut scored: Vec<(u32, PaletteItem)> = Command::all()
+        .into_iter()
+        .filter(|&command| command != Command::OpenPalette)
+        .filter_map(|command| {
+            Some((
+                fuzzy_score(query, &command.label())?,
+                PaletteItem::Command(command),
+            ))
+        })
+        .collect();
+    if !query.trim().is_empty() {
+        scored.extend(card_names.iter().filter_map(|name| {
+            Some((
+                fuzzy_score(query, name)?,
+                PaletteItem::OpenCard(name.clone()),
+            ))
+        }));
+    }
+    // Stable, so commands stay in their usual order when nothing is typed.
+    scored.sort_by(|(a, _), (b, _)| b.cmp(a));
+    let mut items: Vec<PaletteItem> = scored.into_iter().map(|(_, item)| item).collect();
+    items.truncate(limit);
+    if !query.trim().is_empty() {
+        items.push(PaletteItem::SearchCards(query.trim().to_string()));
+    }
+    items
+}
+
+/// The keyboard shortcut bound to each command. Saved between sessions.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Shortcuts {
+    pub bindings: Vec<(Command, KeyboardShortcut)>,
+}
+
+impl Default for Shortcuts {
+    fn default() -> Self {
+        let ctrl = |key| KeyboardShortcut::new(Modifiers::COMMAND, key);
+        let ctrl_shift = |key| KeyboardShortcut::new(Modifiers::COMMAND | Modifiers::SHIFT, key);
+        let number_keys = [Key::Num1, Key::Num2, Key::Num3, Key::Num4, Key::Num5];
+        let mut bindings = vec![
+            (Command::OpenPalette, ctrl(Key::K)),
+            (Command::FocusSearch, ctrl(Key::F)),
+            (Command::AddToDeck, ctrl(Key::D)),
+            (Command::AddToCollection, ctrl_shift(Key::D)),
+            (Command::ImportDeck, ctrl(Key::I)),
+            (Command::ExportDeck, ctrl(Key::E)),
+            (Command::ToggleTheme, ctrl_shift(Key::T)),
+        ];
+        bindings.extend(
+            View::ALL
+                .into_iter()
+                .zip(number_keys)
+                .map(|(view, key)| (Command::Show(view), ctrl(key))),
+        );
+        Self { bindings }
+    }
+}
+
+impl Shortcuts {
+    pub fn get(&self, command: Command) -> Option<KeyboardShortcut> {
+        self.bindings
+            .iter()
+            .find(|(c, _)| *c == command)
+            .map(|(_, shortcut)| *shortcut)
+    }
+
+    /// Bind a shortcut to a command, taking it away from any other command.
+    pub fn set(&mut self, command: Command, shortcut: Option<KeyboardShortcut>) {
+        self.bindings
+            .retain(|(c, s)| *c != command && Some(*s) != shortcut);
+        if let Some(shortcut) = shortcut {
+            self.bindings.push((command, shortcut));
+        }
+    }
+
+    /// The commands whose shortcut was pressed this frame. The key presses are consumed so
+    /// widgets don't also react to them.
+    pub fn pressed(&self, ctx: &egui::Context) -> Vec<Command> {
+        // Check shortcuts with more modifiers first, so Ctrl+Shift+D isn't taken for Ctrl+D.
+        let mut bindings = self.bindings.clone();
+        bindings.sort_by_key(|(_, shortcut)| {
+            std::cmp::Reverse(
+                [
+                    shortcut.modifiers.shift,
+                    shortcut.modifiers.alt,
+                    shortcut.modifiers.command || shortcut.modifiers.ctrl,
+                ]
+                .iter()
+                .filter(|&&m| m)
+                .count(),
+            )
+        });
+        ctx.input_mut(|input| {
+            bindings
+                .into_iter()
+                .filter(|(_, shortcut)| input.consume_shortcut(shortcut))
+                .map(|(command, _)| command)
+                .collect()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzzy_matches_in_order() {
+        assert!(fuzzy_score("col", "Go to Collection").is_some());
+        assert!(fuzzy_score("gtc", "Go to Collection").is_some());
+        assert_eq!(fuzzy_score("loc", "Go to Collection"), None);
+        assert_eq!(fuzzy_score("xyz", "Go to Collection"), None);
+        // Case and spaces in the query don't matter.
+        assert_eq!(
+            fuzzy_score("CO L", "Go to Collection"),
+            fuzzy_score("col", "Go to Collection")
+        );
+    }
+
+    #[test]
+    fn fuzzy_ranking() {
+        // A match at the start of a word beats one inside a word.
+        assert!(fuzzy_score("col", "Go to Collection") > fuzzy_score("col", "Protocol"));
+        // Consecutive letters beat scattered ones.
+        assert!(fuzzy_score("dec", "Deck") > fuzzy_score("dec", "Dance circle"));
+        // The shorter text wins when both match the same way.
+        assert!(fuzzy_score("bolt", "Bolt") > fuzzy_score("bolt", "Bolt Bend"));
+    }
+
+    #[test]
+    fn palette_without_query() {
+        let items = palette_items("", &["Lightning Bolt".to_string()], 100);
+        let commands: Vec<PaletteItem> = Command::all()
+            .into_iter()
+            .filter(|&command| command != Command::OpenPalette)
+            .map(PaletteItem::Command)
+            .collect();
+        // Every command in its usual order, and no card or search.
+        assert_eq!(items, commands);
+        assert_eq!(palette_items("", &[], 3).len(), 3);
+    }
+
+    #[test]
+    fn palette_ranks_commands_and_cards() {
+        let items = palette_items("collection", &[], 10);
+        assert_eq!(
+            items[0],
+            PaletteItem::Command(Command::Show(View::Collection))
+        );
+        assert_eq!(
+            items.last(),
+            Some(&PaletteItem::SearchCards("collection".to_string()))
+        );
+
+        let cards = ["Counterspell".to_string(), "Lightning Bolt".to_string()];
+        let items = palette_items(" bolt ", &cards, 10);
+        assert_eq!(
+            items,
+            vec![
+                PaletteItem::OpenCard("Lightning Bolt".to_string()),
+                PaletteItem::SearchCards("bolt".to_string()),
+            ]
+        );
+        // The search is offered beyond the limit.
+        assert_eq!(palette_items("go", &[], 2).len(), 3);
+    }
+
+    #[test]
+    fn setting_a_shortcut_steals_it() {
+        let mut shortcuts = Shortcuts::default();
+        let ctrl_k = KeyboardShortcut::new(Modifiers::COMMAND, Key::K);
+        let ctrl_i = KeyboardShortcut::new(Modifiers::COMMAND, Key::I);
+        assert_eq!(shortcuts.get(Command::OpenPalette), Some(ctrl_k));
+        assert_eq!(shortcuts.get(Command::ImportDeck), Some(ctrl_i));
+
+        shortcuts.set(Command::ImportDeck, Some(ctrl_k));
+        assert_eq!(shortcuts.get(Command::ImportDeck), Some(ctrl_k));
+        assert_eq!(shortcuts.get(Command::OpenPalette), None);
+        // The old shortcut of the command is free again.
+        assert!(shortcuts.bindings.iter().all(|(_, s)| *s != ctrl_i));
+
+        shortcuts.set(Command::ImportDeck, None);
+        assert_eq!(shortcuts.get(Command::ImportDeck), None);
+        assert!(shortcuts.bindings.iter().all(|(_, s)| *s != ctrl_k));
+    }
+}
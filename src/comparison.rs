@@ -0,0 +1,188 @@
+use crate::scryfall_models::Card;
+use serde::{Deserialize, Serialize};
+
+/// Cards pinned to be compared side by side. The first one is the baseline the others'
+/// text is compared with.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct Comparison {
+    pub cards: Vec<Card>,
+}
+
+impl Comparison {
+    /// Pin a card, unless it is already pinned.
+    pub fn pin(&mut self, card: Card) {
+        if !self.cards.iter().any(|c| c.id == card.id) {
+            self.cards.push(card);
+        }
+    }
+
+    pub fn unpin(&mut self, card_id: &str) {
+        self.cards.retain(|c| c.id != card_id);
+    }
+
+    pub fn make_baseline(&mut self, card_id: &str) {
+        if let Some(i) = self.cards.iter().position(|c| c.id == card_id) {
+            let card = self.cards.remove(i);
+            self.cards.insert(0, card);
+        }
+    }
+}
+
+/// The rules text of a card, with the text of every face for multi-faced cards.
+pub fn full_oracle_text(card: &Card) -> String {
+    if card.card_faces.is_empty() {
+        return card.oracle_text.clone().unwrap_or_default();
+    }
+    card.card_faces
+        .iter()
+        .filter_map(|face| face.oracle_text.as_deref())
+        .collect::<Vec<_>>()
+        .join("\n//\n")
+}
+
+/// Power and toughness, or loyalty, of a card or its first face that has them.
+pub fn stats(card: &Card) -> Option<String> {
+    let power_toughness = |power: &Option<String>, toughness: &Option<String>| {
+        Some(format!("{}/{}", power.as_ref()?, toughness.as_ref()?))
+    };
+    power_toughness(&card.power, &card.toughness)
+        .or_else(|| card.loyalty.as_ref().map(|l| format!("Loyalty {}", l)))
+        .or_else(|| {
+            card.card_faces.iter().find_map(|face| {
+                power_toughness(&face.power, &face.toughness)
+                    .or_else(|| face.loyalty.as_ref().map(|l| format!("Loyalty {}", l)))
+            })
+        })
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WordChange {
+    Same,
+    Added,
+    Removed,
+}
+
+/// The words of `new` compared with `old`, with the words only `old` has in between, using
+/// the longest common subsequence of words. Line breaks are kept as "\n" words.
+pub fn word_diff(old: &str, new: &str) -> Vec<(WordChange, String)> {
+    let words = |text: &str| -> Vec<String> {
+        text.split('\n')
+            .enumerate()
+            .flat_map(|(i, line)| {
+                let newline = (i > 0).then(|| "\n".to_string());
+                newline
+                    .into_iter()
+                    .chain(line.split_whitespace().map(str::to_string))
+            })
+            .collect()
+    };
+    let old = words(old);
+    let new = words(new);
+    // lengths[i][j] is the length of the longest common subsequence of old[i..] and new[j..].
+    let mut lengths = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i][j] = if old[i] == new[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    let mut diff = vec![];
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            diff.push((WordChange::Same, new[j].clone()));
+            i += 1;
+            j += 1;
+        } else if j < new.len() && (i == old.len() || lengths[i][j + 1] >= lengths[i + 1][j]) {
+            diff.push((WordChange::Added, new[j].clone()));
+            j += 1;
+        } else {
+            diff.push((WordChange::Removed, old[i].clone()));
+            i += 1;
+        }
+    }
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use WordChange::*;
+
+    fn ids(comparison: &Comparison) -> Vec<&str> {
+        comparison.cards.iter().map(|c| c.id.as_str()).collect()
+    }
+
+    #[test]
+    fn pinning() {
+        let mut comparison = Comparison::default();
+        for name in ["Shock", "Bolt", "Shock", "Chain Lightning"] {
+            comparison.pin(Card::test(name, json!({})));
+        }
+        assert_eq!(ids(&comparison), ["Shock", "Bolt", "Chain Lightning"]);
+        comparison.make_baseline("Chain Lightning");
+        assert_eq!(ids(&comparison), ["Chain Lightning", "Shock", "Bolt"]);
+        comparison.unpin("Shock");
+        comparison.make_baseline("Shock");
+        assert_eq!(ids(&comparison), ["Chain Lightning", "Bolt"]);
+    }
+
+    #[test]
+    fn text_and_stats_of_faces() {
+        let creature = Card::test(
+            "Goyf",
+            json!({ "oracle_text": "Big.", "power": "*", "toughness": "1+*" }),
+        );
+        assert_eq!(full_oracle_text(&creature), "Big.");
+        assert_eq!(stats(&creature).as_deref(), Some("*/1+*"));
+
+        let flip = Card::test(
+            "Jace",
+            json!({ "card_faces": [
+                { "name": "Jace, Vryn's Prodigy", "oracle_text": "Loot.", "power": "0", "toughness": "2" },
+                { "name": "Jace, Telepath Unbound", "oracle_text": "+1: Shrink.", "loyalty": "5" }
+            ] }),
+        );
+        assert_eq!(full_oracle_text(&flip), "Loot.\n//\n+1: Shrink.");
+        assert_eq!(stats(&flip).as_deref(), Some("0/2"));
+
+        let walker = Card::test("Teferi", json!({ "loyalty": "4" }));
+        assert_eq!(stats(&walker).as_deref(), Some("Loyalty 4"));
+        assert_eq!(stats(&Card::test("Bolt", json!({}))), None);
+        assert_eq!(full_oracle_text(&Card::test("Vanilla", json!({}))), "");
+    }
+
+    #[test]
+    fn diffing_words() {
+        let diff = word_diff(
+            "Lightning Bolt deals 3 damage to any target.",
+            "Shock deals 2 damage to any target.",
+        );
+        let changes: Vec<(WordChange, &str)> = diff.iter().map(|(c, w)| (*c, w.as_str())).collect();
+        assert_eq!(
+            changes,
+            [
+                (Added, "Shock"),
+                (Removed, "Lightning"),
+                (Removed, "Bolt"),
+                (Same, "deals"),
+                (Added, "2"),
+                (Removed, "3"),
+                (Same, "damage"),
+                (Same, "to"),
+                (Same, "any"),
+                (Same, "target."),
+            ]
+        );
+
+        // Line breaks are words of their own.
+        let diff = word_diff("Flying\nVigilance", "Flying\nHaste");
+        assert_eq!(diff[1], (Same, "\n".to_string()));
+        assert!(word_diff("", "").is_empty());
+        assert!(word_diff("a b", "a b").iter().all(|(c, _)| *c == Same));
+    }
+}
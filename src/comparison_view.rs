@@ -0,0 +1,245 @@
+use crate::card_detail_view::{legality_color, legality_label};
+use crate::card_search_view::CardAction;
+use crate::collection::Finish;
+use crate::comparison::{full_oracle_text, stats, word_diff, Comparison, WordChange};
+use crate::deck::DeckZone;
+use crate::format_validation::Format;
+use crate::prices::{card_price, PriceSource};
+use crate::scryfall_models::Card;
+use crate::settings::Settings;
+use crate::symbol_cache::SymbolCache;
+use crate::texture_cache::TextureCache;
+use egui::{Color32, Image, RichText};
+
+pub const COMPARISON_KEY: &str = "comparison";
+const COLUMN_WIDTH: f32 = 220.0;
+
+/// Cards pinned from the searcher, the collection or a deck, side by side with their
+/// attributes aligned in rows.
+pub struct ComparisonView {
+    comparison: Comparison,
+    textures: TextureCache,
+    add_to_zone: DeckZone,
+}
+
+impl Default for ComparisonView {
+    fn default() -> Self {
+        Self {
+            comparison: Comparison::default(),
+            textures: TextureCache::default(),
+            add_to_zone: DeckZone::Main,
+        }
+    }
+}
+
+impl ComparisonView {
+    pub fn apply_settings(&mut self, settings: &Settings) {
+        self.textures
+            .configure(settings.image_size, settings.images_in_memory);
+    }
+
+    /// Restore the cards pinned in a previous session.
+    pub fn load(&mut self, storage: &dyn eframe::Storage) {
+        if let Some(json) = storage.get_string(COMPARISON_KEY) {
+            match serde_json::from_str(&json) {
+                Ok(comparison) => self.comparison = comparison,
+                Err(e) => log::error!("Could not read the compared cards: {}", e),
+            }
+        }
+    }
+
+    pub fn save(&self, storage: &mut dyn eframe::Storage) {
+        match serde_json::to_string(&self.comparison) {
+            Ok(json) => storage.set_string(COMPARISON_KEY, json),
+            Err(e) => log::error!("Could not save the compared cards: {}", e),
+        }
+    }
+
+    pub fn pin(&mut self, card: Card) {
+        self.comparison.pin(card);
+    }
+
+    /// Draw the comparison. Returns the card the user chose to add to the deck, if any.
+    pub fn draw(
+        &mut self,
+        ui: &mut egui::Ui,
+        ctx: &egui::Context,
+        symbols: &SymbolCache,
+        price_source: PriceSource,
+    ) -> Option<CardAction> {
+        ui.horizontal(|ui| {
+            ui.label(format!("{} cards pinned", self.comparison.cards.len()));
+            ui.label("Add the chosen one to:");
+            egui::ComboBox::from_id_salt("compare_add_to_zone")
+                .selected_text(self.add_to_zone.label())
+                .show_ui(ui, |ui| {
+                    for zone in DeckZone::ALL {
+                        ui.selectable_value(&mut self.add_to_zone, zone, zone.label());
+                    }
+                });
+            if ui.button("Unpin all").clicked() {
+                self.comparison.cards.clear();
+            }
+        });
+        ui.separator();
+        if self.comparison.cards.is_empty() {
+            ui.label("Pin cards with 📌 in the card searcher, the collection or a deck.");
+            return None;
+        }
+
+        let cards = self.comparison.cards.clone();
+        let baseline_text = full_oracle_text(&cards[0]);
+        let mut action = None;
+        let mut unpinned = None;
+        let mut baseline = None;
+        egui::ScrollArea::both()
+            .id_salt("comparison")
+            .show(ui, |ui| {
+                egui::Grid::new("comparison_grid")
+                    .striped(true)
+                    .min_col_width(COLUMN_WIDTH)
+                    .show(ui, |ui| {
+                        ui.label("");
+                        for (i, card) in cards.iter().enumerate() {
+                            ui.vertical(|ui| {
+                                if let Some(texture) = self.textures.get(ctx, card) {
+                                    ui.add(
+                                        Image::new(&texture)
+                                            .rounding(10.0)
+                                            .max_width(COLUMN_WIDTH)
+                                            .maintain_aspect_ratio(true),
+                                    );
+                                }
+                                ui.horizontal(|ui| {
+                                    if ui.button("Add to deck").clicked() {
+                                        action = Some(CardAction::AddToDeck(
+                                            card.clone(),
+                                            self.add_to_zone,
+                                        ));
+                                    }
+                                    if i > 0
+                                        && ui
+                                            .small_button("⏮")
+                                            .on_hover_text("Compare the others with this card")
+                                            .clicked()
+                                    {
+                                        baseline = Some(card.id.clone());
+                                    }
+                                    if ui.small_button("✖").on_hover_text("Unpin").clicked() {
+                                        unpinned = Some(card.id.clone());
+                                    }
+                                });
+                            });
+                        }
+                        ui.end_row();
+
+                        row(ui, "Name", &cards, |ui, card| {
+                            ui.strong(&card.name);
+                            ui.weak(format!(
+                                "{} ({}) #{}",
+                                card.set_name,
+                                card.set.to_uppercase(),
+                                card.collector_number
+                            ));
+                        });
+                        row(ui, "Mana cost", &cards, |ui, card| {
+                            if let Some(cost) = mana_cost(card) {
+                                symbols.mana_cost(ui, &cost);
+                            }
+                            if let Some(cmc) = card.cmc {
+                                ui.weak(format!("Mana value {}", cmc));
+                            }
+                        });
+                        row(ui, "Type", &cards, |ui, card| {
+                            ui.label(card.type_line.as_deref().unwrap_or(""));
+                        });
+                        row(ui, "P/T", &cards, |ui, card| {
+                            ui.label(stats(card).unwrap_or_default());
+                        });
+                        row(ui, "Text", &cards, |ui, card| {
+                            let text = full_oracle_text(card);
+                            if card.id == cards[0].id {
+                                symbols.rules_text(ui, &text);
+                            } else {
+                                show_text_diff(ui, &baseline_text, &text);
+                            }
+                        });
+                        for format in Format::ALL {
+                            row(ui, format.label(), &cards, |ui, card| {
+                                let status = card
+                                    .legalities
+                                    .get(format.scryfall_key())
+                                    .map_or("not_legal", String::as_str);
+                                ui.colored_label(legality_color(status), legality_label(status));
+                            });
+                        }
+                        row(ui, "Price", &cards, |ui, card| {
+                            for finish in Finish::ALL {
+                                if let Some(price) = card_price(card, finish, price_source) {
+                                    ui.label(format!(
+                                        "{}: {}",
+                                        finish.label(),
+                                        price_source.format(price)
+                                    ));
+                                }
+                            }
+                        });
+                    });
+            });
+        if let Some(id) = unpinned {
+            self.comparison.unpin(&id);
+        }
+        if let Some(id) = baseline {
+            self.comparison.make_baseline(&id);
+        }
+        action
+    }
+}
+
+/// One attribute of every card, in aligned cells.
+fn row(ui: &mut egui::Ui, label: &str, cards: &[Card], mut cell: impl FnMut(&mut egui::Ui, &Card)) {
+    ui.strong(label);
+    for card in cards {
+        ui.vertical(|ui| {
+            ui.set_max_width(COLUMN_WIDTH);
+            cell(ui, card);
+        });
+    }
+    ui.end_row();
+}
+
+/// The mana cost of a card, or of its faces for cards that have one per face.
+fn mana_cost(card: &Card) -> Option<String> {
+    if let Some(cost) = card.mana_cost.as_ref().filter(|c| !c.is_empty()) {
+        return Some(cost.clone());
+    }
+    let costs: Vec<&str> = card
+        .card_faces
+        .iter()
+        .filter_map(|face| face.mana_cost.as_deref())
+        .filter(|cost| !cost.is_empty())
+        .collect();
+    (!costs.is_empty()).then(|| costs.join(" // "))
+}
+
+/// The text of a card with the words the baseline does not have highlighted, and the
+/// baseline's words it lacks struck through.
+fn show_text_diff(ui: &mut egui::Ui, baseline: &str, text: &str) {
+    let diff = word_diff(baseline, text);
+    let lines = diff.split(|(_, word)| word == "\n");
+    for line in lines {
+        ui.horizontal_wrapped(|ui| {
+            ui.spacing_mut().item_spacing.x = 4.0;
+            for (change, word) in line {
+                let word = RichText::new(word);
+                ui.label(match change {
+                    WordChange::Same => word,
+                    WordChange::Added => word
+                        .color(Color32::BLACK)
+                        .background_color(Color32::from_rgb(150, 220, 150)),
+                    WordChange::Removed => word.strikethrough().color(Color32::LIGHT_RED),
+                });
+            }
+        });
+    }
+}
@@ -0,0 +1,260 @@
+use crate::card_search_view::CardSearchView;
+use crate::collection::Collection;
+use crate::deck_builder_view::DeckBuilderView;
+use crate::local_cache::cache_size;
+use crate::prices::{collection_value, date_string, now, PriceSource, SECONDS_PER_DAY};
+use crate::prices_view::PricesView;
+use crate::router::View;
+use crate::rulings::bulk_downloaded_at;
+use egui::Color32;
+use std::time::UNIX_EPOCH;
+
+const RECENT_CARDS_SHOWN: usize = 8;
+const RECENT_DECKS_SHOWN: usize = 5;
+const MOVERS_SHOWN: usize = 5;
+
+/// Something the user asked for from the dashboard, done by the app.
+pub enum DashboardAction {
+    Search(String),
+    OpenCard(String),
+    OpenDeck(usize),
+    NewDeck,
+    ImportDeck,
+    Show(View),
+}
+
+/// How old the downloaded data is. Measuring it reads the disk, so it is done once and when
+/// asked for.
+struct Freshness {
+    rulings_downloaded: Option<u64>,
+    cache_bytes: u64,
+}
+
+impl Freshness {
+    fn measure() -> Self {
+        Self {
+            rulings_downloaded: bulk_downloaded_at()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_secs()),
+            cache_bytes: cache_size(),
+        }
+    }
+}
+
+/// The home page: an overview of the collection, decks and prices, with quick actions.
+#[derive(Default)]
+pub struct DashboardView {
+    query: String,
+    freshness: Option<Freshness>,
+}
+
+impl DashboardView {
+    pub fn draw(
+        &mut self,
+        ui: &mut egui::Ui,
+        collection: &Collection,
+        decks: &DeckBuilderView,
+        prices: &PricesView,
+        search: &mut CardSearchView,
+        price_source: PriceSource,
+    ) -> Option<DashboardAction> {
+        let mut action = None;
+        ui.heading("Welcome to eMTG");
+        ui.horizontal(|ui| {
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut self.query)
+                    .hint_text("Search cards, e.g. t:angel c:w")
+                    .desired_width(300.0),
+            );
+            let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            if (ui.button("Search").clicked() || submitted) && !self.query.trim().is_empty() {
+                action = Some(DashboardAction::Search(self.query.trim().to_string()));
+            }
+            ui.separator();
+            if ui.button("New deck").clicked() {
+                action = Some(DashboardAction::NewDeck);
+            }
+            if ui.button("Import decklist").clicked() {
+                action = Some(DashboardAction::ImportDeck);
+            }
+        });
+        ui.separator();
+        egui::ScrollArea::vertical()
+            .id_salt("dashboard")
+            .show(ui, |ui| {
+                ui.columns(2, |columns| {
+                    let ui = &mut columns[0];
+                    ui.heading("Collection");
+                    show_collection(ui, collection, prices, price_source, &mut action);
+                    ui.separator();
+                    ui.heading("Recently edited decks");
+                    show_recent_decks(ui, decks, &mut action);
+
+                    let ui = &mut columns[1];
+                    ui.heading("Price movers");
+                    show_movers(ui, prices, price_source, &mut action);
+                    ui.separator();
+                    ui.heading("Saved searches");
+                    if let Some(query) = search.show_saved_searches(ui) {
+                        action = Some(DashboardAction::Search(query));
+                    }
+                    ui.separator();
+                    ui.heading("Data");
+                    self.show_freshness(ui, prices);
+                });
+            });
+        action
+    }
+
+    fn show_freshness(&mut self, ui: &mut egui::Ui, prices: &PricesView) {
+        let freshness = self.freshness.get_or_insert_with(Freshness::measure);
+        egui::Grid::new("dashboard_freshness").show(ui, |ui| {
+            ui.label("Prices recorded:");
+            match prices.last_recorded() {
+                Some(timestamp) => ui.label(age_label(timestamp)),
+                None => ui.weak("Never"),
+            };
+            ui.end_row();
+            ui.label("Rulings bulk data:");
+            match freshness.rulings_downloaded {
+                Some(timestamp) => ui.label(age_label(timestamp)),
+                None => ui.weak("Not downloaded"),
+            };
+            ui.end_row();
+            ui.label("Disk cache:");
+            ui.label(format!(
+                "{:.1} MB",
+                freshness.cache_bytes as f64 / 1_000_000.0
+            ));
+            ui.end_row();
+        });
+        if ui.button("Refresh").clicked() {
+            self.freshness = None;
+        }
+    }
+}
+
+fn show_collection(
+    ui: &mut egui::Ui,
+    collection: &Collection,
+    prices: &PricesView,
+    price_source: PriceSource,
+    action: &mut Option<DashboardAction>,
+) {
+    if collection.entries.is_empty() {
+        ui.label("Add cards to the collection from the card searcher.");
+        return;
+    }
+    let value = collection_value(collection, price_source);
+    ui.label(format!(
+        "{} cards, {} different printings, worth {}",
+        collection.total_cards(),
+        collection.entries.len(),
+        price_source.format(value.total)
+    ));
+    let window_days = prices.window_days();
+    if let Some(before) = prices.collection_value_days_ago(window_days, price_source) {
+        let change = value.total - before;
+        ui.label(format!(
+            "{} {} over the last {} days",
+            if change < 0.0 { "Down" } else { "Up" },
+            price_source.format(change.abs()),
+            window_days
+        ));
+    }
+    let recent = collection.recently_added(RECENT_CARDS_SHOWN);
+    if !recent.is_empty() {
+        ui.strong("Recently added");
+        egui::Grid::new("dashboard_recent_cards")
+            .striped(true)
+            .show(ui, |ui| {
+                for entry in recent {
+                    if ui.link(&entry.card.name).clicked() {
+                        *action = Some(DashboardAction::OpenCard(entry.card.name.clone()));
+                    }
+                    ui.label(entry.card.set.to_uppercase());
+                    ui.weak(age_label(entry.added));
+                    ui.end_row();
+                }
+            });
+    }
+    if ui.button("Open the collection").clicked() {
+        *action = Some(DashboardAction::Show(View::Collection));
+    }
+}
+
+fn show_recent_decks(
+    ui: &mut egui::Ui,
+    decks: &DeckBuilderView,
+    action: &mut Option<DashboardAction>,
+) {
+    let recent = decks.recent_decks(RECENT_DECKS_SHOWN);
+    if recent.is_empty() {
+        ui.label("No decks yet.");
+        return;
+    }
+    egui::Grid::new("dashboard_recent_decks")
+        .striped(true)
+        .show(ui, |ui| {
+            for (index, deck) in recent {
+                if ui.link(&deck.name).clicked() {
+                    *action = Some(DashboardAction::OpenDeck(index));
+                }
+                let cards: u32 = deck.entries.iter().map(|e| e.quantity).sum();
+                ui.label(format!("{} cards", cards));
+                if deck.modified > 0 {
+                    ui.weak(age_label(deck.modified));
+                } else {
+                    ui.label("");
+                }
+                ui.end_row();
+            }
+        });
+}
+
+fn show_movers(
+    ui: &mut egui::Ui,
+    prices: &PricesView,
+    price_source: PriceSource,
+    action: &mut Option<DashboardAction>,
+) {
+    let movers = prices.movers(price_source);
+    if movers.is_empty() {
+        ui.label(format!(
+            "No price changed in the last {} days.",
+            prices.window_days()
+        ));
+        return;
+    }
+    egui::Grid::new("dashboard_movers")
+        .striped(true)
+        .show(ui, |ui| {
+            for mover in movers.iter().take(MOVERS_SHOWN) {
+                if ui.link(&mover.name).clicked() {
+                    *action = Some(DashboardAction::OpenCard(mover.name.clone()));
+                }
+                ui.label(price_source.format(mover.new_price));
+                let color = if mover.change() > 0.0 {
+                    Color32::GREEN
+                } else {
+                    Color32::LIGHT_RED
+                };
+                ui.colored_label(color, format!("{:+.1}%", mover.relative_change() * 100.0));
+                ui.end_row();
+            }
+        });
+    if ui.button("All prices").clicked() {
+        *action = Some(DashboardAction::Show(View::Prices));
+    }
+}
+
+/// "Today", "Yesterday", "3 days ago", then the date for anything older than a month.
+fn age_label(timestamp: u64) -> String {
+    let days = now().saturating_sub(timestamp) / SECONDS_PER_DAY;
+    match days {
+        0 => "Today".to_string(),
+        1 => "Yesterday".to_string(),
+        2..=30 => format!("{} days ago", days),
+        _ => date_string(timestamp),
+    }
+}
@@ -0,0 +1,208 @@
+use crate::format_validation::Format;
+use crate::scryfall_models::Card;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+/// The different piles a card can belong to inside a deck.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum DeckZone {
+    Main,
+    Sideboard,
+    Maybeboard,
+    Commander,
+    Companion,
+}
+
+impl DeckZone {
+    pub const ALL: [DeckZone; 5] = [
+        DeckZone::Commander,
+        DeckZone::Companion,
+        DeckZone::Main,
+        DeckZone::Sideboard,
+        DeckZone::Maybeboard,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            DeckZone::Main => "Deck",
+            DeckZone::Sideboard => "Sideboard",
+            DeckZone::Maybeboard => "Maybeboard",
+            DeckZone::Commander => "Commander",
+            DeckZone::Companion => "Companion",
+        }
+    }
+
+    /// Match a section header such as "Sideboard", "SIDEBOARD:" or "Commander (1)".
+    pub fn from_header(header: &str) -> Option<DeckZone> {
+        let header = header.trim().trim_end_matches(':').trim();
+        // Some sites append the card count to the header, e.g. "Sideboard (15)".
+        let header = match header.find('(') {
+            Some(idx) if header.ends_with(')') => header[..idx].trim(),
+            _ => header,
+        };
+        match header.to_ascii_lowercase().as_str() {
+            "deck" | "main" | "maindeck" | "mainboard" | "main deck" => Some(DeckZone::Main),
+            "sideboard" | "side" | "side board" => Some(DeckZone::Sideboard),
+            "maybeboard" | "maybe" | "considering" => Some(DeckZone::Maybeboard),
+            "commander" | "commanders" => Some(DeckZone::Commander),
+            "companion" => Some(DeckZone::Companion),
+            _ => None,
+        }
+    }
+}
+
+/// A single line of a deck: a card name, how many copies and, optionally, which printing.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DeckEntry {
+    pub name: String,
+    pub quantity: u32,
+    pub zone: DeckZone,
+    #[serde(default)]
+    pub set: Option<String>,
+    #[serde(default)]
+    pub collector_number: Option<String>,
+    #[serde(default)]
+    pub foil: bool,
+    /// The scryfall card this entry resolved to, if it has been resolved. Saved decks only keep
+    /// its id, in `card_id`, and read the card back from the disk cache.
+    #[serde(default, skip_serializing)]
+    pub card: Option<Card>,
+    #[serde(default)]
+    pub card_id: Option<String>,
+    /// What the card is in the deck for, e.g. "ramp" or "removal".
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl DeckEntry {
+    pub fn new(name: String, quantity: u32, zone: DeckZone) -> Self {
+        Self {
+            name,
+            quantity,
+            zone,
+            set: None,
+            collector_number: None,
+            foil: false,
+            card: None,
+            card_id: None,
+            tags: vec![],
+        }
+    }
+
+    /// An entry for an already known printing.
+    pub fn from_card(card: Card, quantity: u32, zone: DeckZone) -> Self {
+        Self {
+            name: card.name.clone(),
+            quantity,
+            zone,
+            set: Some(card.set.clone()),
+            collector_number: Some(card.collector_number.clone()),
+            foil: false,
+            card_id: Some(card.id.clone()),
+            card: Some(card),
+            tags: vec![],
+        }
+    }
+
+    /// Two entries refer to the same slot when they name the same card and printing.
+    pub fn same_slot(&self, other: &DeckEntry) -> bool {
+        self.zone == other.zone
+            && self.name.eq_ignore_ascii_case(&other.name)
+            && self.set == other.set
+            && self.collector_number == other.collector_number
+            && self.foil == other.foil
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct Deck {
+    pub name: String,
+    pub entries: Vec<DeckEntry>,
+    /// The format the deck is built for, used to check its legality.
+    #[serde(default)]
+    pub format: Option<Format>,
+    /// When the deck was last edited, as a unix timestamp.
+    #[serde(default)]
+    pub modified: u64,
+}
+
+impl Deck {
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            entries: vec![],
+            format: None,
+            modified: 0,
+        }
+    }
+
+    /// A hash of the deck's name, format and slots, to notice when it is edited.
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.name.hash(&mut hasher);
+        self.format.hash(&mut hasher);
+        for entry in &self.entries {
+            entry.name.hash(&mut hasher);
+            entry.quantity.hash(&mut hasher);
+            entry.zone.hash(&mut hasher);
+            entry.set.hash(&mut hasher);
+            entry.collector_number.hash(&mut hasher);
+            entry.foil.hash(&mut hasher);
+            entry.tags.hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+
+    /// Add an entry, merging its quantity into an existing identical slot.
+    pub fn add_entry(&mut self, entry: DeckEntry) {
+        match self.entries.iter_mut().find(|e| e.same_slot(&entry)) {
+            Some(existing) => {
+                existing.quantity += entry.quantity;
+                if existing.card.is_none() {
+                    existing.card = entry.card;
+                }
+                for tag in entry.tags {
+                    if !existing.tags.contains(&tag) {
+                        existing.tags.push(tag);
+                    }
+                }
+            }
+            None => self.entries.push(entry),
+        }
+    }
+
+    pub fn zone_entries(&self, zone: DeckZone) -> impl Iterator<Item = &DeckEntry> {
+        self.entries.iter().filter(move |e| e.zone == zone)
+    }
+
+    /// Total number of cards in a zone, counting every copy.
+    pub fn zone_count(&self, zone: DeckZone) -> u32 {
+        self.zone_entries(zone).map(|e| e.quantity).sum()
+    }
+
+    /// Write the deck as an MTG Arena style decklist, which `parse_decklist` reads back.
+    pub fn to_text(&self) -> String {
+        let mut sections = vec![];
+        for zone in DeckZone::ALL {
+            if self.zone_entries(zone).next().is_none() {
+                continue;
+            }
+            let mut section = zone.label().to_string();
+            for entry in self.zone_entries(zone) {
+                section.push_str(&format!("\n{} {}", entry.quantity, entry.name));
+                if let Some(set) = &entry.set {
+                    section.push_str(&format!(" ({})", set.to_uppercase()));
+                    if let Some(collector_number) = &entry.collector_number {
+                        section.push_str(&format!(" {}", collector_number));
+                    }
+                }
+                if entry.foil {
+                    section.push_str(" *F*");
+                }
+            }
+            sections.push(section);
+        }
+        sections.join("\n\n")
+    }
+}
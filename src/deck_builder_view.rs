@@ -0,0 +1,1012 @@
+use crate::card_lookup::{cache_card, cached_card, CardLookup};
+use crate::card_search_view::CardSearchView;
+use crate::collection::Collection;
+use crate::collection_diff::{buy_list, deck_diff, missing_deck, DiffLine, MatchLevel};
+use crate::deck::{Deck, DeckEntry, DeckZone};
+use crate::deck_stats::{deck_stats, DeckStats, CURVE_CAP};
+use crate::decklist_parser::{parse_decklist, LineDiagnostic, ParsedDecklist, Severity};
+use crate::draw_probability::{draw_probability, DrawCategory, DrawQuery, OPENING_HAND_SIZE};
+use crate::format_validation::{validate_deck, Format, ViolationKind};
+use crate::goldfish_view::GoldfishView;
+use crate::mana::ManaColor;
+use crate::prices::{deck_value, now, PriceSource};
+use crate::scryfall_models::{Card, CardIdentifier};
+use crate::settings::Settings;
+use crate::symbol_cache::SymbolCache;
+use crate::tag_editor::edit_tags;
+use crate::tags::known_tags;
+use egui::{Color32, Frame, Id, RichText};
+use egui_plot::{Bar, BarChart, Legend, Plot};
+use std::collections::{BTreeMap, HashSet};
+
+pub const DECKS_KEY: &str = "decks";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum DeckGrouping {
+    None,
+    Type,
+    ManaValue,
+    Color,
+    Tag,
+}
+
+impl DeckGrouping {
+    fn label(&self) -> &'static str {
+        match self {
+            DeckGrouping::None => "None",
+            DeckGrouping::Type => "Type",
+            DeckGrouping::ManaValue => "Mana value",
+            DeckGrouping::Color => "Color",
+            DeckGrouping::Tag => "Tag",
+        }
+    }
+
+    /// The group an entry belongs to. The leading number keeps groups in a sensible order.
+    fn group_of(&self, entry: &DeckEntry) -> (u8, String) {
+        let card = entry.card.as_ref();
+        match self {
+            DeckGrouping::None => (0, String::new()),
+            DeckGrouping::Type => {
+                let type_line = card.and_then(|c| c.type_line.as_deref()).unwrap_or("");
+                // Only look at the front face of double faced cards.
+                let front = type_line.split(" // ").next().unwrap_or("");
+                const TYPES: [&str; 8] = [
+                    "Land",
+                    "Creature",
+                    "Planeswalker",
+                    "Battle",
+                    "Instant",
+                    "Sorcery",
+                    "Artifact",
+                    "Enchantment",
+                ];
+                match TYPES.iter().position(|t| front.contains(t)) {
+                    Some(i) => (i as u8, TYPES[i].to_string()),
+                    None => (TYPES.len() as u8, "Other".to_string()),
+                }
+            }
+            DeckGrouping::ManaValue => match card.and_then(|c| c.cmc) {
+                Some(cmc) if card_is_land(card) => (u8::MAX, format!("Lands ({})", cmc)),
+                Some(cmc) => (cmc as u8, format!("Mana value {}", cmc)),
+                None => (u8::MAX, "Unknown".to_string()),
+            },
+            DeckGrouping::Color => {
+                let colors = card.and_then(|c| c.colors.clone()).unwrap_or_default();
+                match colors.as_slice() {
+                    [] if card_is_land(card) => (7, "Lands".to_string()),
+                    [] => (5, "Colorless".to_string()),
+                    [color] => match ManaColor::from_letter(color) {
+                        Some(color) => {
+                            let i = ManaColor::ALL.iter().position(|c| *c == color);
+                            (i.unwrap_or(0) as u8, color.name().to_string())
+                        }
+                        None => (5, "Colorless".to_string()),
+                    },
+                    _ => (6, "Multicolor".to_string()),
+                }
+            }
+            // Entries are grouped by their first tag, usually their main role in the deck.
+            DeckGrouping::Tag => match entry.tags.first() {
+                Some(tag) => (0, tag.clone()),
+                None => (1, "Untagged".to_string()),
+            },
+        }
+    }
+}
+
+fn card_is_land(card: Option<&Card>) -> bool {
+    card.and_then(|c| c.type_line.as_deref())
+        .is_some_and(|t| t.split(" // ").next().unwrap_or("").contains("Land"))
+}
+
+/// Payload used when dragging an entry from one zone to another.
+struct MovedEntry(usize);
+
+pub struct DeckBuilderView {
+    decks: Vec<Deck>,
+    selected_deck: usize,
+    grouping: DeckGrouping,
+    new_deck_name: String,
+    import_text: String,
+    show_import: bool,
+    import_diagnostics: Vec<LineDiagnostic>,
+    /// An imported decklist waiting for its cards to be looked up.
+    pending_import: Option<(ParsedDecklist, CardLookup)>,
+    /// Saved cards missing from the disk cache, being looked up again.
+    card_lookup: Option<CardLookup>,
+    /// Saved card ids already looked up, so cards scryfall didn't return are not asked for
+    /// again every frame.
+    looked_up_ids: HashSet<String>,
+    draw_odds: DrawOddsSettings,
+    goldfish: GoldfishView,
+    show_goldfish: bool,
+    match_level: MatchLevel,
+    /// Cards the user pinned for comparison, picked up by the app every frame.
+    compared: Vec<Card>,
+    /// The index and fingerprint of the active deck when last drawn, to notice edits.
+    active_fingerprint: Option<(usize, u64)>,
+    /// Whether a tag may have been added or removed since the app last asked.
+    tags_changed: bool,
+}
+
+/// What the draw odds table is computed for.
+struct DrawOddsSettings {
+    category: DrawCategory,
+    wanted: u32,
+    on_the_play: bool,
+    max_mulligans: u32,
+    mulligan_below: u32,
+}
+
+impl Default for DrawOddsSettings {
+    fn default() -> Self {
+        Self {
+            category: DrawCategory::Lands,
+            wanted: 3,
+            on_the_play: true,
+            max_mulligans: 0,
+            mulligan_below: 2,
+        }
+    }
+}
+
+impl Default for DeckBuilderView {
+    fn default() -> Self {
+        Self {
+            decks: vec![],
+            selected_deck: 0,
+            grouping: DeckGrouping::Type,
+            new_deck_name: String::new(),
+            import_text: String::new(),
+            show_import: false,
+            import_diagnostics: vec![],
+            pending_import: None,
+            card_lookup: None,
+            looked_up_ids: HashSet::new(),
+            draw_odds: DrawOddsSettings::default(),
+            goldfish: GoldfishView::default(),
+            show_goldfish: false,
+            match_level: MatchLevel::Oracle,
+            compared: vec![],
+            active_fingerprint: None,
+            tags_changed: false,
+        }
+    }
+}
+
+impl DeckBuilderView {
+    pub fn apply_settings(&mut self, settings: &Settings) {
+        self.goldfish.apply_settings(settings);
+    }
+
+    /// Restore the decks saved by a previous session.
+    pub fn load(&mut self, storage: &dyn eframe::Storage) {
+        if let Some(json) = storage.get_string(DECKS_KEY) {
+            match serde_json::from_str(&json) {
+                Ok(decks) => self.decks = decks,
+                Err(e) => log::error!("Could not read the saved decks: {}", e),
+            }
+        }
+        for entry in self.decks.iter_mut().flat_map(|d| d.entries.iter_mut()) {
+            if let Some(id) = &entry.card_id {
+                entry.card = cached_card(id);
+            }
+        }
+        self.tags_changed = true;
+    }
+
+    pub fn take_tags_changed(&mut self) -> bool {
+        std::mem::take(&mut self.tags_changed)
+    }
+
+    /// Save the decks, with only the id of their cards. The cards themselves go to the disk
+    /// cache.
+    pub fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        for entry in self.decks.iter_mut().flat_map(|d| d.entries.iter_mut()) {
+            if let Some(card) = &entry.card {
+                entry.card_id = Some(card.id.clone());
+                cache_card(card);
+            }
+        }
+        match serde_json::to_string(&self.decks) {
+            Ok(json) => storage.set_string(DECKS_KEY, json),
+            Err(e) => log::error!("Could not save the decks: {}", e),
+        }
+    }
+
+    pub fn decks(&self) -> &[Deck] {
+        &self.decks
+    }
+
+    pub fn active_deck_mut(&mut self) -> Option<&mut Deck> {
+        self.decks.get_mut(self.selected_deck)
+    }
+
+    /// Add a card to the active deck, creating one if there is none yet.
+    pub fn add_card(&mut self, card: Card, zone: DeckZone) {
+        if self.decks.is_empty() {
+            self.push_deck(Deck::new("New deck".to_string()));
+        }
+        if let Some(deck) = self.active_deck_mut() {
+            deck.add_entry(DeckEntry::from_card(card, 1, zone));
+            deck.modified = now();
+        }
+    }
+
+    /// Add a deck and make it the active one.
+    fn push_deck(&mut self, mut deck: Deck) {
+        deck.modified = now();
+        self.decks.push(deck);
+        self.set_selected_deck(self.decks.len() - 1);
+        self.tags_changed = true;
+    }
+
+    /// Start an empty deck, named after how many there are.
+    pub fn new_deck(&mut self) {
+        let name = format!("Deck {}", self.decks.len() + 1);
+        self.push_deck(Deck::new(name));
+    }
+
+    pub fn select_deck(&mut self, index: usize) {
+        if index < self.decks.len() {
+            self.set_selected_deck(index);
+        }
+    }
+
+    fn delete_selected_deck(&mut self) {
+        self.decks.remove(self.selected_deck);
+        self.set_selected_deck(self.selected_deck.saturating_sub(1));
+        self.tags_changed = true;
+    }
+
+    /// Make another deck the active one. Its content is not compared with the previous one's,
+    /// so switching doesn't count as an edit.
+    fn set_selected_deck(&mut self, index: usize) {
+        self.selected_deck = index;
+        self.active_fingerprint = None;
+    }
+
+    /// The decks edited most recently, latest first, with their index.
+    pub fn recent_decks(&self, count: usize) -> Vec<(usize, &Deck)> {
+        let mut decks: Vec<(usize, &Deck)> = self.decks.iter().enumerate().collect();
+        decks.sort_by_key(|(_, deck)| std::cmp::Reverse(deck.modified));
+        decks.truncate(count);
+        decks
+    }
+
+    /// Mark the active deck as edited if its content changed since the last frame.
+    fn track_edits(&mut self) {
+        let selected = self.selected_deck;
+        let Some(deck) = self.decks.get_mut(selected) else {
+            self.active_fingerprint = None;
+            return;
+        };
+        let fingerprint = deck.fingerprint();
+        if let Some((index, previous)) = self.active_fingerprint {
+            if index == selected && previous != fingerprint {
+                deck.modified = now();
+            }
+        }
+        self.active_fingerprint = Some((selected, fingerprint));
+    }
+
+    /// Take in the cards looked up in the background: those of an imported decklist, and saved
+    /// cards that were missing from the disk cache.
+    pub fn receive_cards(&mut self, ctx: &egui::Context) {
+        if let Some((parsed, lookup)) = &mut self.pending_import {
+            let found = lookup.receive();
+            parsed.resolve_found(&found);
+            if lookup.is_finished() {
+                if let Some((parsed, lookup)) = self.pending_import.take() {
+                    self.finish_import(parsed, lookup.error());
+                }
+            }
+        }
+        match &mut self.card_lookup {
+            Some(lookup) => {
+                let found = lookup.receive();
+                for entry in self.decks.iter_mut().flat_map(|d| d.entries.iter_mut()) {
+                    if entry.card.is_none() {
+                        let id = entry.card_id.as_deref();
+                        entry.card = found.iter().find(|c| Some(c.id.as_str()) == id).cloned();
+                    }
+                }
+                if lookup.is_finished() {
+                    if let Some(error) = lookup.error() {
+                        log::warn!("Could not look up the cards of the saved decks: {}", error);
+                    }
+                    self.card_lookup = None;
+                }
+            }
+            None if !self.decks.is_empty() => {
+                let mut ids: Vec<&String> = self
+                    .decks
+                    .iter()
+                    .flat_map(|d| &d.entries)
+                    .filter(|e| e.card.is_none())
+                    .filter_map(|e| e.card_id.as_ref())
+                    .filter(|id| !self.looked_up_ids.contains(*id))
+                    .collect();
+                ids.sort();
+                ids.dedup();
+                if !ids.is_empty() {
+                    // Each id is only tried once a session, found or not, so a failed lookup
+                    // is not started again on the next frame.
+                    self.looked_up_ids
+                        .extend(ids.iter().map(|id| id.to_string()));
+                    let identifiers = ids
+                        .into_iter()
+                        .map(|id| CardIdentifier::Id { id: id.clone() })
+                        .collect();
+                    self.card_lookup = Some(CardLookup::start(ctx, identifiers));
+                }
+            }
+            None => {}
+        }
+    }
+
+    fn finish_import(&mut self, mut parsed: ParsedDecklist, lookup_error: Option<&str>) {
+        parsed.report_unresolved(lookup_error);
+        self.import_diagnostics = parsed.diagnostics;
+        if !parsed.deck.entries.is_empty() {
+            self.push_deck(parsed.deck);
+        }
+    }
+
+    pub fn open_import(&mut self) {
+        self.show_import = true;
+    }
+
+    /// Copy the active deck to the clipboard as a decklist.
+    pub fn export_active_deck(&self, ctx: &egui::Context) {
+        if let Some(deck) = self.decks.get(self.selected_deck) {
+            ctx.copy_text(deck.to_text());
+        }
+    }
+
+    pub fn take_compared(&mut self) -> Vec<Card> {
+        std::mem::take(&mut self.compared)
+    }
+
+    /// The names of every card in every deck.
+    pub fn card_names(&self) -> impl Iterator<Item = &str> {
+        self.decks
+            .iter()
+            .flat_map(|d| d.entries.iter().map(|e| e.name.as_str()))
+    }
+
+    pub fn draw(
+        &mut self,
+        ui: &mut egui::Ui,
+        ctx: &egui::Context,
+        search: &mut CardSearchView,
+        collection: &Collection,
+        symbols: &SymbolCache,
+        price_source: PriceSource,
+    ) {
+        self.show_deck_selector(ui);
+        ui.separator();
+        if self.show_import {
+            self.show_import_window(ctx, collection);
+        }
+        if let Some(deck) = self.decks.get(self.selected_deck) {
+            if self.show_goldfish {
+                self.goldfish.draw(ctx, &mut self.show_goldfish, deck);
+            }
+        }
+        let known_tags = search.known_tags().to_vec();
+        ui.columns(2, |columns| {
+            self.show_search_column(&mut columns[0], search);
+            self.show_deck_column(
+                &mut columns[1],
+                ctx,
+                collection,
+                symbols,
+                price_source,
+                &known_tags,
+            );
+        });
+        self.track_edits();
+    }
+
+    fn show_deck_selector(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let selected_name = self
+                .decks
+                .get(self.selected_deck)
+                .map(|d| d.name.clone())
+                .unwrap_or_else(|| "No deck".to_string());
+            let mut selected = self.selected_deck;
+            egui::ComboBox::from_id_salt("deck_selector")
+                .selected_text(selected_name)
+                .show_ui(ui, |ui| {
+                    for (i, deck) in self.decks.iter().enumerate() {
+                        ui.selectable_value(&mut selected, i, &deck.name);
+                    }
+                });
+            if selected != self.selected_deck {
+                self.set_selected_deck(selected);
+            }
+            if let Some(deck) = self.decks.get_mut(self.selected_deck) {
+                ui.label("Name:");
+                ui.text_edit_singleline(&mut deck.name);
+                if ui.button("Delete deck").clicked() {
+                    self.delete_selected_deck();
+                }
+            }
+            ui.separator();
+            ui.text_edit_singleline(&mut self.new_deck_name);
+            if ui.button("New deck").clicked() {
+                match self.new_deck_name.trim() {
+                    "" => self.new_deck(),
+                    name => self.push_deck(Deck::new(name.to_string())),
+                }
+                self.new_deck_name.clear();
+            }
+            if ui.button("Import decklist").clicked() {
+                self.show_import = true;
+            }
+            if ui.button("Sample hand").clicked() {
+                self.show_goldfish = true;
+            }
+        });
+    }
+
+    fn show_import_window(&mut self, ctx: &egui::Context, collection: &Collection) {
+        let mut open = self.show_import;
+        egui::Window::new("Import decklist")
+            .open(&mut open)
+            .show(ctx, |ui| {
+                ui.label("Paste a decklist from MTG Arena, MTGO or any deck building site.");
+                egui::ScrollArea::vertical()
+                    .max_height(300.0)
+                    .show(ui, |ui| {
+                        ui.text_edit_multiline(&mut self.import_text);
+                    });
+                if let Some((_, lookup)) = &self.pending_import {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.add(
+                            egui::ProgressBar::new(lookup.progress())
+                                .text("Looking up cards on Scryfall"),
+                        );
+                    });
+                } else if ui.button("Import").clicked() {
+                    let mut parsed = parse_decklist(&self.import_text);
+                    let known_cards: Vec<Card> = self
+                        .decks
+                        .iter()
+                        .flat_map(|d| d.entries.iter().filter_map(|e| e.card.clone()))
+                        .chain(collection.known_cards().cloned())
+                        .collect();
+                    let identifiers = parsed.resolve_known(&known_cards);
+                    self.import_text.clear();
+                    self.import_diagnostics.clear();
+                    if identifiers.is_empty() {
+                        self.finish_import(parsed, None);
+                    } else {
+                        self.pending_import = Some((parsed, CardLookup::start(ctx, identifiers)));
+                    }
+                }
+                for diagnostic in &self.import_diagnostics {
+                    let color = match diagnostic.severity {
+                        Severity::Error => Color32::LIGHT_RED,
+                        Severity::Warning => Color32::YELLOW,
+                    };
+                    ui.colored_label(
+                        color,
+                        format!("Line {}: {}", diagnostic.line, diagnostic.message),
+                    );
+                }
+            });
+        self.show_import = open;
+    }
+
+    fn show_search_column(&mut self, ui: &mut egui::Ui, search: &mut CardSearchView) {
+        search.show_search_bar(ui);
+        ui.label("Drag cards into a zone or use the buttons.");
+        let mut added = vec![];
+        egui::ScrollArea::vertical()
+            .id_salt("deck_builder_search")
+            .show(ui, |ui| {
+                for card in search.search_results() {
+                    ui.horizontal(|ui| {
+                        if ui.small_button("+ Deck").clicked() {
+                            added.push((card.clone(), DeckZone::Main));
+                        }
+                        if ui.small_button("+ Side").clicked() {
+                            added.push((card.clone(), DeckZone::Sideboard));
+                        }
+                        if ui.small_button("📌").on_hover_text("Compare").clicked() {
+                            self.compared.push(card.clone());
+                        }
+                        let id = Id::new(("deck_builder_search", &card.id));
+                        ui.dnd_drag_source(id, card.clone(), |ui| {
+                            ui.label(&card.name);
+                        });
+                    });
+                }
+            });
+        for (card, zone) in added {
+            self.add_card(card, zone);
+        }
+    }
+
+    fn show_deck_column(
+        &mut self,
+        ui: &mut egui::Ui,
+        ctx: &egui::Context,
+        collection: &Collection,
+        symbols: &SymbolCache,
+        price_source: PriceSource,
+        known_tags: &[String],
+    ) {
+        let grouping = &mut self.grouping;
+        ui.horizontal(|ui| {
+            ui.label("Group by:");
+            for option in [
+                DeckGrouping::None,
+                DeckGrouping::Type,
+                DeckGrouping::ManaValue,
+                DeckGrouping::Color,
+                DeckGrouping::Tag,
+            ] {
+                ui.selectable_value(grouping, option, option.label());
+            }
+        });
+        let grouping = self.grouping;
+        let other_decks: Vec<&Deck> = self
+            .decks
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != self.selected_deck)
+            .map(|(_, deck)| deck)
+            .collect();
+        let diff = self
+            .decks
+            .get(self.selected_deck)
+            .map(|deck| deck_diff(deck, collection, &other_decks, self.match_level))
+            .unwrap_or_default();
+        let draw_odds = &mut self.draw_odds;
+        let match_level = &mut self.match_level;
+        let compared = &mut self.compared;
+        let tags_changed = &mut self.tags_changed;
+        let Some(deck) = self.decks.get_mut(self.selected_deck) else {
+            ui.label("Create or import a deck to start building.");
+            return;
+        };
+
+        let illegal_cards = show_legality(ui, deck);
+        let value = deck_value(deck, price_source);
+        let value_label = ui.label(format!("Value: {}", price_source.format(value.total)));
+        if value.unpriced_cards > 0 {
+            value_label.on_hover_text(format!(
+                "{} cards have no {} price",
+                value.unpriced_cards,
+                price_source.label()
+            ));
+        }
+        egui::CollapsingHeader::new("Statistics")
+            .id_salt("deck_statistics")
+            .show(ui, |ui| show_statistics(ui, &deck_stats(deck)));
+        egui::CollapsingHeader::new("Draw odds")
+            .id_salt("deck_draw_odds")
+            .show(ui, |ui| show_draw_odds(ui, deck, draw_odds));
+        egui::CollapsingHeader::new("Collection")
+            .id_salt("deck_collection_diff")
+            .show(ui, |ui| {
+                show_collection_diff(ui, ctx, deck, &diff, match_level)
+            });
+        egui::ScrollArea::vertical()
+            .id_salt("deck_builder_zones")
+            .show(ui, |ui| {
+                for zone in DeckZone::ALL {
+                    let context = ZoneContext {
+                        grouping,
+                        illegal_cards: &illegal_cards,
+                        symbols,
+                        known_tags,
+                    };
+                    *tags_changed |= show_zone(ui, deck, zone, &context, compared);
+                }
+            });
+    }
+}
+
+/// Let the user pick the deck's format and list what makes the deck illegal in it. Returns the
+/// names of the cards that break a rule so they can be highlighted.
+fn show_legality(ui: &mut egui::Ui, deck: &mut Deck) -> Vec<String> {
+    ui.horizontal(|ui| {
+        ui.label("Format:");
+        let selected = deck.format.map(|f| f.label()).unwrap_or("Any");
+        egui::ComboBox::from_id_salt("deck_format")
+            .selected_text(selected)
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut deck.format, None, "Any");
+                for format in Format::ALL {
+                    ui.selectable_value(&mut deck.format, Some(format), format.label());
+                }
+            });
+    });
+    let Some(format) = deck.format else {
+        return vec![];
+    };
+    let violations = validate_deck(deck, format);
+    if violations.is_empty() {
+        ui.colored_label(Color32::GREEN, format!("Legal in {}", format.label()));
+        return vec![];
+    }
+    let illegal_cards = violations.iter().filter_map(|v| v.card.clone()).collect();
+    egui::CollapsingHeader::new(
+        RichText::new(format!(
+            "{} problems in {}",
+            violations.len(),
+            format.label()
+        ))
+        .color(Color32::LIGHT_RED),
+    )
+    .id_salt("deck_violations")
+    .show(ui, |ui| {
+        for violation in violations {
+            let color = match violation.kind {
+                ViolationKind::Unresolved => Color32::YELLOW,
+                _ => Color32::LIGHT_RED,
+            };
+            ui.colored_label(color, violation.message);
+        }
+    });
+    illegal_cards
+}
+
+fn show_statistics(ui: &mut egui::Ui, stats: &DeckStats) {
+    ui.label(format!(
+        "{} cards, {} lands (about {} recommended), average mana value {:.2}",
+        stats.total_cards, stats.land_count, stats.recommended_lands, stats.average_mana_value
+    ));
+
+    let creature_bars = stats
+        .curve
+        .iter()
+        .enumerate()
+        .map(|(mv, bucket)| Bar::new(mv as f64, bucket.creatures as f64))
+        .collect();
+    let creatures = BarChart::new(creature_bars)
+        .name("Creatures")
+        .color(Color32::from_rgb(0, 120, 215));
+    let noncreature_bars = stats
+        .curve
+        .iter()
+        .enumerate()
+        .map(|(mv, bucket)| Bar::new(mv as f64, bucket.noncreatures as f64))
+        .collect();
+    let noncreatures = BarChart::new(noncreature_bars)
+        .name("Noncreatures")
+        .color(Color32::from_rgb(215, 120, 0))
+        .stack_on(&[&creatures]);
+    ui.label(format!(
+        "Mana curve ({} and above share the last bar)",
+        CURVE_CAP
+    ));
+    Plot::new("mana_curve")
+        .legend(Legend::default())
+        .height(150.0)
+        .allow_zoom(false)
+        .allow_drag(false)
+        .allow_scroll(false)
+        .show(ui, |plot_ui| {
+            plot_ui.bar_chart(creatures);
+            plot_ui.bar_chart(noncreatures);
+        });
+
+    ui.label("Colored pips / land sources");
+    egui::Grid::new("color_pips").striped(true).show(ui, |ui| {
+        for (i, color) in ManaColor::ALL.iter().enumerate() {
+            if stats.color_pips[i] == 0 && stats.land_sources[i] == 0 {
+                continue;
+            }
+            ui.label(color.name());
+            ui.label(stats.color_pips[i].to_string());
+            ui.label(stats.land_sources[i].to_string());
+            ui.end_row();
+        }
+    });
+
+    ui.label("Card types");
+    egui::Grid::new("type_counts").striped(true).show(ui, |ui| {
+        for (card_type, count) in &stats.type_counts {
+            ui.label(*card_type);
+            ui.label(count.to_string());
+            ui.end_row();
+        }
+    });
+}
+
+/// A table with the odds of having enough cards of a category in hand on each turn.
+fn show_draw_odds(ui: &mut egui::Ui, deck: &Deck, settings: &mut DrawOddsSettings) {
+    ui.horizontal(|ui| {
+        ui.label("At least");
+        ui.add(egui::DragValue::new(&mut settings.wanted).range(1..=20));
+        let selected = match &settings.category {
+            DrawCategory::Lands => "Lands",
+            DrawCategory::Type(_) => "Type",
+            DrawCategory::Card(_) => "Card",
+            DrawCategory::Tag(_) => "Tag",
+        };
+        let tags = known_tags(deck.entries.iter().map(|e| &e.tags));
+        egui::ComboBox::from_id_salt("draw_odds_category")
+            .selected_text(selected)
+            .show_ui(ui, |ui| {
+                if ui.selectable_label(selected == "Lands", "Lands").clicked() {
+                    settings.category = DrawCategory::Lands;
+                }
+                if ui.selectable_label(selected == "Type", "Type").clicked() {
+                    settings.category = DrawCategory::Type("Creature".to_string());
+                }
+                if ui.selectable_label(selected == "Card", "Card").clicked() {
+                    settings.category = DrawCategory::Card(String::new());
+                }
+                if ui.selectable_label(selected == "Tag", "Tag").clicked() {
+                    let tag = tags.first().cloned().unwrap_or_default();
+                    settings.category = DrawCategory::Tag(tag);
+                }
+            });
+        match &mut settings.category {
+            DrawCategory::Lands => {}
+            DrawCategory::Type(text) | DrawCategory::Card(text) => {
+                ui.text_edit_singleline(text);
+            }
+            DrawCategory::Tag(tag) if tags.is_empty() => {
+                ui.weak("Tag the deck's cards first");
+                tag.clear();
+            }
+            DrawCategory::Tag(tag) => {
+                egui::ComboBox::from_id_salt("draw_odds_tag")
+                    .selected_text(tag.as_str())
+                    .show_ui(ui, |ui| {
+                        for known in &tags {
+                            ui.selectable_value(tag, known.clone(), known);
+                        }
+                    });
+            }
+        }
+    });
+    ui.horizontal(|ui| {
+        ui.radio_value(&mut settings.on_the_play, true, "On the play");
+        ui.radio_value(&mut settings.on_the_play, false, "On the draw");
+        ui.label("Mulligans:");
+        ui.add(egui::DragValue::new(&mut settings.max_mulligans).range(0..=6));
+        if settings.max_mulligans > 0 {
+            ui.label("when fewer than");
+            ui.add(egui::DragValue::new(&mut settings.mulligan_below).range(0..=7));
+            ui.label("in hand");
+        }
+    });
+
+    let deck_size = deck.zone_count(DeckZone::Main);
+    let category_count = settings.category.count_in(deck);
+    ui.label(format!(
+        "{} of {} cards are in the category",
+        category_count, deck_size
+    ));
+    egui::Grid::new("draw_odds_table")
+        .striped(true)
+        .show(ui, |ui| {
+            ui.strong("Turn");
+            ui.strong("Cards seen");
+            ui.strong("Probability");
+            ui.end_row();
+            for turn in 0..=10 {
+                let query = DrawQuery {
+                    deck_size,
+                    category_count,
+                    wanted: settings.wanted,
+                    turn,
+                    on_the_play: settings.on_the_play,
+                    max_mulligans: settings.max_mulligans,
+                    mulligan_below: settings.mulligan_below,
+                };
+                if turn == 0 {
+                    ui.label("Opening hand");
+                } else {
+                    ui.label(turn.to_string());
+                }
+                ui.label((OPENING_HAND_SIZE + query.draws()).to_string());
+                ui.label(format!("{:.1}%", draw_probability(&query) * 100.0));
+                ui.end_row();
+            }
+        });
+}
+
+/// What the collection already covers of the deck, and ways to export what is missing.
+fn show_collection_diff(
+    ui: &mut egui::Ui,
+    ctx: &egui::Context,
+    deck: &Deck,
+    diff: &[DiffLine],
+    match_level: &mut MatchLevel,
+) {
+    ui.horizontal(|ui| {
+        for level in [MatchLevel::Oracle, MatchLevel::Printing] {
+            ui.radio_value(match_level, level, level.label());
+        }
+    });
+    let needed: u32 = diff.iter().map(|line| line.needed).sum();
+    let missing: u32 = diff.iter().map(|line| line.missing).sum();
+    ui.label(format!(
+        "Own {} of {} cards, {} missing",
+        needed - missing,
+        needed,
+        missing
+    ));
+    ui.horizontal(|ui| {
+        if ui.button("Copy buy list").clicked() {
+            ctx.copy_text(buy_list(diff));
+        }
+        if ui.button("Copy missing as decklist").clicked() {
+            ctx.copy_text(missing_deck(&deck.name, diff).to_text());
+        }
+    });
+    egui::Grid::new("collection_diff")
+        .striped(true)
+        .show(ui, |ui| {
+            ui.strong("Card");
+            ui.strong("Needed");
+            ui.strong("Owned");
+            ui.strong("Missing");
+            ui.strong("Also in");
+            ui.end_row();
+            for line in diff {
+                let color = if line.missing > 0 {
+                    Color32::LIGHT_RED
+                } else if line.short_because_committed() {
+                    Color32::YELLOW
+                } else {
+                    Color32::GREEN
+                };
+                ui.colored_label(color, &line.entry.name);
+                ui.label(line.needed.to_string());
+                ui.label(line.owned.to_string());
+                ui.label(line.missing.to_string());
+                let committed = line
+                    .committed
+                    .iter()
+                    .map(|(deck_name, quantity)| format!("{} ({})", deck_name, quantity))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let label = ui.label(committed);
+                if line.short_because_committed() {
+                    label.on_hover_text("Not enough copies to build every deck at the same time");
+                }
+                ui.end_row();
+            }
+        });
+}
+
+/// How the zones of a deck are drawn.
+struct ZoneContext<'a> {
+    grouping: DeckGrouping,
+    /// Cards that break the rules of the deck's format, highlighted.
+    illegal_cards: &'a [String],
+    symbols: &'a SymbolCache,
+    /// Tags to suggest when tagging a slot.
+    known_tags: &'a [String],
+}
+
+/// Draw one zone of the deck as a drop target for searched cards and entries of other zones.
+fn show_zone(
+    ui: &mut egui::Ui,
+    deck: &mut Deck,
+    zone: DeckZone,
+    context: &ZoneContext<'_>,
+    compared: &mut Vec<Card>,
+) -> bool {
+    let ZoneContext {
+        grouping,
+        illegal_cards,
+        symbols,
+        known_tags,
+    } = *context;
+    let count = deck.zone_count(zone);
+    let frame = Frame::group(ui.style());
+    let mut tags_changed = false;
+    let (inner, dropped_card) = ui.dnd_drop_zone::<Card, _>(frame, |ui| {
+        ui.set_min_width(ui.available_width());
+        ui.label(RichText::new(format!("{} ({})", zone.label(), count)).strong());
+
+        let mut groups: BTreeMap<(u8, String), Vec<usize>> = BTreeMap::new();
+        for (i, entry) in deck.entries.iter().enumerate() {
+            if entry.zone == zone {
+                groups.entry(grouping.group_of(entry)).or_default().push(i);
+            }
+        }
+
+        let mut removed = None;
+        for ((_, group_name), indices) in groups {
+            if grouping != DeckGrouping::None {
+                let group_count: u32 = indices.iter().map(|&i| deck.entries[i].quantity).sum();
+                ui.label(RichText::new(format!("{} ({})", group_name, group_count)).italics());
+            }
+            for i in indices {
+                let entry = &mut deck.entries[i];
+                ui.horizontal(|ui| {
+                    if ui.small_button("-").clicked() {
+                        entry.quantity -= 1;
+                        if entry.quantity == 0 {
+                            removed = Some(i);
+                        }
+                    }
+                    ui.label(entry.quantity.to_string());
+                    if ui.small_button("+").clicked() {
+                        entry.quantity += 1;
+                    }
+                    let id = Id::new(("deck_entry", i));
+                    ui.dnd_drag_source(id, MovedEntry(i), |ui| {
+                        let mut name = RichText::new(&entry.name);
+                        let card_name = entry.card.as_ref().map_or(&entry.name, |c| &c.name);
+                        if illegal_cards.contains(card_name) {
+                            name = name.color(Color32::LIGHT_RED);
+                        }
+                        let label = ui.label(name);
+                        if entry.card.is_none() {
+                            label.on_hover_text("Not resolved to a Scryfall card");
+                        }
+                    });
+                    if let Some(cost) = entry.card.as_ref().and_then(|c| c.mana_cost.as_deref()) {
+                        symbols.mana_cost(ui, cost);
+                    }
+                    if let Some(card) = &entry.card {
+                        if ui.small_button("📌").on_hover_text("Compare").clicked() {
+                            compared.push(card.clone());
+                        }
+                    }
+                    if edit_tags(ui, ("deck_entry", i), &mut entry.tags, known_tags) {
+                        tags_changed = true;
+                    }
+                });
+            }
+        }
+        if let Some(i) = removed {
+            deck.entries.remove(i);
+            tags_changed = true;
+        }
+    });
+
+    if let Some(card) = dropped_card {
+        deck.add_entry(DeckEntry::from_card((*card).clone(), 1, zone));
+    }
+    if let Some(moved) = inner.response.dnd_release_payload::<MovedEntry>() {
+        if let Some(entry) = deck.entries.get(moved.0) {
+            if entry.zone != zone {
+                let mut entry = deck.entries.remove(moved.0);
+                entry.zone = zone;
+                deck.add_entry(entry);
+            }
+        }
+    }
+    tags_changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deleting_a_deck_is_not_an_edit_of_the_next() {
+        let mut view = DeckBuilderView::default();
+        view.new_deck();
+        view.new_deck();
+        view.decks[0].add_entry(DeckEntry::new("Island".to_string(), 1, DeckZone::Main));
+        view.decks[1].modified = 1;
+        view.select_deck(0);
+        view.track_edits();
+
+        view.delete_selected_deck();
+        view.track_edits();
+        assert_eq!(view.decks[0].modified, 1);
+
+        // Editing the deck that took its place still counts.
+        view.decks[0].add_entry(DeckEntry::new("Forest".to_string(), 1, DeckZone::Main));
+        view.track_edits();
+        assert!(view.decks[0].modified > 1);
+    }
+}
@@ -0,0 +1,285 @@
+use crate::deck::{Deck, DeckEntry, DeckZone};
+use crate::mana::{mana_value, parse_mana_cost, ManaColor};
+use crate::scryfall_models::Card;
+
+/// Mana values at or above this one share the last bucket of the curve.
+pub const CURVE_CAP: usize = 7;
+
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+pub struct CurveBucket {
+    pub creatures: u32,
+    pub noncreatures: u32,
+}
+
+/// Everything the statistics panel shows about a deck. Only the cards you start the game with
+/// are counted: the main deck and the command zone.
+#[derive(Clone, Default, Debug, PartialEq)]
+pub struct DeckStats {
+    /// Nonland cards by mana value, the last bucket holds everything from `CURVE_CAP` up.
+    pub curve: [CurveBucket; CURVE_CAP + 1],
+    /// Colored mana symbols in the costs of the deck, in WUBRG order.
+    pub color_pips: [u32; 5],
+    /// How many lands produce each color, in WUBRG order.
+    pub land_sources: [u32; 5],
+    pub average_mana_value: f32,
+    /// Card type and how many cards have it. A card with several types counts for each.
+    pub type_counts: Vec<(&'static str, u32)>,
+    pub total_cards: u32,
+    pub land_count: u32,
+    pub recommended_lands: u32,
+}
+
+const CARD_TYPES: [&str; 8] = [
+    "Creature",
+    "Instant",
+    "Sorcery",
+    "Artifact",
+    "Enchantment",
+    "Planeswalker",
+    "Battle",
+    "Land",
+];
+
+/// The front face type line, which is what matters while the card is in the library.
+fn front_type_line(card: &Card) -> &str {
+    card.type_line
+        .as_deref()
+        .and_then(|t| t.split(" // ").next())
+        .unwrap_or("")
+}
+
+/// Count the colored symbols of a mana cost such as `{2}{W}{U/B}`. Hybrid symbols count for
+/// each of their colors.
+/// The cost paid to cast a card. Transform and modal double-faced cards only have one on
+/// their faces, and are cast with their front face.
+fn mana_cost(card: &Card) -> &str {
+    card.mana_cost
+        .as_deref()
+        .or_else(|| card.card_faces.first()?.mana_cost.as_deref())
+        .unwrap_or("")
+}
+
+pub fn color_pips(mana_cost: &str) -> [u32; 5] {
+    let mut pips = [0; 5];
+    for symbol in parse_mana_cost(mana_cost) {
+        for color in symbol.colors() {
+            if let Some(i) = ManaColor::ALL.iter().position(|c| *c == color) {
+                pips[i] += 1;
+            }
+        }
+    }
+    pips
+}
+
+pub fn deck_stats(deck: &Deck) -> DeckStats {
+    let entries: Vec<(&DeckEntry, &Card)> = deck
+        .entries
+        .iter()
+        .filter(|e| matches!(e.zone, DeckZone::Main | DeckZone::Commander))
+        .filter_map(|e| e.card.as_ref().map(|c| (e, c)))
+        .collect();
+
+    let mut stats = DeckStats::default();
+    let mut type_counts = [0; CARD_TYPES.len()];
+    let mut mana_value_sum = 0.0;
+    let mut nonland_count = 0;
+
+    for (entry, card) in entries {
+        let quantity = entry.quantity;
+        let type_line = front_type_line(card);
+        stats.total_cards += quantity;
+        for (i, card_type) in CARD_TYPES.iter().enumerate() {
+            if type_line.contains(card_type) {
+                type_counts[i] += quantity;
+            }
+        }
+
+        if type_line.contains("Land") {
+            stats.land_count += quantity;
+            let produced = card.produced_mana.as_deref().unwrap_or_default();
+            for (i, color) in ManaColor::ALL.iter().enumerate() {
+                if produced.iter().any(|p| p == color.letter()) {
+                    stats.land_sources[i] += quantity;
+                }
+            }
+            continue;
+        }
+
+        // Cards saved before scryfall's mana value was kept only have their cost.
+        let mana_value = card.cmc.unwrap_or_else(|| mana_value(mana_cost(card)));
+        mana_value_sum += mana_value * quantity as f32;
+        nonland_count += quantity;
+        let bucket = &mut stats.curve[(mana_value as usize).min(CURVE_CAP)];
+        if type_line.contains("Creature") {
+            bucket.creatures += quantity;
+        } else {
+            bucket.noncreatures += quantity;
+        }
+
+        let pips = color_pips(mana_cost(card));
+        for (total, pip) in stats.color_pips.iter_mut().zip(pips) {
+            *total += pip * quantity;
+        }
+    }
+
+    if nonland_count > 0 {
+        stats.average_mana_value = mana_value_sum / nonland_count as f32;
+    }
+    stats.type_counts = CARD_TYPES
+        .iter()
+        .zip(type_counts)
+        .filter(|(_, count)| *count > 0)
+        .map(|(t, count)| (*t, count))
+        .collect();
+    stats.recommended_lands = recommended_lands(stats.total_cards, stats.average_mana_value);
+    stats
+}
+
+/// Frank Karsten's land count regressions, without the adjustment for cheap card draw and ramp.
+/// Decks of other sizes scale the 60 card formula.
+pub fn recommended_lands(deck_size: u32, average_mana_value: f32) -> u32 {
+    if deck_size == 0 {
+        return 0;
+    }
+    let lands = if deck_size >= 99 {
+        31.42 + 3.13 * average_mana_value
+    } else {
+        (19.59 + 1.90 * average_mana_value) * deck_size as f32 / 60.0
+    };
+    lands.round() as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn entry(name: &str, quantity: u32, zone: DeckZone, fields: serde_json::Value) -> DeckEntry {
+        DeckEntry::from_card(Card::test(name, fields), quantity, zone)
+    }
+
+    fn deck() -> Deck {
+        let mut deck = Deck::new("Izzet".to_string());
+        deck.entries = vec![
+            entry(
+                "Lightning Bolt",
+                4,
+                DeckZone::Main,
+                json!({ "type_line": "Instant", "cmc": 1.0, "mana_cost": "{R}" }),
+            ),
+            entry(
+                "Snapcaster Mage",
+                2,
+                DeckZone::Main,
+                json!({ "type_line": "Creature — Human Wizard", "cmc": 2.0, "mana_cost": "{1}{U}" }),
+            ),
+            entry(
+                "Expansion // Explosion",
+                1,
+                DeckZone::Main,
+                json!({
+                    "type_line": "Instant // Sorcery",
+                    "cmc": 2.0,
+                    "mana_cost": "{U/R}{U/R} // {X}{U}{U}{R}{R}"
+                }),
+            ),
+            // Without a mana value, it is read from the cost.
+            entry(
+                "Emrakul, the Aeons Torn",
+                1,
+                DeckZone::Main,
+                json!({ "type_line": "Legendary Creature — Eldrazi", "mana_cost": "{15}" }),
+            ),
+            entry(
+                "Steam Vents",
+                3,
+                DeckZone::Main,
+                json!({ "type_line": "Land — Island Mountain", "produced_mana": ["U", "R"] }),
+            ),
+            entry(
+                "Island",
+                2,
+                DeckZone::Main,
+                json!({ "type_line": "Basic Land — Island", "produced_mana": ["U"] }),
+            ),
+            // Only the cards you start the game with count.
+            entry(
+                "Pyroblast",
+                3,
+                DeckZone::Sideboard,
+                json!({ "type_line": "Instant", "cmc": 1.0, "mana_cost": "{R}" }),
+            ),
+        ];
+        deck
+    }
+
+    #[test]
+    fn curve_buckets() {
+        let stats = deck_stats(&deck());
+        let bucket = |creatures, noncreatures| CurveBucket {
+            creatures,
+            noncreatures,
+        };
+        assert_eq!(stats.curve[0], bucket(0, 0));
+        assert_eq!(stats.curve[1], bucket(0, 4));
+        assert_eq!(stats.curve[2], bucket(2, 1));
+        // Everything from the cap up shares the last bucket.
+        assert_eq!(stats.curve[CURVE_CAP], bucket(1, 0));
+        assert_eq!(stats.total_cards, 13);
+        assert_eq!(stats.land_count, 5);
+        assert!((stats.average_mana_value - 25.0 / 8.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn color_pips_and_sources() {
+        assert_eq!(color_pips("{2}{W}{W}"), [2, 0, 0, 0, 0]);
+        assert_eq!(color_pips("{U/B}{G/P}{2/R}"), [0, 1, 1, 1, 1]);
+        assert_eq!(color_pips("{X}{C}{5}"), [0; 5]);
+
+        let stats = deck_stats(&deck());
+        // Bolts 4 R, Snapcaster 2 U, Expansion // Explosion 4 U and 4 R.
+        assert_eq!(stats.color_pips, [0, 6, 0, 8, 0]);
+        assert_eq!(stats.land_sources, [0, 5, 0, 3, 0]);
+    }
+
+    #[test]
+    fn double_faced_cards_use_their_front_cost() {
+        let mut deck = Deck::new("Dimir".to_string());
+        deck.entries = vec![entry(
+            "Delver of Secrets // Insectile Aberration",
+            4,
+            DeckZone::Main,
+            json!({
+                "type_line": "Creature — Human Wizard // Creature — Human Insect",
+                "card_faces": [
+                    { "name": "Delver of Secrets", "mana_cost": "{U}" },
+                    { "name": "Insectile Aberration", "mana_cost": "" }
+                ]
+            }),
+        )];
+        let stats = deck_stats(&deck);
+        assert_eq!(stats.color_pips, [0, 4, 0, 0, 0]);
+        assert_eq!(stats.curve[1].creatures, 4);
+    }
+
+    #[test]
+    fn type_counts() {
+        let stats = deck_stats(&deck());
+        assert_eq!(
+            stats.type_counts,
+            [("Creature", 3), ("Instant", 5), ("Land", 5)]
+        );
+    }
+
+    #[test]
+    fn land_recommendation() {
+        assert_eq!(recommended_lands(0, 3.0), 0);
+        // 19.59 + 1.90 * 3
+        assert_eq!(recommended_lands(60, 3.0), 25);
+        // Limited decks scale the 60 card formula.
+        assert_eq!(recommended_lands(40, 2.5), 16);
+        // 31.42 + 3.13 * 3
+        assert_eq!(recommended_lands(100, 3.0), 41);
+        assert_eq!(deck_stats(&deck()).recommended_lands, 6);
+    }
+}
@@ -0,0 +1,472 @@
+use crate::deck::{Deck, DeckEntry, DeckZone};
+use crate::scryfall_models::{Card, CardIdentifier};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// Something worth telling the user about a given line of the decklist.
+#[derive(Clone, Debug)]
+pub struct LineDiagnostic {
+    /// 1 based line number in the original text.
+    pub line: usize,
+    pub severity: Severity,
+    pub message: String,
+}
+
+pub struct ParsedDecklist {
+    pub deck: Deck,
+    /// The line each entry of `deck.entries` was first read from.
+    pub entry_lines: Vec<usize>,
+    pub diagnostics: Vec<LineDiagnostic>,
+}
+
+impl ParsedDecklist {
+    fn add_entry(&mut self, entry: DeckEntry, line: usize) {
+        let entries_before = self.deck.entries.len();
+        self.deck.add_entry(entry);
+        if self.deck.entries.len() > entries_before {
+            self.entry_lines.push(line);
+        }
+    }
+
+    fn diagnose(&mut self, line: usize, severity: Severity, message: String) {
+        self.diagnostics.push(LineDiagnostic {
+            line,
+            severity,
+            message,
+        });
+    }
+
+    /// Attach the cards we already have locally to the entries, and return how to look up
+    /// the rest online, in as few requests as possible.
+    pub fn resolve_known(&mut self, known_cards: &[Card]) -> Vec<CardIdentifier> {
+        let mut identifiers = vec![];
+        for entry in self.deck.entries.iter_mut() {
+            if entry.card.is_some() {
+                continue;
+            }
+            match known_cards
+                .iter()
+                .find(|card| entry_matches_card(entry, card))
+            {
+                Some(card) => entry.card = Some(card.clone()),
+                None => identifiers.push(entry_identifier(entry)),
+            }
+        }
+        identifiers
+    }
+
+    /// Attach cards looked up online to the entries still missing one.
+    pub fn resolve_found(&mut self, found: &[Card]) {
+        for entry in self.deck.entries.iter_mut().filter(|e| e.card.is_none()) {
+            if let Some(card) = found.iter().find(|card| entry_matches_card(entry, card)) {
+                entry.card = Some(card.clone());
+            }
+        }
+    }
+
+    /// Report the entries left without a card once the lookup is over, and why it failed.
+    pub fn report_unresolved(&mut self, lookup_error: Option<&str>) {
+        if let Some(error) = lookup_error {
+            self.diagnose(
+                0,
+                Severity::Error,
+                format!("Could not reach Scryfall: {}", error),
+            );
+        }
+        let unresolved: Vec<(usize, String)> = self
+            .deck
+            .entries
+            .iter()
+            .zip(&self.entry_lines)
+            .filter(|(entry, _)| entry.card.is_none())
+            .map(|(entry, line)| (*line, format!("No card found for \"{}\"", entry.name)))
+            .collect();
+        for (line, message) in unresolved {
+            self.diagnose(line, Severity::Error, message);
+        }
+    }
+}
+
+fn entry_identifier(entry: &DeckEntry) -> CardIdentifier {
+    match (&entry.set, &entry.collector_number) {
+        (Some(set), Some(number)) => CardIdentifier::SetNumber {
+            set: set.to_ascii_lowercase(),
+            collector_number: number.clone(),
+        },
+        (Some(set), None) => CardIdentifier::NameSet {
+            name: entry.name.clone(),
+            set: set.to_ascii_lowercase(),
+        },
+        _ => CardIdentifier::Name {
+            name: entry.name.clone(),
+        },
+    }
+}
+
+/// Whether a card satisfies an entry. Double faced cards may be written with only their front
+/// face name, so "Delver of Secrets" matches "Delver of Secrets // Insectile Aberration".
+fn entry_matches_card(entry: &DeckEntry, card: &Card) -> bool {
+    if let (Some(set), Some(number)) = (&entry.set, &entry.collector_number) {
+        if card.set.eq_ignore_ascii_case(set) && card.collector_number == *number {
+            return true;
+        }
+    }
+    let front_face = card.name.split(" // ").next().unwrap_or(&card.name);
+    let name_matches =
+        card.name.eq_ignore_ascii_case(&entry.name) || front_face.eq_ignore_ascii_case(&entry.name);
+    let set_matches = entry
+        .set
+        .as_ref()
+        .map_or(true, |set| card.set.eq_ignore_ascii_case(set));
+    name_matches && set_matches
+}
+
+/// Parse a decklist in any of the usual text formats: MTG Arena exports
+/// (`4 Lightning Bolt (M10) 146`), MTGO `.txt` files, and what most deck building sites give
+/// when copying a list. Unreadable lines are skipped and reported in the diagnostics.
+pub fn parse_decklist(text: &str) -> ParsedDecklist {
+    let mut parsed = ParsedDecklist {
+        deck: Deck::new("Imported deck".to_string()),
+        entry_lines: vec![],
+        diagnostics: vec![],
+    };
+    let lines: Vec<&str> = text.lines().collect();
+    let sideboard_start = trailing_block_start(&lines);
+    let mut zone = DeckZone::Main;
+    // Arena exports may leave out the "Deck" header after the commander.
+    let mut leave_command_zone = false;
+    let mut in_about_section = false;
+
+    for (index, raw_line) in lines.iter().enumerate() {
+        let line_number = index + 1;
+        let line = raw_line.trim();
+
+        if line.is_empty() {
+            let command_zone = matches!(zone, DeckZone::Commander | DeckZone::Companion);
+            if command_zone && parsed.deck.zone_entries(zone).next().is_some() {
+                leave_command_zone = true;
+            }
+            in_about_section = false;
+            continue;
+        }
+
+        // Comments. Some sites write the section headers as comments, e.g. "// Sideboard".
+        if let Some(comment) = comment(line) {
+            if let Some(new_zone) = DeckZone::from_header(comment) {
+                zone = new_zone;
+                leave_command_zone = false;
+            }
+            continue;
+        }
+
+        if line.eq_ignore_ascii_case("about") {
+            in_about_section = true;
+            continue;
+        }
+        if in_about_section {
+            if let Some(name) = line.strip_prefix("Name ") {
+                parsed.deck.name = name.trim().to_string();
+            }
+            continue;
+        }
+
+        if let Some(new_zone) = DeckZone::from_header(line) {
+            zone = new_zone;
+            leave_command_zone = false;
+            continue;
+        }
+
+        if Some(index) == sideboard_start && zone == DeckZone::Main {
+            zone = DeckZone::Sideboard;
+        }
+        if leave_command_zone {
+            leave_command_zone = false;
+            zone = DeckZone::Main;
+        }
+
+        let (line_zone, card_line) = match line
+            .strip_prefix("SB:")
+            .or_else(|| line.strip_prefix("sb:"))
+        {
+            Some(rest) => (DeckZone::Sideboard, rest.trim()),
+            None => (zone, line),
+        };
+
+        match parse_card_line(card_line, line_zone) {
+            Ok((entry, assumed_quantity)) => {
+                if assumed_quantity {
+                    parsed.diagnose(
+                        line_number,
+                        Severity::Warning,
+                        format!("No quantity given for \"{}\", assuming 1", entry.name),
+                    );
+                }
+                parsed.add_entry(entry, line_number);
+            }
+            Err(message) => parsed.diagnose(line_number, Severity::Error, message),
+        }
+    }
+
+    if parsed.deck.entries.is_empty() {
+        parsed.diagnose(0, Severity::Error, "The decklist has no cards".to_string());
+    }
+    parsed
+}
+
+fn comment(line: &str) -> Option<&str> {
+    line.strip_prefix("//").or_else(|| line.strip_prefix('#'))
+}
+
+/// MTGO and older Arena exports separate the sideboard with a blank line instead of a header.
+/// The index of the first line of that sideboard: the cards after the last blank line, when
+/// cards came before it and no header or comment comes after it.
+fn trailing_block_start(lines: &[&str]) -> Option<usize> {
+    let mut start = None;
+    let mut cards_before = false;
+    let mut blank_line_seen = false;
+    let mut in_about_section = false;
+    for (index, line) in lines.iter().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            blank_line_seen = cards_before;
+            in_about_section = false;
+            continue;
+        }
+        if line.eq_ignore_ascii_case("about") {
+            in_about_section = true;
+        }
+        if in_about_section || comment(line).is_some() || DeckZone::from_header(line).is_some() {
+            start = None;
+            blank_line_seen = false;
+            continue;
+        }
+        if blank_line_seen {
+            start = Some(index);
+            blank_line_seen = false;
+        }
+        cards_before = true;
+    }
+    start
+}
+
+/// Parse `4 Lightning Bolt`, `4x Lightning Bolt` or `4 Lightning Bolt (M10) 146 *F*`.
+/// The returned flag is true when the quantity was missing and 1 was assumed.
+fn parse_card_line(line: &str, zone: DeckZone) -> Result<(DeckEntry, bool), String> {
+    let (quantity, rest, assumed_quantity) = match line.split_once(char::is_whitespace) {
+        Some((first, _))
+            if first.starts_with(['+', '-']) && parse_quantity(&first[1..]).is_some() =>
+        {
+            return Err(format!("\"{}\" is not a valid quantity", first));
+        }
+        Some((first, rest)) => match parse_quantity(first) {
+            Some(quantity) => (quantity, rest.trim(), false),
+            None => (1, line, true),
+        },
+        None => (1, line, true),
+    };
+    // Allow "4 x Lightning Bolt" as well.
+    let rest = match rest.strip_prefix("x ") {
+        Some(stripped) if !assumed_quantity => stripped.trim(),
+        _ => rest,
+    };
+    if quantity == 0 {
+        return Err(format!("Quantity of \"{}\" can not be 0", rest));
+    }
+
+    let (mut rest, foil) = match rest.strip_suffix("*F*") {
+        Some(stripped) => (stripped.trim_end(), true),
+        None => (rest, false),
+    };
+    // Archidekt and others append tags and categories after the card, e.g. "^Ramp^" or "[Ramp]".
+    if let Some(idx) = rest.find(['^', '[']) {
+        rest = rest[..idx].trim_end();
+    }
+
+    let mut entry = DeckEntry::new(String::new(), quantity, zone);
+    entry.foil = foil;
+    match split_printing(rest) {
+        Some((name, set, number)) => {
+            entry.name = name.to_string();
+            entry.set = Some(set.to_ascii_lowercase());
+            entry.collector_number = number.map(str::to_string);
+        }
+        None => entry.name = rest.to_string(),
+    }
+
+    if entry.name.is_empty() || entry.name.chars().all(|c| c.is_ascii_digit()) {
+        return Err(format!("Could not read a card name from \"{}\"", line));
+    }
+    Ok((entry, assumed_quantity))
+}
+
+fn parse_quantity(token: &str) -> Option<u32> {
+    let digits = token
+        .strip_suffix('x')
+        .or_else(|| token.strip_suffix('X'))
+        .unwrap_or(token);
+    // `parse` would accept a sign, as in "+2".
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse().ok()
+}
+
+/// Split `Lightning Bolt (M10) 146` into its name, set code and collector number.
+fn split_printing(text: &str) -> Option<(&str, &str, Option<&str>)> {
+    let open = text.rfind(" (")?;
+    let close = open + text[open..].find(')')?;
+    let set = &text[open + 2..close];
+    let valid_set = (2..=6).contains(&set.len()) && set.chars().all(|c| c.is_ascii_alphanumeric());
+    if !valid_set {
+        return None;
+    }
+    let after = text[close + 1..].split_whitespace().next();
+    Some((text[..open].trim(), set, after))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn zone_names(parsed: &ParsedDecklist, zone: DeckZone) -> Vec<(u32, &str)> {
+        parsed
+            .deck
+            .zone_entries(zone)
+            .map(|e| (e.quantity, e.name.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn arena_export() {
+        let parsed = parse_decklist(
+            "About\nName Mono Red\n\nCommander\n1 Krenko, Mob Boss (DDT) 52\n\n\
+             Deck\n4 Lightning Bolt (M10) 146\n20 Mountain (ANB) 114 *F*\n\n\
+             Sideboard\n2 Smash to Smithereens (ORI) 163\n",
+        );
+        assert!(parsed.diagnostics.is_empty());
+        assert_eq!(parsed.deck.name, "Mono Red");
+        assert_eq!(
+            zone_names(&parsed, DeckZone::Commander),
+            [(1, "Krenko, Mob Boss")]
+        );
+        assert_eq!(
+            zone_names(&parsed, DeckZone::Main),
+            [(4, "Lightning Bolt"), (20, "Mountain")]
+        );
+        assert_eq!(
+            zone_names(&parsed, DeckZone::Sideboard),
+            [(2, "Smash to Smithereens")]
+        );
+        let bolt = parsed.deck.zone_entries(DeckZone::Main).next().unwrap();
+        assert_eq!(bolt.set.as_deref(), Some("m10"));
+        assert_eq!(bolt.collector_number.as_deref(), Some("146"));
+        assert!(!bolt.foil);
+        assert!(
+            parsed
+                .deck
+                .zone_entries(DeckZone::Main)
+                .nth(1)
+                .unwrap()
+                .foil
+        );
+    }
+
+    #[test]
+    fn arena_commander_without_deck_header() {
+        let parsed = parse_decklist("Commander\n1 Krenko, Mob Boss\n\n1 Sol Ring\n");
+        assert_eq!(
+            zone_names(&parsed, DeckZone::Commander),
+            [(1, "Krenko, Mob Boss")]
+        );
+        assert_eq!(zone_names(&parsed, DeckZone::Main), [(1, "Sol Ring")]);
+    }
+
+    #[test]
+    fn mtgo_blank_line_sideboard() {
+        let parsed = parse_decklist("4 Lightning Bolt\n20 Mountain\n\n2 Pyroblast\n");
+        assert_eq!(
+            zone_names(&parsed, DeckZone::Main),
+            [(4, "Lightning Bolt"), (20, "Mountain")]
+        );
+        assert_eq!(zone_names(&parsed, DeckZone::Sideboard), [(2, "Pyroblast")]);
+    }
+
+    #[test]
+    fn only_the_trailing_block_is_the_sideboard() {
+        let parsed = parse_decklist("4 Lightning Bolt\n\n20 Mountain\n\n2 Pyroblast\n");
+        assert_eq!(zone_names(&parsed, DeckZone::Main).len(), 2);
+        assert_eq!(zone_names(&parsed, DeckZone::Sideboard), [(2, "Pyroblast")]);
+    }
+
+    #[test]
+    fn type_group_comments_stay_in_the_main_deck() {
+        let parsed = parse_decklist(
+            "// Creatures\n4 Goblin Guide\n\n// Instants\n4 Lightning Bolt\n\n\
+             // Lands\n20 Mountain\n",
+        );
+        assert_eq!(zone_names(&parsed, DeckZone::Main).len(), 3);
+        assert_eq!(zone_names(&parsed, DeckZone::Sideboard), []);
+    }
+
+    #[test]
+    fn web_lists() {
+        let parsed = parse_decklist(
+            "4x Lightning Bolt\n4 x Goblin Guide ^Aggro^\n1 Sol Ring [Ramp]\n\
+             SB: 2 Pyroblast\n// Sideboard\n1 Red Elemental Blast\n",
+        );
+        assert!(parsed.diagnostics.is_empty());
+        assert_eq!(
+            zone_names(&parsed, DeckZone::Main),
+            [(4, "Lightning Bolt"), (4, "Goblin Guide"), (1, "Sol Ring")]
+        );
+        assert_eq!(
+            zone_names(&parsed, DeckZone::Sideboard),
+            [(2, "Pyroblast"), (1, "Red Elemental Blast")]
+        );
+    }
+
+    #[test]
+    fn set_without_collector_number() {
+        let parsed = parse_decklist("1 Sol Ring (C21)\n");
+        let entry = &parsed.deck.entries[0];
+        assert_eq!(entry.name, "Sol Ring");
+        assert_eq!(entry.set.as_deref(), Some("c21"));
+        assert_eq!(entry.collector_number, None);
+    }
+
+    #[test]
+    fn diagnostics() {
+        let parsed = parse_decklist("Lightning Bolt\n0 Mountain\n+2 Pyroblast\n-1 Shock\n");
+        let lines: Vec<(usize, Severity)> = parsed
+            .diagnostics
+            .iter()
+            .map(|d| (d.line, d.severity))
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                (1, Severity::Warning),
+                (2, Severity::Error),
+                (3, Severity::Error),
+                (4, Severity::Error)
+            ]
+        );
+        assert_eq!(zone_names(&parsed, DeckZone::Main), [(1, "Lightning Bolt")]);
+        let empty = parse_decklist("");
+        assert_eq!(empty.diagnostics.len(), 1);
+        assert_eq!(empty.diagnostics[0].severity, Severity::Error);
+    }
+
+    #[test]
+    fn quantities() {
+        assert_eq!(parse_quantity("4"), Some(4));
+        assert_eq!(parse_quantity("4x"), Some(4));
+        assert_eq!(parse_quantity("+2"), None);
+        assert_eq!(parse_quantity("-2"), None);
+        assert_eq!(parse_quantity("x"), None);
+    }
+}
@@ -0,0 +1,254 @@
+use crate::deck::{Deck, DeckZone};
+use crate::tags::normalize_tag;
+
+pub const OPENING_HAND_SIZE: u32 = 7;
+
+/// The cards whose odds of being drawn we want to know.
+#[derive(Clone, PartialEq, Debug)]
+pub enum DrawCategory {
+    Lands,
+    /// Every card with this word in its type line, e.g. "Creature".
+    Type(String),
+    /// Copies of a single card.
+    Card(String),
+    /// Every card with this tag in the deck, e.g. "ramp".
+    Tag(String),
+}
+
+impl DrawCategory {
+    /// How many cards of the main deck belong to the category.
+    pub fn count_in(&self, deck: &Deck) -> u32 {
+        deck.zone_entries(DeckZone::Main)
+            .filter(|entry| {
+                let type_line = entry
+                    .card
+                    .as_ref()
+                    .and_then(|c| c.type_line.as_deref())
+                    .unwrap_or("");
+                match self {
+                    DrawCategory::Lands => type_line.contains("Land"),
+                    DrawCategory::Type(card_type) => type_line.contains(card_type.as_str()),
+                    DrawCategory::Card(name) => entry.name.eq_ignore_ascii_case(name),
+                    DrawCategory::Tag(tag) => entry.tags.contains(&normalize_tag(tag)),
+                }
+            })
+            .map(|entry| entry.quantity)
+            .sum()
+    }
+}
+
+/// "What are the odds of having `wanted` cards of a category by `turn`".
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct DrawQuery {
+    pub deck_size: u32,
+    /// Cards of the category in the deck.
+    pub category_count: u32,
+    pub wanted: u32,
+    /// Turn 0 only looks at the opening hand.
+    pub turn: u32,
+    pub on_the_play: bool,
+    /// How many times we are willing to mulligan, following the London mulligan rule.
+    pub max_mulligans: u32,
+    /// Opening hands with fewer category cards than this are sent back, as long as mulligans
+    /// are left.
+    pub mulligan_below: u32,
+}
+
+impl DrawQuery {
+    /// Cards drawn after the opening hand up to and including `turn`. The player on the play
+    /// skips their first draw.
+    pub fn draws(&self) -> u32 {
+        if self.on_the_play {
+            self.turn.saturating_sub(1)
+        } else {
+            self.turn
+        }
+    }
+}
+
+/// Number of ways to choose `k` items out of `n`, as a float since it quickly gets huge.
+pub fn choose(n: u32, k: u32) -> f64 {
+    if k > n {
+        return 0.0;
+    }
+    let k = k.min(n - k);
+    (0..k).fold(1.0, |acc, i| acc * (n - i) as f64 / (i + 1) as f64)
+}
+
+/// Probability of drawing exactly `hits` successes when drawing `draws` cards out of
+/// `population` cards of which `successes` are successes.
+pub fn hypergeometric(population: u32, successes: u32, draws: u32, hits: u32) -> f64 {
+    if draws > population || successes > population || hits > draws || hits > successes {
+        return 0.0;
+    }
+    if draws - hits > population - successes {
+        return 0.0;
+    }
+    choose(successes, hits) * choose(population - successes, draws - hits)
+        / choose(population, draws)
+}
+
+/// Probability of drawing at least `wanted` successes.
+pub fn hypergeometric_at_least(population: u32, successes: u32, draws: u32, wanted: u32) -> f64 {
+    let draws = draws.min(population);
+    let below: f64 = (0..wanted)
+        .map(|hits| hypergeometric(population, successes, draws, hits))
+        .sum();
+    (1.0 - below).clamp(0.0, 1.0)
+}
+
+/// Probability of having at least `query.wanted` cards of the category in hand by `query.turn`.
+///
+/// Every mulligan draws a fresh 7 and bottoms one more card; cards outside the category are
+/// always bottomed first. The last allowed hand is always kept.
+pub fn draw_probability(query: &DrawQuery) -> f64 {
+    let population = query.deck_size;
+    let successes = query.category_count.min(population);
+    let hand_size = OPENING_HAND_SIZE.min(population);
+    let library = population - hand_size;
+    let draws = query.draws().min(library);
+
+    let mut probability = 0.0;
+    // Probability of getting to look at the current hand at all.
+    let mut reach = 1.0;
+    for mulligans in 0..=query.max_mulligans {
+        let kept_size = hand_size.saturating_sub(mulligans);
+        let last_hand = mulligans == query.max_mulligans || kept_size == 0;
+        let mut mulligan_chance = 0.0;
+        for in_hand in 0..=hand_size.min(successes) {
+            let chance = hypergeometric(population, successes, hand_size, in_hand);
+            if !last_hand && in_hand < query.mulligan_below {
+                mulligan_chance += chance;
+                continue;
+            }
+            let kept = in_hand.min(kept_size);
+            let still_wanted = query.wanted.saturating_sub(kept);
+            let success =
+                hypergeometric_at_least(library, successes - in_hand, draws, still_wanted);
+            probability += reach * chance * success;
+        }
+        if last_hand {
+            break;
+        }
+        reach *= mulligan_chance;
+    }
+    probability.clamp(0.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deck::DeckEntry;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    fn query(deck_size: u32, category_count: u32, wanted: u32, turn: u32) -> DrawQuery {
+        DrawQuery {
+            deck_size,
+            category_count,
+            wanted,
+            turn,
+            on_the_play: true,
+            max_mulligans: 0,
+            mulligan_below: 0,
+        }
+    }
+
+    #[test]
+    fn binomial_coefficients() {
+        assert_eq!(choose(5, 2), 10.0);
+        assert_eq!(choose(40, 7), 18_643_560.0);
+        assert_eq!(choose(3, 4), 0.0);
+        assert_eq!(choose(7, 0), 1.0);
+    }
+
+    #[test]
+    fn known_hypergeometric_values() {
+        // Exactly 3 lands in a 7 card hand from 60 cards with 24 lands.
+        assert!(close(hypergeometric(60, 24, 7, 3), 0.308_704_256_257_241_6));
+        // At least 2 lands in the opening 7 of a 40 card deck with 17 lands.
+        assert!(close(
+            hypergeometric_at_least(40, 17, 7, 2),
+            0.894_802_494_802_494_9
+        ));
+        assert_eq!(hypergeometric(60, 4, 7, 5), 0.0);
+        assert_eq!(hypergeometric_at_least(60, 4, 7, 0), 1.0);
+        let total: f64 = (0..=7).map(|hits| hypergeometric(60, 24, 7, hits)).sum();
+        assert!(close(total, 1.0));
+    }
+
+    #[test]
+    fn draws_by_turn() {
+        // A 4-of by turn 3 on the draw: 10 cards seen.
+        let mut on_the_draw = query(60, 4, 1, 3);
+        on_the_draw.on_the_play = false;
+        assert_eq!(on_the_draw.draws(), 3);
+        assert!(close(
+            draw_probability(&on_the_draw),
+            0.527_720_528_674_110_7
+        ));
+        // On the play the first draw is skipped.
+        let on_the_play = query(60, 4, 1, 3);
+        assert_eq!(on_the_play.draws(), 2);
+        assert!(close(
+            draw_probability(&on_the_play),
+            hypergeometric_at_least(60, 4, 9, 1)
+        ));
+        assert!(close(
+            draw_probability(&query(40, 17, 2, 0)),
+            hypergeometric_at_least(40, 17, 7, 2)
+        ));
+    }
+
+    #[test]
+    fn london_mulligan() {
+        // Mulligan hands with fewer than 2 lands once: keep the first hand when it has 2,
+        // otherwise take the second one whatever it holds.
+        let mut mulligan = query(60, 24, 2, 0);
+        mulligan.max_mulligans = 1;
+        mulligan.mulligan_below = 2;
+        let keep = hypergeometric_at_least(60, 24, 7, 2);
+        assert!(close(
+            draw_probability(&mulligan),
+            keep + (1.0 - keep) * keep
+        ));
+        assert!(close(draw_probability(&mulligan), 0.979_649_299_927_052_6));
+    }
+
+    #[test]
+    fn mulligans_bottom_other_cards_first() {
+        // A 7 card deck with 3 lands always sends back its first hand. The second hand bottoms
+        // a spell, so all 3 lands are kept.
+        let mut mulligan = query(7, 3, 3, 0);
+        mulligan.max_mulligans = 1;
+        mulligan.mulligan_below = 4;
+        assert!(close(draw_probability(&mulligan), 1.0));
+
+        // With only lands, one has to go to the bottom.
+        let mut all_lands = query(7, 7, 7, 0);
+        all_lands.max_mulligans = 1;
+        all_lands.mulligan_below = 8;
+        assert_eq!(draw_probability(&all_lands), 0.0);
+        all_lands.wanted = 6;
+        assert!(close(draw_probability(&all_lands), 1.0));
+    }
+
+    #[test]
+    fn category_counts() {
+        let mut deck = Deck::new("Test".to_string());
+        let mut ramp = DeckEntry::new("Rampant Growth".to_string(), 4, DeckZone::Main);
+        ramp.tags = vec!["ramp".to_string()];
+        let mut sideboard = DeckEntry::new("Cultivate".to_string(), 2, DeckZone::Sideboard);
+        sideboard.tags = vec!["ramp".to_string()];
+        deck.entries = vec![
+            ramp,
+            sideboard,
+            DeckEntry::new("Forest".to_string(), 20, DeckZone::Main),
+        ];
+        assert_eq!(DrawCategory::Tag("Ramp".to_string()).count_in(&deck), 4);
+        assert_eq!(DrawCategory::Card("forest".to_string()).count_in(&deck), 20);
+        assert_eq!(DrawCategory::Tag("removal".to_string()).count_in(&deck), 0);
+    }
+}
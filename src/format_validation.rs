@@ -0,0 +1,820 @@
+use crate::deck::{Deck, DeckEntry, DeckZone};
+use crate::scryfall_models::Card;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Format {
+    Standard,
+    Pioneer,
+    Modern,
+    Legacy,
+    Vintage,
+    Pauper,
+    Historic,
+    Commander,
+    Brawl,
+    StandardBrawl,
+    Oathbreaker,
+}
+
+impl Format {
+    pub const ALL: [Format; 11] = [
+        Format::Standard,
+        Format::Pioneer,
+        Format::Modern,
+        Format::Legacy,
+        Format::Vintage,
+        Format::Pauper,
+        Format::Historic,
+        Format::Commander,
+        Format::Brawl,
+        Format::StandardBrawl,
+        Format::Oathbreaker,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Format::Standard => "Standard",
+            Format::Pioneer => "Pioneer",
+            Format::Modern => "Modern",
+            Format::Legacy => "Legacy",
+            Format::Vintage => "Vintage",
+            Format::Pauper => "Pauper",
+            Format::Historic => "Historic",
+            Format::Commander => "Commander",
+            Format::Brawl => "Brawl",
+            Format::StandardBrawl => "Standard Brawl",
+            Format::Oathbreaker => "Oathbreaker",
+        }
+    }
+
+    /// The key scryfall uses for this format in a card's `legalities`.
+    pub fn scryfall_key(&self) -> &'static str {
+        match self {
+            Format::Standard => "standard",
+            Format::Pioneer => "pioneer",
+            Format::Modern => "modern",
+            Format::Legacy => "legacy",
+            Format::Vintage => "vintage",
+            Format::Pauper => "pauper",
+            Format::Historic => "historic",
+            Format::Commander => "commander",
+            Format::Brawl => "brawl",
+            Format::StandardBrawl => "standardbrawl",
+            Format::Oathbreaker => "oathbreaker",
+        }
+    }
+
+    /// Formats built around a commander, where the deck is singleton and color identity matters.
+    pub fn has_commander(&self) -> bool {
+        matches!(
+            self,
+            Format::Commander | Format::Brawl | Format::StandardBrawl | Format::Oathbreaker
+        )
+    }
+
+    /// Exact deck size for commander formats, minimum main deck size for the rest. The
+    /// commander zone counts towards it.
+    fn deck_size(&self) -> u32 {
+        match self {
+            Format::Commander | Format::Brawl => 100,
+            _ => 60,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ViolationKind {
+    DeckSize,
+    SideboardSize,
+    TooManyCopies,
+    Banned,
+    NotLegal,
+    Restricted,
+    Commander,
+    ColorIdentity,
+    Companion,
+    Unresolved,
+}
+
+/// A rule of the format the deck does not follow.
+#[derive(Clone, Debug)]
+pub struct Violation {
+    pub kind: ViolationKind,
+    /// The card the violation is about, if it is about a single card.
+    pub card: Option<String>,
+    pub message: String,
+}
+
+impl Violation {
+    fn new(kind: ViolationKind, card: Option<&str>, message: String) -> Self {
+        Self {
+            kind,
+            card: card.map(str::to_string),
+            message,
+        }
+    }
+}
+
+/// Check a deck against the rules of a format. Maybeboard cards are ignored.
+pub fn validate_deck(deck: &Deck, format: Format) -> Vec<Violation> {
+    let mut violations = vec![];
+    let counted: Vec<&DeckEntry> = deck
+        .entries
+        .iter()
+        .filter(|e| e.zone != DeckZone::Maybeboard)
+        .collect();
+
+    for entry in &counted {
+        if entry.card.is_none() {
+            violations.push(Violation::new(
+                ViolationKind::Unresolved,
+                Some(&entry.name),
+                format!(
+                    "{} is not resolved to a card and can not be checked",
+                    entry.name
+                ),
+            ));
+        }
+    }
+
+    check_sizes(deck, format, &mut violations);
+    check_legalities(&counted, format, &mut violations);
+    check_copies(&counted, format, &mut violations);
+    if format.has_commander() {
+        check_commanders(deck, format, &mut violations);
+    }
+    check_companion(deck, format, &mut violations);
+    violations
+}
+
+fn check_sizes(deck: &Deck, format: Format, violations: &mut Vec<Violation>) {
+    let main = deck.zone_count(DeckZone::Main);
+    let sideboard = deck.zone_count(DeckZone::Sideboard);
+    let commanders = deck.zone_count(DeckZone::Commander);
+    let companions = deck.zone_count(DeckZone::Companion);
+    let size = format.deck_size();
+
+    if format.has_commander() {
+        let total = main + commanders;
+        if total != size {
+            violations.push(Violation::new(
+                ViolationKind::DeckSize,
+                None,
+                format!(
+                    "{} decks must have exactly {} cards including the commander, this one has {}",
+                    format.label(),
+                    size,
+                    total
+                ),
+            ));
+        }
+        if sideboard > 0 {
+            violations.push(Violation::new(
+                ViolationKind::SideboardSize,
+                None,
+                format!("{} does not allow a sideboard", format.label()),
+            ));
+        }
+    } else {
+        if main < size {
+            violations.push(Violation::new(
+                ViolationKind::DeckSize,
+                None,
+                format!(
+                    "The main deck needs at least {} cards, it has {}",
+                    size, main
+                ),
+            ));
+        }
+        // The companion lives in the sideboard in constructed formats.
+        if sideboard + companions > 15 {
+            violations.push(Violation::new(
+                ViolationKind::SideboardSize,
+                None,
+                format!(
+                    "The sideboard can have at most 15 cards, it has {}",
+                    sideboard + companions
+                ),
+            ));
+        }
+        if commanders > 0 {
+            violations.push(Violation::new(
+                ViolationKind::Commander,
+                None,
+                format!("{} does not use a commander", format.label()),
+            ));
+        }
+    }
+}
+
+fn check_legalities(entries: &[&DeckEntry], format: Format, violations: &mut Vec<Violation>) {
+    for entry in entries {
+        let Some(card) = &entry.card else {
+            continue;
+        };
+        let legality = card
+            .legalities
+            .get(format.scryfall_key())
+            .map(String::as_str)
+            .unwrap_or("not_legal");
+        let (kind, message) = match legality {
+            "banned" => (ViolationKind::Banned, "is banned"),
+            "not_legal" => (ViolationKind::NotLegal, "is not legal"),
+            _ => continue,
+        };
+        violations.push(Violation::new(
+            kind,
+            Some(&card.name),
+            format!("{} {} in {}", card.name, message, format.label()),
+        ));
+    }
+}
+
+/// How many copies of a card a deck may have regardless of the format's copy limit, for basic
+/// lands and cards like Relentless Rats or Seven Dwarves.
+pub fn copy_limit_exception(card: &Card) -> Option<u32> {
+    let type_line = card.type_line.as_deref().unwrap_or("");
+    if type_line.contains("Basic") && type_line.contains("Land") {
+        return Some(u32::MAX);
+    }
+    let oracle_text = card.oracle_text.as_deref().unwrap_or("");
+    if oracle_text.contains("A deck can have any number of cards named") {
+        return Some(u32::MAX);
+    }
+    let rest = oracle_text.split("A deck can have up to ").nth(1)?;
+    let word = rest.split_whitespace().next()?;
+    let numbers = [
+        "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten",
+    ];
+    numbers
+        .iter()
+        .position(|n| *n == word)
+        .map(|i| i as u32 + 1)
+        .or_else(|| word.parse().ok())
+}
+
+fn check_copies(entries: &[&DeckEntry], format: Format, violations: &mut Vec<Violation>) {
+    let mut copies: HashMap<String, (u32, Option<&Card>)> = HashMap::new();
+    for entry in entries {
+        let name = entry
+            .card
+            .as_ref()
+            .map(|c| c.name.clone())
+            .unwrap_or_else(|| entry.name.clone());
+        let slot = copies.entry(name).or_insert((0, None));
+        slot.0 += entry.quantity;
+        if slot.1.is_none() {
+            slot.1 = entry.card.as_ref();
+        }
+    }
+
+    let default_limit = if format.has_commander() { 1 } else { 4 };
+    let mut names: Vec<&String> = copies.keys().collect();
+    names.sort();
+    for name in names {
+        let (count, card) = copies[name];
+        let restricted = card.is_some_and(|c| {
+            c.legalities.get(format.scryfall_key()).map(String::as_str) == Some("restricted")
+        });
+        let limit = if restricted {
+            1
+        } else {
+            card.and_then(copy_limit_exception).unwrap_or(default_limit)
+        };
+        if count > limit {
+            let kind = if restricted {
+                ViolationKind::Restricted
+            } else {
+                ViolationKind::TooManyCopies
+            };
+            violations.push(Violation::new(
+                kind,
+                Some(name),
+                format!("{} copies of {}, the limit is {}", count, name, limit),
+            ));
+        }
+    }
+}
+
+fn is_type(card: &Card, card_type: &str) -> bool {
+    card.type_line
+        .as_deref()
+        .and_then(|t| t.split(" // ").next())
+        .is_some_and(|t| t.contains(card_type))
+}
+
+fn has_keyword(card: &Card, keyword: &str) -> bool {
+    card.keywords
+        .iter()
+        .any(|k| k.eq_ignore_ascii_case(keyword))
+}
+
+fn can_be_commander(card: &Card, format: Format) -> bool {
+    let oracle_text = card.oracle_text.as_deref().unwrap_or("");
+    if oracle_text.contains("can be your commander") {
+        return true;
+    }
+    let legendary = is_type(card, "Legendary");
+    match format {
+        Format::Brawl | Format::StandardBrawl => {
+            legendary && (is_type(card, "Creature") || is_type(card, "Planeswalker"))
+        }
+        _ => legendary && is_type(card, "Creature"),
+    }
+}
+
+/// Whether two cards can share the command zone through partner, friends forever, backgrounds
+/// or the Doctor's companion.
+fn valid_pair(a: &Card, b: &Card) -> bool {
+    let partner_with = |card: &Card, other: &Card| {
+        card.oracle_text
+            .as_deref()
+            .is_some_and(|t| t.contains(&format!("Partner with {}", other.name)))
+    };
+    let plain_partner = |card: &Card| {
+        has_keyword(card, "Partner")
+            && !card
+                .oracle_text
+                .as_deref()
+                .unwrap_or("")
+                .contains("Partner with")
+    };
+    let background = |card: &Card, other: &Card| {
+        has_keyword(card, "Choose a background") && is_type(other, "Background")
+    };
+    let doctor = |card: &Card, other: &Card| {
+        has_keyword(card, "Doctor's companion") && is_type(other, "Time Lord Doctor")
+    };
+
+    (partner_with(a, b) && partner_with(b, a))
+        || (plain_partner(a) && plain_partner(b))
+        || (has_keyword(a, "Friends forever") && has_keyword(b, "Friends forever"))
+        || background(a, b)
+        || background(b, a)
+        || doctor(a, b)
+        || doctor(b, a)
+}
+
+fn check_commanders(deck: &Deck, format: Format, violations: &mut Vec<Violation>) {
+    if deck.zone_count(DeckZone::Commander) == 0 {
+        violations.push(Violation::new(
+            ViolationKind::Commander,
+            None,
+            format!("{} decks need a commander", format.label()),
+        ));
+        return;
+    }
+    // Without every commander there is no color identity to check against.
+    let unresolved: Vec<&DeckEntry> = deck
+        .zone_entries(DeckZone::Commander)
+        .filter(|e| e.card.is_none())
+        .collect();
+    for entry in &unresolved {
+        violations.push(Violation::new(
+            ViolationKind::Commander,
+            Some(&entry.name),
+            format!(
+                "The commander {} is not resolved to a card, the color identity can not be checked",
+                entry.name
+            ),
+        ));
+    }
+    if !unresolved.is_empty() {
+        return;
+    }
+    let command_zone: Vec<&Card> = deck
+        .zone_entries(DeckZone::Commander)
+        .filter_map(|e| e.card.as_ref())
+        .collect();
+
+    // The cards whose color identity defines the deck's.
+    let leaders: Vec<&Card> = if format == Format::Oathbreaker {
+        check_oathbreaker(&command_zone, violations)
+    } else {
+        match command_zone.as_slice() {
+            [commander] => {
+                if !can_be_commander(commander, format) {
+                    violations.push(Violation::new(
+                        ViolationKind::Commander,
+                        Some(&commander.name),
+                        format!("{} can not be your commander", commander.name),
+                    ));
+                }
+                vec![commander]
+            }
+            [a, b] => {
+                for card in [a, b] {
+                    let is_background = is_type(card, "Background");
+                    if !is_background && !can_be_commander(card, format) {
+                        violations.push(Violation::new(
+                            ViolationKind::Commander,
+                            Some(&card.name),
+                            format!("{} can not be your commander", card.name),
+                        ));
+                    }
+                }
+                if !valid_pair(a, b) {
+                    violations.push(Violation::new(
+                        ViolationKind::Commander,
+                        None,
+                        format!("{} and {} can not be commanders together", a.name, b.name),
+                    ));
+                }
+                vec![a, b]
+            }
+            _ => {
+                violations.push(Violation::new(
+                    ViolationKind::Commander,
+                    None,
+                    "A deck can have at most two commanders".to_string(),
+                ));
+                command_zone.clone()
+            }
+        }
+    };
+
+    let identity: Vec<&String> = leaders.iter().flat_map(|c| &c.color_identity).collect();
+    let checked = deck.entries.iter().filter(|e| {
+        matches!(
+            e.zone,
+            DeckZone::Main | DeckZone::Companion | DeckZone::Commander
+        )
+    });
+    for card in checked.filter_map(|e| e.card.as_ref()) {
+        if let Some(color) = card.color_identity.iter().find(|c| !identity.contains(c)) {
+            violations.push(Violation::new(
+                ViolationKind::ColorIdentity,
+                Some(&card.name),
+                format!(
+                    "{} has {} outside of the commander's color identity",
+                    card.name, color
+                ),
+            ));
+        }
+    }
+}
+
+/// Oathbreaker puts a planeswalker and an instant or sorcery, its signature spell, in the command
+/// zone. Returns the oathbreaker.
+fn check_oathbreaker<'a>(
+    command_zone: &[&'a Card],
+    violations: &mut Vec<Violation>,
+) -> Vec<&'a Card> {
+    let oathbreaker = command_zone.iter().find(|c| is_type(c, "Planeswalker"));
+    let signature_spell = command_zone
+        .iter()
+        .find(|c| is_type(c, "Instant") || is_type(c, "Sorcery"));
+    if command_zone.len() != 2 || oathbreaker.is_none() || signature_spell.is_none() {
+        violations.push(Violation::new(
+            ViolationKind::Commander,
+            None,
+            "The command zone must hold one planeswalker and one instant or sorcery".to_string(),
+        ));
+    }
+    if let (Some(oathbreaker), Some(spell)) = (oathbreaker, signature_spell) {
+        if let Some(color) = spell
+            .color_identity
+            .iter()
+            .find(|c| !oathbreaker.color_identity.contains(c))
+        {
+            violations.push(Violation::new(
+                ViolationKind::ColorIdentity,
+                Some(&spell.name),
+                format!(
+                    "The signature spell {} has {} outside of {}'s color identity",
+                    spell.name, color, oathbreaker.name
+                ),
+            ));
+        }
+    }
+    oathbreaker.map(|c| vec![*c]).unwrap_or_default()
+}
+
+/// Check the companion restriction printed on the card against the deck, for the companions
+/// whose condition can be read from the card data.
+fn check_companion(deck: &Deck, format: Format, violations: &mut Vec<Violation>) {
+    let companions: Vec<&Card> = deck
+        .zone_entries(DeckZone::Companion)
+        .filter_map(|e| e.card.as_ref())
+        .collect();
+    if deck.zone_count(DeckZone::Companion) > 1 {
+        violations.push(Violation::new(
+            ViolationKind::Companion,
+            None,
+            "A deck can only have one companion".to_string(),
+        ));
+    }
+    let Some(companion) = companions.first() else {
+        return;
+    };
+    if !has_keyword(companion, "Companion") {
+        violations.push(Violation::new(
+            ViolationKind::Companion,
+            Some(&companion.name),
+            format!("{} does not have companion", companion.name),
+        ));
+        return;
+    }
+
+    // The starting deck: commanders are part of it, the sideboard is not.
+    let deck_cards: Vec<(&Card, u32)> = deck
+        .entries
+        .iter()
+        .filter(|e| matches!(e.zone, DeckZone::Main | DeckZone::Commander))
+        .filter_map(|e| e.card.as_ref().map(|c| (c, e.quantity)))
+        .collect();
+    let permanents = || {
+        deck_cards
+            .iter()
+            .map(|(c, _)| *c)
+            .filter(|c| !is_type(c, "Instant") && !is_type(c, "Sorcery"))
+    };
+    let non_lands = || {
+        deck_cards
+            .iter()
+            .map(|(c, _)| *c)
+            .filter(|c| !is_type(c, "Land"))
+    };
+    let cmc = |c: &Card| c.cmc.unwrap_or(0.0) as u32;
+
+    let offender: Option<&Card> = match companion.name.as_str() {
+        "Gyruda, Doom of Depths" => non_lands().find(|c| cmc(c) % 2 == 1),
+        "Obosh, the Preypiercer" => non_lands().find(|c| cmc(c) % 2 == 0),
+        "Keruga, the Macrosage" => non_lands().find(|c| cmc(c) < 3),
+        "Lurrus of the Dream-Den" => permanents().find(|c| cmc(c) > 2),
+        "Kaheera, the Orphanguard" => deck_cards.iter().map(|(c, _)| *c).find(|c| {
+            let creature_types = ["Cat", "Elemental", "Nightmare", "Dinosaur", "Beast"];
+            is_type(c, "Creature") && !creature_types.iter().any(|t| is_type(c, t))
+        }),
+        "Lutri, the Spellchaser" => {
+            // Copies of a card may be spread over several printings.
+            let mut copies: HashMap<&str, u32> = HashMap::new();
+            for (card, quantity) in &deck_cards {
+                *copies.entry(card.name.as_str()).or_default() += quantity;
+            }
+            non_lands().find(|c| copies[c.name.as_str()] > 1)
+        }
+        "Jegantha, the Wellspring" => non_lands().find(|c| {
+            let cost = c.mana_cost.as_deref().unwrap_or("");
+            let symbols: Vec<&str> = cost.split('}').filter(|s| !s.is_empty()).collect();
+            symbols.iter().enumerate().any(|(i, s)| {
+                s.trim_start_matches('{').parse::<u32>().is_err() && symbols[..i].contains(s)
+            })
+        }),
+        "Yorion, Sky Nomad" => {
+            let minimum = format.deck_size() + 20;
+            if deck.zone_count(DeckZone::Main) < minimum {
+                violations.push(Violation::new(
+                    ViolationKind::Companion,
+                    Some(&companion.name),
+                    format!("Yorion needs a starting deck of at least {} cards", minimum),
+                ));
+            }
+            None
+        }
+        _ => None,
+    };
+    if let Some(card) = offender {
+        violations.push(Violation::new(
+            ViolationKind::Companion,
+            Some(&card.name),
+            format!(
+                "{} breaks the companion condition of {}",
+                card.name, companion.name
+            ),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn card(name: &str, type_line: &str, identity: &[&str], fields: serde_json::Value) -> Card {
+        let mut card = Card::test(name, fields);
+        card.type_line = Some(type_line.to_string());
+        card.color_identity = identity.iter().map(|c| c.to_string()).collect();
+        for format in Format::ALL {
+            card.legalities
+                .entry(format.scryfall_key().to_string())
+                .or_insert_with(|| "legal".to_string());
+        }
+        card
+    }
+
+    fn entry(card: Card, quantity: u32, zone: DeckZone) -> DeckEntry {
+        DeckEntry::from_card(card, quantity, zone)
+    }
+
+    fn mountain() -> Card {
+        card("Mountain", "Basic Land — Mountain", &[], json!({}))
+    }
+
+    fn kinds(deck: &Deck, format: Format) -> Vec<ViolationKind> {
+        validate_deck(deck, format).iter().map(|v| v.kind).collect()
+    }
+
+    /// A legal 60 card deck: four bolts and basic lands.
+    fn modern_deck() -> Deck {
+        let mut deck = Deck::new("Burn".to_string());
+        let bolt = card("Lightning Bolt", "Instant", &["R"], json!({}));
+        deck.add_entry(entry(bolt, 4, DeckZone::Main));
+        deck.add_entry(entry(mountain(), 56, DeckZone::Main));
+        deck
+    }
+
+    /// A legal commander deck around a mono red commander.
+    fn commander_deck(commanders: Vec<Card>) -> Deck {
+        let mut deck = Deck::new("Goblins".to_string());
+        let count = commanders.len() as u32;
+        for commander in commanders {
+            deck.add_entry(entry(commander, 1, DeckZone::Commander));
+        }
+        deck.add_entry(entry(mountain(), 100 - count, DeckZone::Main));
+        deck
+    }
+
+    fn krenko() -> Card {
+        card(
+            "Krenko, Mob Boss",
+            "Legendary Creature — Goblin",
+            &["R"],
+            json!({}),
+        )
+    }
+
+    fn partner(name: &str, identity: &[&str]) -> Card {
+        card(
+            name,
+            "Legendary Creature — Human",
+            identity,
+            json!({ "keywords": ["Partner"], "oracle_text": "Partner" }),
+        )
+    }
+
+    #[test]
+    fn legal_decks() {
+        assert_eq!(kinds(&modern_deck(), Format::Modern), []);
+        assert_eq!(
+            kinds(&commander_deck(vec![krenko()]), Format::Commander),
+            []
+        );
+    }
+
+    #[test]
+    fn deck_size() {
+        let mut deck = modern_deck();
+        deck.entries[1].quantity = 50;
+        assert_eq!(kinds(&deck, Format::Modern), [ViolationKind::DeckSize]);
+
+        let mut deck = commander_deck(vec![krenko()]);
+        deck.entries[1].quantity = 100;
+        assert_eq!(kinds(&deck, Format::Commander), [ViolationKind::DeckSize]);
+    }
+
+    #[test]
+    fn copy_limits() {
+        let mut deck = modern_deck();
+        deck.entries[0].quantity = 5;
+        deck.entries[1].quantity = 55;
+        assert_eq!(kinds(&deck, Format::Modern), [ViolationKind::TooManyCopies]);
+
+        // Copies are counted by name, across printings.
+        let mut deck = commander_deck(vec![krenko()]);
+        let mut other_printing = card("Sol Ring", "Artifact", &[], json!({}));
+        deck.add_entry(entry(other_printing.clone(), 1, DeckZone::Main));
+        other_printing.id = "Sol Ring 2".to_string();
+        other_printing.set = "c21".to_string();
+        deck.add_entry(entry(other_printing, 1, DeckZone::Main));
+        deck.entries[1].quantity = 97;
+        assert_eq!(
+            kinds(&deck, Format::Commander),
+            [ViolationKind::TooManyCopies]
+        );
+    }
+
+    #[test]
+    fn any_number_of_copies() {
+        let rats = card(
+            "Relentless Rats",
+            "Creature — Rat",
+            &["B"],
+            json!({ "oracle_text": "A deck can have any number of cards named Relentless Rats." }),
+        );
+        let dwarves = card(
+            "Seven Dwarves",
+            "Creature — Dwarf",
+            &["R"],
+            json!({ "oracle_text": "A deck can have up to seven cards named Seven Dwarves." }),
+        );
+        assert_eq!(copy_limit_exception(&rats), Some(u32::MAX));
+        assert_eq!(copy_limit_exception(&dwarves), Some(7));
+        assert_eq!(copy_limit_exception(&mountain()), Some(u32::MAX));
+        assert_eq!(copy_limit_exception(&krenko()), None);
+    }
+
+    #[test]
+    fn banned_and_restricted() {
+        let mut deck = modern_deck();
+        let bolt = deck.entries[0].card.as_mut().unwrap();
+        bolt.legalities
+            .insert("modern".to_string(), "banned".to_string());
+        bolt.legalities
+            .insert("vintage".to_string(), "restricted".to_string());
+        bolt.legalities
+            .insert("pauper".to_string(), "not_legal".to_string());
+        assert_eq!(kinds(&deck, Format::Modern), [ViolationKind::Banned]);
+        assert_eq!(kinds(&deck, Format::Vintage), [ViolationKind::Restricted]);
+        assert_eq!(kinds(&deck, Format::Pauper), [ViolationKind::NotLegal]);
+        deck.entries[0].quantity = 1;
+        deck.entries[1].quantity = 59;
+        assert_eq!(kinds(&deck, Format::Vintage), []);
+    }
+
+    #[test]
+    fn color_identity() {
+        let mut deck = commander_deck(vec![krenko()]);
+        let counterspell = card("Counterspell", "Instant", &["U"], json!({}));
+        deck.add_entry(entry(counterspell, 1, DeckZone::Main));
+        deck.entries[1].quantity = 98;
+        let violations = validate_deck(&deck, Format::Commander);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].kind, ViolationKind::ColorIdentity);
+        assert_eq!(violations[0].card.as_deref(), Some("Counterspell"));
+    }
+
+    #[test]
+    fn partner_pairs() {
+        let pair = vec![
+            partner("Tymna", &["W", "B"]),
+            partner("Thrasios", &["G", "U"]),
+        ];
+        assert_eq!(kinds(&commander_deck(pair), Format::Commander), []);
+
+        let unpaired = vec![partner("Tymna", &["W", "B"]), krenko()];
+        assert_eq!(
+            kinds(&commander_deck(unpaired), Format::Commander),
+            [ViolationKind::Commander]
+        );
+
+        let pir = card(
+            "Pir",
+            "Legendary Creature — Human",
+            &["G"],
+            json!({ "keywords": ["Partner with"], "oracle_text": "Partner with Toothy" }),
+        );
+        let toothy = card(
+            "Toothy",
+            "Legendary Creature — Illusion",
+            &["U"],
+            json!({ "keywords": ["Partner with"], "oracle_text": "Partner with Pir" }),
+        );
+        assert!(valid_pair(&pir, &toothy));
+        assert!(!valid_pair(&pir, &partner("Tymna", &["W", "B"])));
+    }
+
+    #[test]
+    fn commander_problems() {
+        let deck = commander_deck(vec![]);
+        let violations = validate_deck(&deck, Format::Commander);
+        assert!(violations
+            .iter()
+            .any(|v| v.message == "Commander decks need a commander"));
+
+        let mut deck = commander_deck(vec![krenko()]);
+        deck.entries[0].card = None;
+        let violations = validate_deck(&deck, Format::Commander);
+        assert!(violations
+            .iter()
+            .any(|v| v.kind == ViolationKind::Commander && v.message.contains("not resolved")));
+        assert!(!violations.iter().any(|v| v.message.contains("at most two")));
+
+        let bolt = card("Lightning Bolt", "Instant", &["R"], json!({}));
+        let deck = commander_deck(vec![bolt]);
+        assert_eq!(kinds(&deck, Format::Commander), [ViolationKind::Commander]);
+    }
+
+    #[test]
+    fn lutri_counts_copies_by_name() {
+        let lutri = card(
+            "Lutri, the Spellchaser",
+            "Legendary Creature — Elemental Otter",
+            &["U", "R"],
+            json!({ "keywords": ["Companion"] }),
+        );
+        let mut deck = modern_deck();
+        deck.entries[0].quantity = 1;
+        deck.add_entry(entry(lutri, 1, DeckZone::Companion));
+        let mut bolt = deck.entries[0].card.clone().unwrap();
+        deck.entries[1].quantity = 59;
+        assert_eq!(kinds(&deck, Format::Modern), []);
+
+        bolt.id = "Lightning Bolt 2".to_string();
+        bolt.set = "m10".to_string();
+        deck.add_entry(entry(bolt, 1, DeckZone::Main));
+        assert_eq!(kinds(&deck, Format::Modern), [ViolationKind::Companion]);
+    }
+}
@@ -0,0 +1,410 @@
+use crate::deck::{Deck, DeckZone};
+use crate::draw_probability::OPENING_HAND_SIZE;
+use crate::scryfall_models::Card;
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+/// A small seedable random number generator (SplitMix64), so the same seed always gives the
+/// same shuffle.
+#[derive(Clone, Debug)]
+pub struct SeededRng {
+    state: u64,
+}
+
+impl SeededRng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `0..upper`.
+    pub fn below(&mut self, upper: usize) -> usize {
+        (self.next_u64() % upper as u64) as usize
+    }
+
+    /// Fisher-Yates shuffle.
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.below(i + 1);
+            items.swap(i, j);
+        }
+    }
+}
+
+/// One physical copy of a card in the simulated game, with only what the game looks at.
+#[derive(Clone, PartialEq, Debug)]
+pub struct GoldfishCard {
+    pub name: String,
+    pub mana_value: u32,
+    pub is_land: bool,
+    /// Stays on the battlefield once cast, unlike instants and sorceries.
+    pub is_permanent: bool,
+}
+
+impl GoldfishCard {
+    pub fn new(name: &str, card: Option<&Card>) -> Self {
+        // Only the front face of a modal double faced card is played.
+        let type_line = card
+            .and_then(|c| c.type_line.as_deref())
+            .and_then(|t| t.split(" // ").next())
+            .unwrap_or("");
+        Self {
+            name: name.to_string(),
+            mana_value: card.and_then(|c| c.cmc).unwrap_or(0.0) as u32,
+            is_land: type_line.contains("Land"),
+            is_permanent: !type_line.contains("Instant") && !type_line.contains("Sorcery"),
+        }
+    }
+}
+
+/// Expand the main deck into one `GoldfishCard` per copy.
+pub fn deck_cards(deck: &Deck) -> Arc<[GoldfishCard]> {
+    deck.zone_entries(DeckZone::Main)
+        .flat_map(|entry| {
+            let copy = GoldfishCard::new(&entry.name, entry.card.as_ref());
+            std::iter::repeat(copy).take(entry.quantity as usize)
+        })
+        .collect()
+}
+
+/// A game against no opponent, to see how a deck draws and curves out. Colors are ignored:
+/// every land makes one mana of any color.
+pub struct GoldfishGame {
+    /// Shared between the games of a batch.
+    pub cards: Arc<[GoldfishCard]>,
+    /// Indices into `cards`, top of the library first.
+    pub library: VecDeque<usize>,
+    pub hand: Vec<usize>,
+    pub battlefield: Vec<usize>,
+    pub graveyard: Vec<usize>,
+    pub mulligans: u32,
+    /// Cards still to be put on the bottom after the last mulligan.
+    pub to_bottom: u32,
+    /// 0 while deciding whether to keep the opening hand.
+    pub turn: u32,
+    pub on_the_play: bool,
+    pub land_played: bool,
+    pub mana_spent: u32,
+    rng: SeededRng,
+}
+
+impl GoldfishGame {
+    pub fn new(cards: Arc<[GoldfishCard]>, seed: u64, on_the_play: bool) -> Self {
+        let mut game = Self {
+            library: (0..cards.len()).collect(),
+            cards,
+            hand: vec![],
+            battlefield: vec![],
+            graveyard: vec![],
+            mulligans: 0,
+            to_bottom: 0,
+            turn: 0,
+            on_the_play,
+            land_played: false,
+            mana_spent: 0,
+            rng: SeededRng::new(seed),
+        };
+        game.rng.shuffle(game.library.make_contiguous());
+        game.draw(OPENING_HAND_SIZE);
+        game
+    }
+
+    pub fn draw(&mut self, amount: u32) {
+        for _ in 0..amount {
+            let Some(card) = self.library.pop_front() else {
+                return;
+            };
+            self.hand.push(card);
+        }
+    }
+
+    /// London mulligan: shuffle the hand back, draw 7 and put one more card on the bottom than
+    /// the last time.
+    pub fn mulligan(&mut self) {
+        if self.turn > 0 {
+            return;
+        }
+        self.library.extend(self.hand.drain(..));
+        self.rng.shuffle(self.library.make_contiguous());
+        self.draw(OPENING_HAND_SIZE);
+        self.mulligans += 1;
+        self.to_bottom = self.mulligans.min(OPENING_HAND_SIZE);
+    }
+
+    /// Put a card of the hand on the bottom of the library while paying for a mulligan.
+    pub fn bottom(&mut self, hand_index: usize) {
+        if self.to_bottom == 0 || hand_index >= self.hand.len() {
+            return;
+        }
+        let card = self.hand.remove(hand_index);
+        self.library.push_back(card);
+        self.to_bottom -= 1;
+    }
+
+    /// Keep the hand and start the first turn. Only possible once the mulligan is paid.
+    pub fn keep(&mut self) -> bool {
+        if self.turn > 0 || self.to_bottom > 0 {
+            return false;
+        }
+        self.turn = 1;
+        if !self.on_the_play {
+            self.draw(1);
+        }
+        true
+    }
+
+    pub fn next_turn(&mut self) {
+        if self.turn == 0 {
+            return;
+        }
+        self.turn += 1;
+        self.land_played = false;
+        self.mana_spent = 0;
+        self.draw(1);
+    }
+
+    pub fn lands_on_battlefield(&self) -> u32 {
+        self.battlefield
+            .iter()
+            .filter(|&&i| self.cards[i].is_land)
+            .count() as u32
+    }
+
+    pub fn available_mana(&self) -> u32 {
+        self.lands_on_battlefield().saturating_sub(self.mana_spent)
+    }
+
+    /// Play a land or cast a spell from the hand.
+    pub fn play(&mut self, hand_index: usize) -> Result<(), String> {
+        if self.turn == 0 {
+            return Err("Keep a hand first".to_string());
+        }
+        let Some(&card_index) = self.hand.get(hand_index) else {
+            return Err("There is no such card in hand".to_string());
+        };
+        let card = &self.cards[card_index];
+        if card.is_land {
+            if self.land_played {
+                return Err("A land was already played this turn".to_string());
+            }
+            self.land_played = true;
+        } else {
+            let cost = card.mana_value;
+            if cost > self.available_mana() {
+                return Err(format!("{} needs {} mana", card.name, cost));
+            }
+            self.mana_spent += cost;
+        }
+        let card_index = self.hand.remove(hand_index);
+        if self.cards[card_index].is_permanent {
+            self.battlefield.push(card_index);
+        } else {
+            self.graveyard.push(card_index);
+        }
+        Ok(())
+    }
+}
+
+/// What a batch of simulated games measures.
+#[derive(Clone, PartialEq, Debug)]
+pub enum GoldfishGoal {
+    /// Having this many lands on the battlefield.
+    Lands(u32),
+    /// Being able to cast the named card.
+    Cast(String),
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct BatchResult {
+    pub games: u32,
+    /// Fraction of games where the goal was met by `max_turn`.
+    pub success_rate: f32,
+    /// Average turn the goal was met on, over the games where it was met.
+    pub average_turn: Option<f32>,
+}
+
+/// How often a batch reports how many games it played.
+pub const PROGRESS_EVERY: u32 = 1000;
+
+/// Play many games keeping every 7 and playing a land each turn when possible, and record the
+/// turn the goal is first met. `on_progress` gets the number of games played every
+/// `PROGRESS_EVERY` games, and stops the batch early by returning false.
+pub fn simulate(
+    cards: &Arc<[GoldfishCard]>,
+    goal: &GoldfishGoal,
+    games: u32,
+    seed: u64,
+    on_the_play: bool,
+    max_turn: u32,
+    on_progress: &mut dyn FnMut(u32) -> bool,
+) -> BatchResult {
+    let mut seeds = SeededRng::new(seed);
+    let mut played = 0;
+    let mut successes = 0;
+    let mut turn_sum = 0;
+    while played < games {
+        if played > 0 && played % PROGRESS_EVERY == 0 && !on_progress(played) {
+            break;
+        }
+        played += 1;
+        let mut game = GoldfishGame::new(cards.clone(), seeds.next_u64(), on_the_play);
+        game.keep();
+        while game.turn <= max_turn {
+            if let Some(i) = game.hand.iter().position(|&c| game.cards[c].is_land) {
+                let _ = game.play(i);
+            }
+            let met = match goal {
+                GoldfishGoal::Lands(lands) => game.lands_on_battlefield() >= *lands,
+                GoldfishGoal::Cast(name) => game.hand.iter().any(|&c| {
+                    let card = &game.cards[c];
+                    card.name.eq_ignore_ascii_case(name)
+                        && card.mana_value <= game.lands_on_battlefield()
+                }),
+            };
+            if met {
+                successes += 1;
+                turn_sum += game.turn;
+                break;
+            }
+            game.next_turn();
+        }
+    }
+    BatchResult {
+        games: played,
+        success_rate: if played > 0 {
+            successes as f32 / played as f32
+        } else {
+            0.0
+        },
+        average_turn: (successes > 0).then(|| turn_sum as f32 / successes as f32),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cards(lands: usize, spells: usize) -> Arc<[GoldfishCard]> {
+        let land = GoldfishCard {
+            name: "Forest".to_string(),
+            mana_value: 0,
+            is_land: true,
+            is_permanent: true,
+        };
+        let spell = GoldfishCard {
+            name: "Grizzly Bears".to_string(),
+            mana_value: 2,
+            is_land: false,
+            is_permanent: true,
+        };
+        std::iter::repeat(land)
+            .take(lands)
+            .chain(std::iter::repeat(spell).take(spells))
+            .collect()
+    }
+
+    fn run(cards: &Arc<[GoldfishCard]>, goal: &GoldfishGoal, games: u32, seed: u64) -> BatchResult {
+        simulate(cards, goal, games, seed, true, 20, &mut |_| true)
+    }
+
+    #[test]
+    fn same_seed_same_numbers() {
+        let mut a = SeededRng::new(42);
+        let mut b = SeededRng::new(42);
+        let first: Vec<u64> = (0..10).map(|_| a.next_u64()).collect();
+        let second: Vec<u64> = (0..10).map(|_| b.next_u64()).collect();
+        assert_eq!(first, second);
+        assert_ne!(
+            first,
+            (0..10)
+                .map(|_| SeededRng::new(43).next_u64())
+                .collect::<Vec<_>>()
+        );
+        assert!((0..100).all(|_| a.below(6) < 6));
+    }
+
+    #[test]
+    fn shuffle_is_a_permutation() {
+        let mut items: Vec<u32> = (0..60).collect();
+        SeededRng::new(7).shuffle(&mut items);
+        let mut again: Vec<u32> = (0..60).collect();
+        SeededRng::new(7).shuffle(&mut again);
+        assert_eq!(items, again);
+        assert_ne!(items, (0..60).collect::<Vec<_>>());
+        items.sort();
+        assert_eq!(items, (0..60).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn games_follow_the_seed() {
+        let cards = cards(24, 36);
+        let a = GoldfishGame::new(cards.clone(), 5, true);
+        let b = GoldfishGame::new(cards.clone(), 5, true);
+        assert_eq!(a.hand, b.hand);
+        assert_eq!(a.library, b.library);
+        assert_eq!(a.hand.len(), 7);
+        assert_eq!(a.library.len(), 53);
+    }
+
+    #[test]
+    fn mulligan_and_bottom() {
+        let mut game = GoldfishGame::new(cards(24, 36), 3, false);
+        game.mulligan();
+        assert_eq!(game.hand.len(), 7);
+        assert!(!game.keep());
+        let bottomed = game.hand[0];
+        game.bottom(0);
+        assert_eq!(game.library.back(), Some(&bottomed));
+        assert!(game.keep());
+        // On the draw the first turn draws a card.
+        assert_eq!(game.hand.len(), 7);
+        assert_eq!(game.library.len(), 53);
+    }
+
+    #[test]
+    fn simulate_is_deterministic() {
+        let cards = cards(24, 36);
+        let goal = GoldfishGoal::Lands(4);
+        assert_eq!(run(&cards, &goal, 500, 9), run(&cards, &goal, 500, 9));
+        let cast = GoldfishGoal::Cast("grizzly bears".to_string());
+        assert_eq!(run(&cards, &cast, 500, 9), run(&cards, &cast, 500, 9));
+    }
+
+    #[test]
+    fn simulate_known_outcomes() {
+        // Only lands: one a turn, so 4 lands on turn 4 every game.
+        let result = run(&cards(60, 0), &GoldfishGoal::Lands(4), 100, 1);
+        assert_eq!(result.success_rate, 1.0);
+        assert_eq!(result.average_turn, Some(4.0));
+        // No lands at all: never.
+        let result = run(&cards(0, 60), &GoldfishGoal::Lands(1), 100, 1);
+        assert_eq!(result.success_rate, 0.0);
+        assert_eq!(result.average_turn, None);
+    }
+
+    #[test]
+    fn progress_can_stop_the_batch() {
+        let mut reported = vec![];
+        let result = simulate(
+            &cards(24, 36),
+            &GoldfishGoal::Lands(3),
+            5000,
+            1,
+            true,
+            20,
+            &mut |played| {
+                reported.push(played);
+                played < 2000
+            },
+        );
+        assert_eq!(reported, vec![1000, 2000]);
+        assert_eq!(result.games, 2000);
+    }
+}
@@ -0,0 +1,321 @@
+use crate::deck::{Deck, DeckZone};
+use crate::goldfish::{
+    deck_cards, simulate, BatchResult, GoldfishCard, GoldfishGame, GoldfishGoal,
+};
+use crate::settings::Settings;
+use crate::texture_cache::TextureCache;
+use egui::{Image, ImageButton, RichText};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+const THUMBNAIL_WIDTH: f32 = 110.0;
+const MAX_TURN: u32 = 20;
+
+enum BatchProgress {
+    Played(u32),
+    Done(BatchResult),
+}
+
+/// A batch of games played by a background thread, as large batches take a while.
+struct RunningBatch {
+    games: u32,
+    played: u32,
+    progress_rx: Receiver<BatchProgress>,
+}
+
+impl RunningBatch {
+    fn start(
+        ctx: &egui::Context,
+        deck: &Deck,
+        goal: GoldfishGoal,
+        games: u32,
+        seed: u64,
+        on_the_play: bool,
+    ) -> Self {
+        let (progress_tx, progress_rx) = mpsc::channel();
+        let cards = deck_cards(deck);
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            let mut on_progress = |played| {
+                ctx.request_repaint();
+                // Stop once the window no longer waits for this batch.
+                progress_tx.send(BatchProgress::Played(played)).is_ok()
+            };
+            let result = simulate(
+                &cards,
+                &goal,
+                games,
+                seed,
+                on_the_play,
+                MAX_TURN,
+                &mut on_progress,
+            );
+            let _ = progress_tx.send(BatchProgress::Done(result));
+            ctx.request_repaint();
+        });
+        Self {
+            games,
+            played: 0,
+            progress_rx,
+        }
+    }
+
+    /// The result once every game is played.
+    fn receive(&mut self) -> Option<BatchResult> {
+        loop {
+            match self.progress_rx.try_recv() {
+                Ok(BatchProgress::Played(played)) => self.played = played,
+                Ok(BatchProgress::Done(result)) => return Some(result),
+                Err(TryRecvError::Empty | TryRecvError::Disconnected) => return None,
+            }
+        }
+    }
+}
+
+/// A window to draw sample hands from a deck and goldfish them, plus Monte Carlo statistics.
+pub struct GoldfishView {
+    seed: u64,
+    on_the_play: bool,
+    game: Option<GoldfishGame>,
+    message: Option<String>,
+    goal: GoldfishGoal,
+    games: u32,
+    batch_result: Option<BatchResult>,
+    running_batch: Option<RunningBatch>,
+    textures: TextureCache,
+}
+
+impl Default for GoldfishView {
+    fn default() -> Self {
+        Self {
+            seed: 1,
+            on_the_play: true,
+            game: None,
+            message: None,
+            goal: GoldfishGoal::Lands(4),
+            games: 1000,
+            batch_result: None,
+            running_batch: None,
+            textures: TextureCache::default(),
+        }
+    }
+}
+
+/// What the user clicked on a card this frame.
+enum CardAction {
+    Bottom(usize),
+    Play(usize),
+}
+
+impl GoldfishView {
+    pub fn apply_settings(&mut self, settings: &Settings) {
+        self.textures
+            .configure(settings.image_size, settings.images_in_memory);
+    }
+
+    pub fn draw(&mut self, ctx: &egui::Context, open: &mut bool, deck: &Deck) {
+        egui::Window::new(format!("Sample hand: {}", deck.name))
+            .open(open)
+            .default_width(800.0)
+            .show(ctx, |ui| {
+                self.show_controls(ui, deck);
+                ui.separator();
+                self.show_game(ui, ctx, deck);
+                ui.separator();
+                self.show_batch(ui, ctx, deck);
+            });
+    }
+
+    fn show_controls(&mut self, ui: &mut egui::Ui, deck: &Deck) {
+        ui.horizontal(|ui| {
+            ui.label("Seed:");
+            ui.add(egui::DragValue::new(&mut self.seed));
+            ui.radio_value(&mut self.on_the_play, true, "On the play");
+            ui.radio_value(&mut self.on_the_play, false, "On the draw");
+            if ui.button("New hand").clicked() {
+                self.game = Some(GoldfishGame::new(
+                    deck_cards(deck),
+                    self.seed,
+                    self.on_the_play,
+                ));
+                self.message = None;
+                // Next hand should be different unless the user asks for the same seed.
+                self.seed = self.seed.wrapping_add(1);
+            }
+            let Some(game) = &mut self.game else {
+                return;
+            };
+            if game.turn == 0 {
+                if ui.button("Mulligan").clicked() {
+                    game.mulligan();
+                }
+                if ui.button("Keep").clicked() && !game.keep() {
+                    self.message =
+                        Some(format!("Put {} cards on the bottom first", game.to_bottom));
+                }
+            } else if ui.button("Next turn").clicked() {
+                game.next_turn();
+                self.message = None;
+            }
+        });
+    }
+
+    fn show_game(&mut self, ui: &mut egui::Ui, ctx: &egui::Context, deck: &Deck) {
+        let Some(game) = &mut self.game else {
+            ui.label("Draw a new hand to start.");
+            return;
+        };
+        if game.turn == 0 {
+            let text = match game.to_bottom {
+                0 => format!("Opening hand, {} mulligans", game.mulligans),
+                n => format!("Click {} cards to put them on the bottom", n),
+            };
+            ui.label(text);
+        } else {
+            ui.label(format!(
+                "Turn {}, {} cards in library, {} of {} mana available",
+                game.turn,
+                game.library.len(),
+                game.available_mana(),
+                game.lands_on_battlefield()
+            ));
+        }
+        if let Some(message) = &self.message {
+            ui.colored_label(egui::Color32::YELLOW, message);
+        }
+
+        ui.label(RichText::new("Hand").strong());
+        let mut action = None;
+        egui::ScrollArea::horizontal()
+            .id_salt("goldfish_hand")
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    for (position, &card_index) in game.hand.iter().enumerate() {
+                        let card = &game.cards[card_index];
+                        let clicked = show_card(ui, ctx, &mut self.textures, deck, card);
+                        if clicked {
+                            action = Some(if game.turn == 0 {
+                                CardAction::Bottom(position)
+                            } else {
+                                CardAction::Play(position)
+                            });
+                        }
+                    }
+                });
+            });
+        match action {
+            Some(CardAction::Bottom(i)) => game.bottom(i),
+            Some(CardAction::Play(i)) => self.message = game.play(i).err(),
+            None => {}
+        }
+
+        ui.label(RichText::new("Battlefield").strong());
+        egui::ScrollArea::horizontal()
+            .id_salt("goldfish_battlefield")
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    for &card_index in &game.battlefield {
+                        let card = &game.cards[card_index];
+                        show_card(ui, ctx, &mut self.textures, deck, card);
+                    }
+                });
+            });
+    }
+
+    fn show_batch(&mut self, ui: &mut egui::Ui, ctx: &egui::Context, deck: &Deck) {
+        if let Some(result) = self.running_batch.as_mut().and_then(RunningBatch::receive) {
+            self.batch_result = Some(result);
+            self.running_batch = None;
+        }
+        ui.label(RichText::new("Monte Carlo").strong());
+        ui.horizontal(|ui| {
+            ui.label("Average turn to");
+            let is_lands = matches!(self.goal, GoldfishGoal::Lands(_));
+            if ui.radio(is_lands, "have lands").clicked() && !is_lands {
+                self.goal = GoldfishGoal::Lands(4);
+            }
+            if ui.radio(!is_lands, "cast").clicked() && is_lands {
+                self.goal = GoldfishGoal::Cast(String::new());
+            }
+            match &mut self.goal {
+                GoldfishGoal::Lands(lands) => {
+                    ui.add(egui::DragValue::new(lands).range(1..=15));
+                }
+                GoldfishGoal::Cast(name) => {
+                    ui.text_edit_singleline(name);
+                }
+            }
+            ui.label("over");
+            ui.add(egui::DragValue::new(&mut self.games).range(1..=100_000));
+            ui.label("games");
+            if let Some(batch) = &self.running_batch {
+                ui.add(
+                    egui::ProgressBar::new(batch.played as f32 / batch.games as f32)
+                        .desired_width(150.0)
+                        .show_percentage(),
+                );
+                if ui.button("Stop").clicked() {
+                    // Dropping the receiver stops the thread at its next progress report.
+                    self.running_batch = None;
+                }
+            } else if ui.button("Run").clicked() {
+                self.running_batch = Some(RunningBatch::start(
+                    ctx,
+                    deck,
+                    self.goal.clone(),
+                    self.games,
+                    self.seed,
+                    self.on_the_play,
+                ));
+            }
+        });
+        if let Some(result) = &self.batch_result {
+            let average = match result.average_turn {
+                Some(turn) => format!(", on average on turn {:.2}", turn),
+                None => String::new(),
+            };
+            ui.label(format!(
+                "Reached in {:.1}% of {} games by turn {}{}",
+                result.success_rate * 100.0,
+                result.games,
+                MAX_TURN,
+                average
+            ));
+        }
+    }
+}
+
+/// Draw a card image, or its name while the image loads. Returns whether it was clicked.
+fn show_card(
+    ui: &mut egui::Ui,
+    ctx: &egui::Context,
+    textures: &mut TextureCache,
+    deck: &Deck,
+    card: &GoldfishCard,
+) -> bool {
+    let texture = deck
+        .zone_entries(DeckZone::Main)
+        .find(|entry| entry.name == card.name)
+        .and_then(|entry| entry.card.as_ref())
+        .and_then(|c| textures.get(ctx, c));
+    match texture {
+        Some(texture) => ui
+            .add(
+                ImageButton::new(
+                    Image::new(&texture)
+                        .rounding(5.0)
+                        .max_width(THUMBNAIL_WIDTH)
+                        .maintain_aspect_ratio(true),
+                )
+                .frame(false),
+            )
+            .on_hover_text(&card.name)
+            .clicked(),
+        None => ui
+            .add_sized(
+                [THUMBNAIL_WIDTH, THUMBNAIL_WIDTH * 1.4],
+                egui::Button::new(&card.name).wrap(),
+            )
+            .clicked(),
+    }
+}
@@ -0,0 +1,238 @@
+use crate::scryfall_models::Card;
+use crate::settings::{ImageSize, Settings};
+use crate::texture_cache::TextureCache;
+use egui::{Color32, Id, Image, Key, Order, Rect, Sense, Vec2};
+
+const MIN_ZOOM: f32 = 0.5;
+const MAX_ZOOM: f32 = 8.0;
+/// Big images take a lot of memory, so at most this many are kept.
+const IMAGES_KEPT: usize = 20;
+
+/// A full-screen viewer for the large images of a card's printings, with zoom and pan.
+pub struct ImageViewer {
+    open: bool,
+    prints: Vec<Card>,
+    index: usize,
+    image_size: ImageSize,
+    /// How many images are kept in memory.
+    limit: usize,
+    zoom: f32,
+    pan: Vec2,
+    textures: TextureCache,
+}
+
+impl Default for ImageViewer {
+    fn default() -> Self {
+        let mut textures = TextureCache::default();
+        textures.configure(ImageSize::Large, IMAGES_KEPT);
+        Self {
+            open: false,
+            prints: vec![],
+            index: 0,
+            image_size: ImageSize::Large,
+            limit: IMAGES_KEPT,
+            zoom: 1.0,
+            pan: Vec2::ZERO,
+            textures,
+        }
+    }
+}
+
+impl ImageViewer {
+    /// Open with the image size of the settings when the viewer offers it, as small images
+    /// look blurry full screen.
+    pub fn apply_settings(&mut self, settings: &Settings) {
+        if ImageSize::VIEWER.contains(&settings.image_size) {
+            self.image_size = settings.image_size;
+        }
+        self.limit = settings.images_in_memory.min(IMAGES_KEPT);
+        self.textures.configure(self.image_size, self.limit);
+    }
+
+    /// Show `prints`, starting with the one with the id `card_id`.
+    pub fn open(&mut self, prints: Vec<Card>, card_id: &str) {
+        self.index = prints.iter().position(|c| c.id == card_id).unwrap_or(0);
+        self.prints = prints;
+        self.open = !self.prints.is_empty();
+        self.reset_view();
+    }
+
+    fn reset_view(&mut self) {
+        self.zoom = 1.0;
+        self.pan = Vec2::ZERO;
+    }
+
+    fn step(&mut self, forward: bool) {
+        let len = self.prints.len();
+        if len > 0 {
+            self.index = if forward {
+                (self.index + 1) % len
+            } else {
+                (self.index + len - 1) % len
+            };
+            self.reset_view();
+        }
+    }
+
+    fn zoom_by(&mut self, factor: f32) {
+        self.zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+    }
+
+    pub fn draw(&mut self, ctx: &egui::Context) {
+        if !self.open {
+            return;
+        }
+        self.handle_keys(ctx);
+        if !self.open {
+            return;
+        }
+        let screen = ctx.screen_rect();
+        egui::Area::new(Id::new("image_viewer"))
+            .order(Order::Foreground)
+            .fixed_pos(screen.min)
+            .show(ctx, |ui| {
+                ui.set_min_size(screen.size());
+                ui.painter()
+                    .rect_filled(screen, 0.0, Color32::from_black_alpha(235));
+                let bar = ui.horizontal(|ui| self.show_toolbar(ui)).response.rect;
+                let area = Rect::from_min_max(egui::pos2(screen.min.x, bar.max.y), screen.max);
+                self.show_image(ui, ctx, area);
+            });
+    }
+
+    /// Left and Right step through printings, + and - zoom, 0 resets and Escape closes.
+    fn handle_keys(&mut self, ctx: &egui::Context) {
+        let (previous, next, zoom_in, zoom_out, reset, close) = ctx.input(|i| {
+            (
+                i.key_pressed(Key::ArrowLeft),
+                i.key_pressed(Key::ArrowRight),
+                i.key_pressed(Key::Plus) || i.key_pressed(Key::Equals),
+                i.key_pressed(Key::Minus),
+                i.key_pressed(Key::Num0),
+                i.key_pressed(Key::Escape),
+            )
+        });
+        if previous {
+            self.step(false);
+        }
+        if next {
+            self.step(true);
+        }
+        if zoom_in {
+            self.zoom_by(1.25);
+        }
+        if zoom_out {
+            self.zoom_by(0.8);
+        }
+        if reset {
+            self.reset_view();
+        }
+        if close {
+            self.open = false;
+        }
+    }
+
+    fn show_toolbar(&mut self, ui: &mut egui::Ui) {
+        ui.add_space(8.0);
+        if ui
+            .button("⏴")
+            .on_hover_text("Previous printing (←)")
+            .clicked()
+        {
+            self.step(false);
+        }
+        ui.label(format!("{} / {}", self.index + 1, self.prints.len()));
+        if ui.button("⏵").on_hover_text("Next printing (→)").clicked() {
+            self.step(true);
+        }
+        if let Some(card) = self.prints.get(self.index) {
+            ui.strong(&card.name);
+            ui.label(format!(
+                "{} ({}) #{}",
+                card.set_name,
+                card.set.to_uppercase(),
+                card.collector_number
+            ));
+            if let Some(artist) = &card.artist {
+                ui.weak(format!("Illustrated by {}", artist));
+            }
+        }
+        ui.separator();
+        for size in ImageSize::VIEWER {
+            if ui
+                .selectable_label(self.image_size == size, size.label())
+                .clicked()
+            {
+                self.image_size = size;
+                self.textures.configure(size, self.limit);
+                self.reset_view();
+            }
+        }
+        ui.separator();
+        if ui.button("−").on_hover_text("Zoom out (-)").clicked() {
+            self.zoom_by(0.8);
+        }
+        ui.label(format!("{:.0}%", self.zoom * 100.0));
+        if ui.button("+").on_hover_text("Zoom in (+)").clicked() {
+            self.zoom_by(1.25);
+        }
+        if ui.button("Fit").on_hover_text("Reset zoom (0)").clicked() {
+            self.reset_view();
+        }
+        ui.separator();
+        if ui.button("✖ Close").on_hover_text("Escape").clicked() {
+            self.open = false;
+        }
+    }
+
+    /// The image fitted to the area, then zoomed around the pointer and panned by dragging.
+    fn show_image(&mut self, ui: &mut egui::Ui, ctx: &egui::Context, area: Rect) {
+        let response = ui.allocate_rect(area, Sense::click_and_drag());
+        let Some(card) = self.prints.get(self.index) else {
+            return;
+        };
+        let Some(texture) = self.textures.get(ctx, card) else {
+            ui.put(
+                Rect::from_center_size(area.center(), Vec2::splat(48.0)),
+                egui::Spinner::new().size(48.0),
+            );
+            return;
+        };
+
+        if response.hovered() {
+            let (scroll, pinch, pointer) = ui.input(|i| {
+                (
+                    i.smooth_scroll_delta.y,
+                    i.zoom_delta(),
+                    i.pointer.hover_pos(),
+                )
+            });
+            let factor = pinch * (scroll * 0.002).exp();
+            if factor != 1.0 {
+                let old_zoom = self.zoom;
+                self.zoom_by(factor);
+                // Keep the point under the pointer in place.
+                if let Some(pointer) = pointer {
+                    let from_center = pointer - area.center() - self.pan;
+                    self.pan -= from_center * (self.zoom / old_zoom - 1.0);
+                }
+            }
+        }
+        if response.dragged() {
+            self.pan += response.drag_delta();
+        }
+        if response.double_clicked() {
+            self.reset_view();
+        }
+
+        let image_size = texture.size_vec2();
+        let fit = (area.width() / image_size.x)
+            .min(area.height() / image_size.y)
+            .min(1.0);
+        let rect = Rect::from_center_size(area.center() + self.pan, image_size * fit * self.zoom);
+        let painter_clip = ui.clip_rect();
+        ui.set_clip_rect(area);
+        Image::new(&texture).rounding(12.0).paint_at(ui, rect);
+        ui.set_clip_rect(painter_clip);
+    }
+}
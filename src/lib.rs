@@ -1,6 +1,45 @@
 #![warn(clippy::all, rust_2018_idioms)]
 
 mod app;
+mod card_detail_view;
+mod card_lookup;
 mod card_search_view;
+mod collection;
+mod collection_diff;
+mod collection_view;
+mod command_palette;
+mod commands;
+mod comparison;
+mod comparison_view;
+mod dashboard_view;
+mod deck;
+mod deck_builder_view;
+mod deck_stats;
+mod decklist_parser;
+mod draw_probability;
+mod format_validation;
+mod goldfish;
+mod goldfish_view;
+mod image_viewer;
+mod local_cache;
+mod locations;
+mod mana;
+mod price_history;
+mod prices;
+mod prices_view;
+mod print_selection;
+mod router;
+mod rulings;
 mod scryfall_models;
+mod search_history;
+mod search_table;
+mod settings;
+mod settings_view;
+mod storage_view;
+mod symbol_cache;
+mod tag_editor;
+mod tags;
+mod texture_cache;
+mod trade_view;
+mod trades;
 pub use app::TemplateApp;
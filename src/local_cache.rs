@@ -0,0 +1,105 @@
+use crate::scryfall_models::ClientSettings;
+use crate::settings::DataSource;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+/// Name of the folder, inside the user's data directory, where eMTG keeps downloaded data.
+#[cfg(not(target_arch = "wasm32"))]
+const CACHE_APP_ID: &str = "eMTG";
+
+/// Directory holding everything downloaded. There is no file system on the web, so
+/// everything is downloaded again there.
+fn cache_root() -> Option<PathBuf> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        Some(eframe::storage_dir(CACHE_APP_ID)?.join("cache"))
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        None
+    }
+}
+
+/// Directory where downloaded data of a given kind is kept between sessions.
+pub fn cache_dir(kind: &str) -> Option<PathBuf> {
+    let dir = cache_root()?.join(kind);
+    std::fs::create_dir_all(&dir).ok()?;
+    Some(dir)
+}
+
+/// Cards that saved decks refer to by id. They are part of the saved data rather than a
+/// cache, so they are read whatever the data source is and never pruned.
+pub const CARDS_KIND: &str = "cards";
+
+/// A file from the cache, unless the app is set to always download fresh data.
+pub fn read_cached(kind: &str, file_name: &str) -> Option<Vec<u8>> {
+    if ClientSettings::current().data_source == DataSource::Online {
+        return None;
+    }
+    read_kept(kind, file_name)
+}
+
+/// A file from the cache whatever the data source is.
+pub fn read_kept(kind: &str, file_name: &str) -> Option<Vec<u8>> {
+    std::fs::read(cache_dir(kind)?.join(file_name)).ok()
+}
+
+pub fn write_cached(kind: &str, file_name: &str, bytes: &[u8]) {
+    let Some(dir) = cache_dir(kind) else {
+        return;
+    };
+    if let Err(e) = std::fs::write(dir.join(file_name), bytes) {
+        log::warn!("Could not write {} to the cache: {}", file_name, e);
+    }
+}
+
+/// When a cached file was written, if it is in the cache.
+pub fn cached_at(kind: &str, file_name: &str) -> Option<SystemTime> {
+    let path = cache_root()?.join(kind).join(file_name);
+    std::fs::metadata(path).ok()?.modified().ok()
+}
+
+/// Every cached file with its size and when it was written, leaving out the saved cards.
+fn cached_files() -> Vec<(PathBuf, u64, SystemTime)> {
+    let Some(kinds) = cache_root().and_then(|root| std::fs::read_dir(root).ok()) else {
+        return vec![];
+    };
+    kinds
+        .flatten()
+        .filter(|kind| kind.file_name() != CARDS_KIND)
+        .filter_map(|kind| std::fs::read_dir(kind.path()).ok())
+        .flat_map(|files| files.flatten())
+        .filter_map(|file| {
+            let metadata = file.metadata().ok()?;
+            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            metadata
+                .is_file()
+                .then(|| (file.path(), metadata.len(), modified))
+        })
+        .collect()
+}
+
+/// How many bytes the cache takes on disk.
+pub fn cache_size() -> u64 {
+    cached_files().iter().map(|(_, size, _)| size).sum()
+}
+
+/// Delete the oldest files until the cache takes at most `limit` bytes.
+pub fn prune_cache(limit: u64) {
+    let mut files = cached_files();
+    let mut size: u64 = files.iter().map(|(_, size, _)| size).sum();
+    files.sort_by_key(|(_, _, modified)| *modified);
+    for (path, file_size, _) in files {
+        if size <= limit {
+            break;
+        }
+        match std::fs::remove_file(&path) {
+            Ok(()) => size -= file_size,
+            Err(e) => log::warn!("Could not delete {}: {}", path.display(), e),
+        }
+    }
+}
+
+pub fn clear_cache() {
+    prune_cache(0);
+}
@@ -0,0 +1,396 @@
+use crate::collection::{Collection, CollectionEntry};
+use serde::{Deserialize, Serialize};
+
+/// What physically holds cards.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ContainerKind {
+    /// Pages with a grid of slots, one printing per slot.
+    Binder {
+        pages: u32,
+        rows: u32,
+        columns: u32,
+    },
+    /// Rows of cards standing up.
+    Box {
+        rows: u32,
+    },
+    DeckBox,
+}
+
+impl ContainerKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ContainerKind::Binder { .. } => "Binder",
+            ContainerKind::Box { .. } => "Box",
+            ContainerKind::DeckBox => "Deck box",
+        }
+    }
+
+    pub fn icon(&self) -> &'static str {
+        match self {
+            ContainerKind::Binder { .. } => "📒",
+            ContainerKind::Box { .. } => "📦",
+            ContainerKind::DeckBox => "🗃",
+        }
+    }
+}
+
+/// A binder, box or deck box, possibly inside another container.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Container {
+    pub id: u64,
+    pub name: String,
+    pub kind: ContainerKind,
+    #[serde(default)]
+    pub parent: Option<u64>,
+}
+
+/// Every container, as a tree through their parents.
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct Containers {
+    pub list: Vec<Container>,
+    next_id: u64,
+}
+
+impl Containers {
+    /// Add a container and return its id.
+    pub fn add(&mut self, name: &str, kind: ContainerKind, parent: Option<u64>) -> u64 {
+        self.next_id += 1;
+        self.list.push(Container {
+            id: self.next_id,
+            name: name.to_string(),
+            kind,
+            parent,
+        });
+        self.next_id
+    }
+
+    pub fn get(&self, id: u64) -> Option<&Container> {
+        self.list.iter().find(|c| c.id == id)
+    }
+
+    pub fn get_mut(&mut self, id: u64) -> Option<&mut Container> {
+        self.list.iter_mut().find(|c| c.id == id)
+    }
+
+    /// The containers directly inside `parent`, or at the top with None.
+    pub fn children(&self, parent: Option<u64>) -> impl Iterator<Item = &Container> {
+        self.list.iter().filter(move |c| c.parent == parent)
+    }
+
+    /// Whether `id` is `ancestor` or somewhere inside it.
+    pub fn is_inside(&self, id: u64, ancestor: u64) -> bool {
+        let mut current = Some(id);
+        // Bounded in case saved data has a cycle.
+        for _ in 0..=self.list.len() {
+            match current {
+                Some(c) if c == ancestor => return true,
+                Some(c) => current = self.get(c).and_then(|c| c.parent),
+                None => return false,
+            }
+        }
+        false
+    }
+
+    /// Put a container inside another, or at the top with None. Returns false, doing nothing,
+    /// when that would put it inside itself.
+    pub fn move_container(&mut self, id: u64, parent: Option<u64>) -> bool {
+        if parent.is_some_and(|p| self.is_inside(p, id)) {
+            return false;
+        }
+        match self.get_mut(id) {
+            Some(container) => {
+                container.parent = parent;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The names from the outermost container in, e.g. "Shelf › Red box".
+    pub fn path(&self, id: u64) -> String {
+        let mut names = vec![];
+        let mut current = self.get(id);
+        while let Some(container) = current {
+            if names.len() > self.list.len() {
+                break;
+            }
+            names.push(container.name.as_str());
+            current = container.parent.and_then(|p| self.get(p));
+        }
+        names.reverse();
+        names.join(" › ")
+    }
+}
+
+/// Where in a container a card is.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Position {
+    Loose,
+    /// A binder slot, both numbers starting at 1.
+    Slot {
+        page: u32,
+        slot: u32,
+    },
+    /// A box row, starting at 1.
+    Row(u32),
+}
+
+impl Position {
+    pub fn label(&self) -> String {
+        match self {
+            Position::Loose => String::new(),
+            Position::Slot { page, slot } => format!("page {}, slot {}", page, slot),
+            Position::Row(row) => format!("row {}", row),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Location {
+    pub container: u64,
+    pub position: Position,
+}
+
+impl Location {
+    /// E.g. "Shelf › Trade binder, page 2, slot 5".
+    pub fn label(&self, containers: &Containers) -> String {
+        let path = containers.path(self.container);
+        match self.position {
+            Position::Loose => path,
+            position => format!("{}, {}", path, position.label()),
+        }
+    }
+}
+
+/// The entries whose card name contains `name`, ignoring case, with their index.
+pub fn find_card<'a>(collection: &'a Collection, name: &str) -> Vec<(usize, &'a CollectionEntry)> {
+    let name = name.to_lowercase();
+    collection
+        .entries
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| e.card.name.to_lowercase().contains(&name))
+        .collect()
+}
+
+/// The indices of the entries kept directly in a container.
+pub fn contents(collection: &Collection, container: u64) -> Vec<usize> {
+    collection
+        .entries
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| e.location.is_some_and(|l| l.container == container))
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Where the next card put in a container goes: the first empty slot of a binder, the first
+/// row of a box. A full binder takes cards loose.
+pub fn next_position(collection: &Collection, container: u64) -> Position {
+    match collection.containers.get(container).map(|c| c.kind) {
+        Some(ContainerKind::Binder {
+            pages,
+            rows,
+            columns,
+        }) => {
+            let taken: Vec<Position> = contents(collection, container)
+                .into_iter()
+                .filter_map(|i| collection.entries[i].location.map(|l| l.position))
+                .collect();
+            (1..=pages)
+                .flat_map(|page| (1..=rows * columns).map(move |slot| (page, slot)))
+                .map(|(page, slot)| Position::Slot { page, slot })
+                .find(|position| !taken.contains(position))
+                .unwrap_or(Position::Loose)
+        }
+        Some(ContainerKind::Box { .. }) => Position::Row(1),
+        _ => Position::Loose,
+    }
+}
+
+/// Put an entry in a container, at its next free position, or take it out with None.
+pub fn move_entry(collection: &mut Collection, entry: usize, container: Option<u64>) {
+    let location = container.map(|container| Location {
+        container,
+        position: next_position(collection, container),
+    });
+    if let Some(entry) = collection.entries.get_mut(entry) {
+        entry.location = location;
+    }
+}
+
+/// Move everything kept in a container to another one.
+pub fn move_contents(collection: &mut Collection, from: u64, to: u64) {
+    if from == to {
+        return;
+    }
+    for entry in contents(collection, from) {
+        move_entry(collection, entry, Some(to));
+    }
+}
+
+/// Delete a container. What it held, cards and containers, goes to its parent.
+pub fn remove_container(collection: &mut Collection, id: u64) {
+    let Some(parent) = collection.containers.get(id).map(|c| c.parent) else {
+        return;
+    };
+    for entry in contents(collection, id) {
+        move_entry(collection, entry, parent);
+    }
+    for child in collection.containers.list.iter_mut() {
+        if child.parent == Some(id) {
+            child.parent = parent;
+        }
+    }
+    collection.containers.list.retain(|c| c.id != id);
+}
+
+/// The entry in each slot of a binder page, row by row.
+pub fn binder_page(collection: &Collection, container: u64, page: u32) -> Vec<Option<usize>> {
+    let slots_per_page = match collection.containers.get(container).map(|c| c.kind) {
+        Some(ContainerKind::Binder { rows, columns, .. }) => rows * columns,
+        _ => 0,
+    };
+    let mut slots = vec![None; slots_per_page as usize];
+    for i in contents(collection, container) {
+        if let Some(Location {
+            position: Position::Slot { page: p, slot },
+            ..
+        }) = collection.entries[i].location
+        {
+            if p == page && slot >= 1 && slot <= slots_per_page {
+                slots[(slot - 1) as usize] = Some(i);
+            }
+        }
+    }
+    slots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collection::Finish;
+    use crate::scryfall_models::Card;
+    use serde_json::json;
+
+    const BINDER: ContainerKind = ContainerKind::Binder {
+        pages: 2,
+        rows: 1,
+        columns: 2,
+    };
+
+    fn collection(names: &[&str]) -> Collection {
+        let mut collection = Collection::default();
+        for name in names {
+            collection.add(Card::test(name, json!({})), 1, Finish::Nonfoil);
+        }
+        collection
+    }
+
+    #[test]
+    fn container_tree() {
+        let mut containers = Containers::default();
+        let shelf = containers.add("Shelf", ContainerKind::Box { rows: 3 }, None);
+        let binder = containers.add("Trade binder", BINDER, Some(shelf));
+        let deck_box = containers.add("Elves", ContainerKind::DeckBox, Some(binder));
+
+        assert_eq!(containers.path(deck_box), "Shelf › Trade binder › Elves");
+        assert!(containers.is_inside(deck_box, shelf));
+        assert!(!containers.is_inside(shelf, deck_box));
+        assert_eq!(containers.children(None).count(), 1);
+
+        // A container can't go inside itself or what it holds.
+        assert!(!containers.move_container(shelf, Some(deck_box)));
+        assert!(!containers.move_container(shelf, Some(shelf)));
+        assert!(containers.move_container(deck_box, None));
+        assert_eq!(containers.path(deck_box), "Elves");
+        assert!(!containers.move_container(42, None));
+    }
+
+    #[test]
+    fn binders_fill_their_slots_in_order() {
+        let mut collection = collection(&["A", "B", "C", "D", "E"]);
+        let binder = collection.containers.add("Binder", BINDER, None);
+        for entry in 0..5 {
+            move_entry(&mut collection, entry, Some(binder));
+        }
+        let positions: Vec<Position> = collection
+            .entries
+            .iter()
+            .map(|e| e.location.unwrap().position)
+            .collect();
+        assert_eq!(
+            positions,
+            [
+                Position::Slot { page: 1, slot: 1 },
+                Position::Slot { page: 1, slot: 2 },
+                Position::Slot { page: 2, slot: 1 },
+                Position::Slot { page: 2, slot: 2 },
+                // The binder is full.
+                Position::Loose,
+            ]
+        );
+        assert_eq!(binder_page(&collection, binder, 2), [Some(2), Some(3)]);
+
+        // A freed slot is the next one used.
+        move_entry(&mut collection, 1, None);
+        assert_eq!(binder_page(&collection, binder, 1), [Some(0), None]);
+        assert_eq!(
+            next_position(&collection, binder),
+            Position::Slot { page: 1, slot: 2 }
+        );
+        assert_eq!(
+            collection.entries[2]
+                .location
+                .unwrap()
+                .label(&collection.containers),
+            "Binder, page 2, slot 1"
+        );
+    }
+
+    #[test]
+    fn removing_a_container_moves_what_it_held_up() {
+        let mut collection = collection(&["Lightning Bolt", "Counterspell"]);
+        let shelf = collection
+            .containers
+            .add("Shelf", ContainerKind::Box { rows: 2 }, None);
+        let deck_box = collection
+            .containers
+            .add("Burn", ContainerKind::DeckBox, Some(shelf));
+        let inner = collection
+            .containers
+            .add("Sideboard", ContainerKind::DeckBox, Some(deck_box));
+        move_entry(&mut collection, 0, Some(deck_box));
+        move_entry(&mut collection, 1, Some(inner));
+
+        remove_container(&mut collection, deck_box);
+        assert!(collection.containers.get(deck_box).is_none());
+        assert_eq!(
+            collection.containers.get(inner).unwrap().parent,
+            Some(shelf)
+        );
+        assert_eq!(
+            collection.entries[0].location,
+            Some(Location {
+                container: shelf,
+                position: Position::Row(1)
+            })
+        );
+
+        move_contents(&mut collection, inner, shelf);
+        assert_eq!(contents(&collection, shelf), [0, 1]);
+        assert!(contents(&collection, inner).is_empty());
+    }
+
+    #[test]
+    fn finding_cards_by_name() {
+        let collection = collection(&["Lightning Bolt", "Lightning Helix", "Counterspell"]);
+        let found: Vec<usize> = find_card(&collection, "LIGHTNING")
+            .into_iter()
+            .map(|(i, _)| i)
+            .collect();
+        assert_eq!(found, [0, 1]);
+        assert!(find_card(&collection, "bolt ").is_empty());
+    }
+}
@@ -0,0 +1,313 @@
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum ManaColor {
+    White,
+    Blue,
+    Black,
+    Red,
+    Green,
+}
+
+impl ManaColor {
+    /// In WUBRG order, the order colors are always listed in.
+    pub const ALL: [ManaColor; 5] = [
+        ManaColor::White,
+        ManaColor::Blue,
+        ManaColor::Black,
+        ManaColor::Red,
+        ManaColor::Green,
+    ];
+
+    pub fn from_letter(letter: &str) -> Option<ManaColor> {
+        match letter {
+            "W" => Some(ManaColor::White),
+            "U" => Some(ManaColor::Blue),
+            "B" => Some(ManaColor::Black),
+            "R" => Some(ManaColor::Red),
+            "G" => Some(ManaColor::Green),
+            _ => None,
+        }
+    }
+
+    pub fn letter(&self) -> &'static str {
+        match self {
+            ManaColor::White => "W",
+            ManaColor::Blue => "U",
+            ManaColor::Black => "B",
+            ManaColor::Red => "R",
+            ManaColor::Green => "G",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ManaColor::White => "White",
+            ManaColor::Blue => "Blue",
+            ManaColor::Black => "Black",
+            ManaColor::Red => "Red",
+            ManaColor::Green => "Green",
+        }
+    }
+}
+
+/// A single `{...}` symbol as printed on cards.
+#[derive(Clone, PartialEq, Debug)]
+pub enum ManaSymbol {
+    Generic(u32),
+    Colored(ManaColor),
+    Colorless,
+    /// `{W/U}`
+    Hybrid(ManaColor, ManaColor),
+    /// `{2/W}`, payable with the color or with generic mana.
+    GenericHybrid(u32, ManaColor),
+    /// `{C/W}`
+    ColorlessHybrid(ManaColor),
+    /// `{W/P}`
+    Phyrexian(ManaColor),
+    /// `{W/U/P}`
+    HybridPhyrexian(ManaColor, ManaColor),
+    Snow,
+    /// `{X}`, `{Y}` and `{Z}`.
+    Variable(char),
+    /// `{½}`, or `{HW}` and `{HR}` from the Unhinged cards.
+    Half(Option<ManaColor>),
+    Infinity,
+    Tap,
+    Untap,
+    Energy,
+    /// Anything else that shows up between braces, like `{PW}` or `{CHAOS}`.
+    Other(String),
+}
+
+impl ManaSymbol {
+    /// Parse the inside of a symbol, without the braces.
+    pub fn parse(symbol: &str) -> ManaSymbol {
+        let symbol = symbol.trim().to_ascii_uppercase();
+        if let Ok(amount) = symbol.parse() {
+            return ManaSymbol::Generic(amount);
+        }
+        if let Some(color) = ManaColor::from_letter(&symbol) {
+            return ManaSymbol::Colored(color);
+        }
+        match symbol.as_str() {
+            "C" => return ManaSymbol::Colorless,
+            "S" => return ManaSymbol::Snow,
+            "X" | "Y" | "Z" => return ManaSymbol::Variable(symbol.chars().next().unwrap_or('X')),
+            "½" => return ManaSymbol::Half(None),
+            "∞" => return ManaSymbol::Infinity,
+            "T" => return ManaSymbol::Tap,
+            "Q" => return ManaSymbol::Untap,
+            "E" => return ManaSymbol::Energy,
+            _ => {}
+        }
+        if let Some(color) = symbol.strip_prefix('H').and_then(ManaColor::from_letter) {
+            return ManaSymbol::Half(Some(color));
+        }
+
+        let parts: Vec<&str> = symbol.split('/').collect();
+        let colors: Vec<Option<ManaColor>> =
+            parts.iter().map(|p| ManaColor::from_letter(p)).collect();
+        match (parts.as_slice(), colors.as_slice()) {
+            ([_, "P"], [Some(color), _]) => ManaSymbol::Phyrexian(*color),
+            ([_, _, "P"], [Some(a), Some(b), _]) => ManaSymbol::HybridPhyrexian(*a, *b),
+            (_, [Some(a), Some(b)]) => ManaSymbol::Hybrid(*a, *b),
+            (["C", _], [_, Some(color)]) => ManaSymbol::ColorlessHybrid(*color),
+            ([amount, _], [None, Some(color)]) => match amount.parse() {
+                Ok(amount) => ManaSymbol::GenericHybrid(amount, *color),
+                Err(_) => ManaSymbol::Other(symbol),
+            },
+            _ => ManaSymbol::Other(symbol),
+        }
+    }
+
+    /// How much this symbol adds to a card's mana value.
+    pub fn mana_value(&self) -> f32 {
+        match self {
+            ManaSymbol::Generic(amount) => *amount as f32,
+            ManaSymbol::GenericHybrid(amount, _) => *amount as f32,
+            ManaSymbol::Colored(_)
+            | ManaSymbol::Colorless
+            | ManaSymbol::Hybrid(..)
+            | ManaSymbol::ColorlessHybrid(_)
+            | ManaSymbol::Phyrexian(_)
+            | ManaSymbol::HybridPhyrexian(..)
+            | ManaSymbol::Snow => 1.0,
+            ManaSymbol::Half(_) => 0.5,
+            ManaSymbol::Infinity => f32::INFINITY,
+            ManaSymbol::Variable(_)
+            | ManaSymbol::Tap
+            | ManaSymbol::Untap
+            | ManaSymbol::Energy
+            | ManaSymbol::Other(_) => 0.0,
+        }
+    }
+
+    /// The colors this symbol gives a card that has it in its mana cost.
+    pub fn colors(&self) -> Vec<ManaColor> {
+        match self {
+            ManaSymbol::Colored(color)
+            | ManaSymbol::GenericHybrid(_, color)
+            | ManaSymbol::ColorlessHybrid(color)
+            | ManaSymbol::Phyrexian(color)
+            | ManaSymbol::Half(Some(color)) => vec![*color],
+            ManaSymbol::Hybrid(a, b) | ManaSymbol::HybridPhyrexian(a, b) => vec![*a, *b],
+            _ => vec![],
+        }
+    }
+
+    /// The symbol written the way scryfall writes it, braces included.
+    pub fn text(&self) -> String {
+        let inner = match self {
+            ManaSymbol::Generic(amount) => amount.to_string(),
+            ManaSymbol::Colored(color) => color.letter().to_string(),
+            ManaSymbol::Colorless => "C".to_string(),
+            ManaSymbol::Hybrid(a, b) => format!("{}/{}", a.letter(), b.letter()),
+            ManaSymbol::GenericHybrid(amount, color) => format!("{}/{}", amount, color.letter()),
+            ManaSymbol::ColorlessHybrid(color) => format!("C/{}", color.letter()),
+            ManaSymbol::Phyrexian(color) => format!("{}/P", color.letter()),
+            ManaSymbol::HybridPhyrexian(a, b) => format!("{}/{}/P", a.letter(), b.letter()),
+            ManaSymbol::Snow => "S".to_string(),
+            ManaSymbol::Variable(letter) => letter.to_string(),
+            ManaSymbol::Half(None) => "½".to_string(),
+            ManaSymbol::Half(Some(color)) => format!("H{}", color.letter()),
+            ManaSymbol::Infinity => "∞".to_string(),
+            ManaSymbol::Tap => "T".to_string(),
+            ManaSymbol::Untap => "Q".to_string(),
+            ManaSymbol::Energy => "E".to_string(),
+            ManaSymbol::Other(symbol) => symbol.clone(),
+        };
+        format!("{{{}}}", inner)
+    }
+}
+
+/// A piece of rules text: either plain text or a symbol.
+#[derive(Clone, PartialEq, Debug)]
+pub enum TextSegment<'a> {
+    Text(&'a str),
+    Symbol(ManaSymbol),
+}
+
+/// Split text such as `{T}: Add {G}.` into plain text and symbols.
+pub fn parse_symbol_text(text: &str) -> Vec<TextSegment<'_>> {
+    let mut segments = vec![];
+    let mut rest = text;
+    while let Some(open) = rest.find('{') {
+        let Some(close) = rest[open..].find('}').map(|i| open + i) else {
+            break;
+        };
+        if open > 0 {
+            segments.push(TextSegment::Text(&rest[..open]));
+        }
+        segments.push(TextSegment::Symbol(ManaSymbol::parse(
+            &rest[open + 1..close],
+        )));
+        rest = &rest[close + 1..];
+    }
+    if !rest.is_empty() {
+        segments.push(TextSegment::Text(rest));
+    }
+    segments
+}
+
+pub fn parse_mana_cost(cost: &str) -> Vec<ManaSymbol> {
+    parse_symbol_text(cost)
+        .into_iter()
+        .filter_map(|segment| match segment {
+            TextSegment::Symbol(symbol) => Some(symbol),
+            TextSegment::Text(_) => None,
+        })
+        .collect()
+}
+
+pub fn mana_value(cost: &str) -> f32 {
+    parse_mana_cost(cost)
+        .iter()
+        .map(ManaSymbol::mana_value)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ManaColor::*;
+
+    #[test]
+    fn parse_symbols() {
+        assert_eq!(ManaSymbol::parse("3"), ManaSymbol::Generic(3));
+        assert_eq!(ManaSymbol::parse("16"), ManaSymbol::Generic(16));
+        assert_eq!(ManaSymbol::parse("g"), ManaSymbol::Colored(Green));
+        assert_eq!(ManaSymbol::parse("C"), ManaSymbol::Colorless);
+        assert_eq!(ManaSymbol::parse("S"), ManaSymbol::Snow);
+        assert_eq!(ManaSymbol::parse("T"), ManaSymbol::Tap);
+        assert_eq!(ManaSymbol::parse("PW"), ManaSymbol::Other("PW".to_string()));
+    }
+
+    #[test]
+    fn parse_hybrid_and_phyrexian() {
+        assert_eq!(ManaSymbol::parse("W/U"), ManaSymbol::Hybrid(White, Blue));
+        assert_eq!(
+            ManaSymbol::parse("2/B"),
+            ManaSymbol::GenericHybrid(2, Black)
+        );
+        assert_eq!(ManaSymbol::parse("C/R"), ManaSymbol::ColorlessHybrid(Red));
+        assert_eq!(ManaSymbol::parse("G/P"), ManaSymbol::Phyrexian(Green));
+        assert_eq!(
+            ManaSymbol::parse("B/G/P"),
+            ManaSymbol::HybridPhyrexian(Black, Green)
+        );
+    }
+
+    #[test]
+    fn parse_variable_and_half() {
+        assert_eq!(ManaSymbol::parse("X"), ManaSymbol::Variable('X'));
+        assert_eq!(ManaSymbol::parse("Y"), ManaSymbol::Variable('Y'));
+        assert_eq!(ManaSymbol::parse("½"), ManaSymbol::Half(None));
+        assert_eq!(ManaSymbol::parse("HR"), ManaSymbol::Half(Some(Red)));
+    }
+
+    #[test]
+    fn text_round_trips() {
+        for text in [
+            "{3}", "{W/U}", "{2/B}", "{C/R}", "{G/P}", "{B/G/P}", "{X}", "{HW}", "{½}",
+        ] {
+            let inner = text.trim_start_matches('{').trim_end_matches('}');
+            assert_eq!(ManaSymbol::parse(inner).text(), text);
+        }
+    }
+
+    #[test]
+    fn mana_costs() {
+        assert_eq!(
+            parse_mana_cost("{X}{R}{R}"),
+            [
+                ManaSymbol::Variable('X'),
+                ManaSymbol::Colored(Red),
+                ManaSymbol::Colored(Red)
+            ]
+        );
+        assert_eq!(parse_mana_cost(""), []);
+        assert_eq!(mana_value("{X}{R}{R}"), 2.0);
+        assert_eq!(mana_value("{2}{W/U}{W/U}"), 4.0);
+        assert_eq!(mana_value("{2/W}{2/W}{2/W}"), 6.0);
+        assert_eq!(mana_value("{1}{G/P}{B/G/P}"), 3.0);
+        assert_eq!(mana_value("{½}"), 0.5);
+        assert_eq!(mana_value("{HR}{1}"), 1.5);
+        assert_eq!(mana_value("{1000000}"), 1_000_000.0);
+    }
+
+    #[test]
+    fn symbol_text() {
+        assert_eq!(
+            parse_symbol_text("{T}: Add {G}."),
+            [
+                TextSegment::Symbol(ManaSymbol::Tap),
+                TextSegment::Text(": Add "),
+                TextSegment::Symbol(ManaSymbol::Colored(Green)),
+                TextSegment::Text(".")
+            ]
+        );
+        assert_eq!(
+            parse_symbol_text("Unclosed {G"),
+            [TextSegment::Text("Unclosed {G")]
+        );
+    }
+}
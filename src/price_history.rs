@@ -0,0 +1,225 @@
+use crate::collection::{Collection, Finish};
+use crate::prices::{price, record_snapshot, PriceSource, ValueSnapshot, SECONDS_PER_DAY};
+use crate::scryfall_models::{Card, Prices};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// About a year of daily prices per printing; older ones are dropped.
+pub const MAX_POINTS_PER_PRINTING: usize = 366;
+
+/// The prices of a printing on one day.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PricePoint {
+    /// Seconds since the unix epoch.
+    pub timestamp: u64,
+    pub prices: Prices,
+}
+
+/// Every price we have seen for one printing, oldest first.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PrintingHistory {
+    pub name: String,
+    pub set: String,
+    pub collector_number: String,
+    pub points: Vec<PricePoint>,
+}
+
+impl PrintingHistory {
+    /// The price on a date: the last one seen on or before it.
+    pub fn price_at(&self, timestamp: u64, finish: Finish, source: PriceSource) -> Option<f64> {
+        self.points
+            .iter()
+            .rev()
+            .find(|point| point.timestamp <= timestamp)
+            .and_then(|point| price(&point.prices, finish, source))
+    }
+
+    pub fn series(&self, finish: Finish, source: PriceSource) -> Vec<(u64, f64)> {
+        self.points
+            .iter()
+            .filter_map(|point| Some((point.timestamp, price(&point.prices, finish, source)?)))
+            .collect()
+    }
+}
+
+/// Price snapshots per printing, by card id, and the value of the collection over time. Only
+/// one snapshot per printing and day is kept, for at most `MAX_POINTS_PER_PRINTING` days.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct PriceHistory {
+    pub printings: HashMap<String, PrintingHistory>,
+    /// One snapshot per day the collection was saved, oldest first.
+    #[serde(default)]
+    pub collection_values: Vec<ValueSnapshot>,
+}
+
+impl PriceHistory {
+    pub fn record(&mut self, card: &Card, timestamp: u64) {
+        let history = self
+            .printings
+            .entry(card.id.clone())
+            .or_insert_with(|| PrintingHistory {
+                name: card.name.clone(),
+                set: card.set.clone(),
+                collector_number: card.collector_number.clone(),
+                points: vec![],
+            });
+        let point = PricePoint {
+            timestamp,
+            prices: card.prices.clone(),
+        };
+        match history.points.last_mut() {
+            Some(last) if last.timestamp / SECONDS_PER_DAY == timestamp / SECONDS_PER_DAY => {
+                *last = point
+            }
+            Some(last) if last.timestamp > timestamp => {}
+            _ => history.points.push(point),
+        }
+        let extra = history.points.len().saturating_sub(MAX_POINTS_PER_PRINTING);
+        history.points.drain(..extra);
+    }
+
+    /// Record what the collection is worth, replacing the value recorded earlier the same day.
+    pub fn record_collection_value(&mut self, collection: &Collection, timestamp: u64) {
+        let snapshot = ValueSnapshot::of_collection(collection, timestamp);
+        record_snapshot(&mut self.collection_values, snapshot);
+    }
+
+    /// The value of the collection on a date: the last one recorded on or before it.
+    pub fn collection_value_at(&self, timestamp: u64, source: PriceSource) -> Option<f64> {
+        self.collection_values
+            .iter()
+            .rev()
+            .find(|snapshot| snapshot.timestamp <= timestamp)
+            .and_then(|snapshot| snapshot.totals.get(&source).copied())
+    }
+
+    pub fn collection_series(&self, source: PriceSource) -> Vec<(u64, f64)> {
+        self.collection_values
+            .iter()
+            .filter_map(|snapshot| Some((snapshot.timestamp, *snapshot.totals.get(&source)?)))
+            .collect()
+    }
+}
+
+/// How much the price of a printing changed over a window of time.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Mover {
+    pub card_id: String,
+    pub name: String,
+    pub set: String,
+    pub old_price: f64,
+    pub new_price: f64,
+}
+
+impl Mover {
+    pub fn change(&self) -> f64 {
+        self.new_price - self.old_price
+    }
+
+    /// The change relative to the old price, as a fraction.
+    pub fn relative_change(&self) -> f64 {
+        if self.old_price > 0.0 {
+            self.change() / self.old_price
+        } else {
+            0.0
+        }
+    }
+}
+
+/// The printings whose price changed the most, relative to their old price, between
+/// `window_days` days before `now` and their latest price. Printings first seen inside the
+/// window are compared with their first price.
+pub fn biggest_movers(
+    history: &PriceHistory,
+    finish: Finish,
+    source: PriceSource,
+    window_days: u64,
+    now: u64,
+) -> Vec<Mover> {
+    let start = now.saturating_sub(window_days * SECONDS_PER_DAY);
+    let mut movers: Vec<Mover> = history
+        .printings
+        .iter()
+        .filter_map(|(id, printing)| {
+            let new_price = price(&printing.points.last()?.prices, finish, source)?;
+            let old_price = printing
+                .price_at(start, finish, source)
+                .or_else(|| price(&printing.points.first()?.prices, finish, source))?;
+            (old_price != new_price).then(|| Mover {
+                card_id: id.clone(),
+                name: printing.name.clone(),
+                set: printing.set.clone(),
+                old_price,
+                new_price,
+            })
+        })
+        .collect();
+    movers.sort_by(|a, b| {
+        b.relative_change()
+            .abs()
+            .total_cmp(&a.relative_change().abs())
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    movers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn card(usd: &str) -> Card {
+        Card::test("Llanowar Elves", json!({"prices": {"usd": usd}}))
+    }
+
+    #[test]
+    fn one_point_per_day() {
+        let mut history = PriceHistory::default();
+        history.record(&card("1.00"), 10);
+        history.record(&card("2.00"), 20);
+        history.record(&card("3.00"), SECONDS_PER_DAY + 10);
+        // Older than the last point: ignored.
+        history.record(&card("4.00"), 30);
+        let printing = &history.printings["Llanowar Elves"];
+        assert_eq!(
+            printing.series(Finish::Nonfoil, PriceSource::Usd),
+            vec![(20, 2.0), (SECONDS_PER_DAY + 10, 3.0)]
+        );
+        assert_eq!(
+            printing.price_at(SECONDS_PER_DAY, Finish::Nonfoil, PriceSource::Usd),
+            Some(2.0)
+        );
+    }
+
+    #[test]
+    fn points_are_capped() {
+        let mut history = PriceHistory::default();
+        let days = MAX_POINTS_PER_PRINTING as u64 + 10;
+        for day in 0..days {
+            history.record(&card("1.00"), day * SECONDS_PER_DAY);
+        }
+        let points = &history.printings["Llanowar Elves"].points;
+        assert_eq!(points.len(), MAX_POINTS_PER_PRINTING);
+        assert_eq!(points[0].timestamp, 10 * SECONDS_PER_DAY);
+    }
+
+    #[test]
+    fn collection_values() {
+        let mut collection = Collection::default();
+        collection.add(card("1.50"), 2, Finish::Nonfoil);
+        let mut history = PriceHistory::default();
+        history.record_collection_value(&collection, 100);
+        collection.add(card("1.50"), 1, Finish::Nonfoil);
+        history.record_collection_value(&collection, 200);
+        history.record_collection_value(&collection, SECONDS_PER_DAY + 100);
+        assert_eq!(
+            history.collection_series(PriceSource::Usd),
+            vec![(200, 4.5), (SECONDS_PER_DAY + 100, 4.5)]
+        );
+        assert_eq!(history.collection_value_at(50, PriceSource::Usd), None);
+        assert_eq!(
+            history.collection_value_at(SECONDS_PER_DAY, PriceSource::Usd),
+            Some(4.5)
+        );
+    }
+}
@@ -0,0 +1,276 @@
+use crate::collection::{Collection, Finish};
+use crate::deck::{Deck, DeckZone};
+use crate::scryfall_models::{Card, Prices};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// The currency, and market, prices are read in.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum PriceSource {
+    /// TCGplayer, in US dollars.
+    #[default]
+    Usd,
+    /// Cardmarket, in euros.
+    Eur,
+    /// Cardhoarder, in MTGO event tickets.
+    Tix,
+}
+
+impl PriceSource {
+    pub const ALL: [PriceSource; 3] = [PriceSource::Usd, PriceSource::Eur, PriceSource::Tix];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PriceSource::Usd => "USD",
+            PriceSource::Eur => "EUR",
+            PriceSource::Tix => "MTGO tix",
+        }
+    }
+
+    pub fn format(&self, value: f64) -> String {
+        match self {
+            PriceSource::Usd => format!("${:.2}", value),
+            PriceSource::Eur => format!("€{:.2}", value),
+            PriceSource::Tix => format!("{:.2} tix", value),
+        }
+    }
+}
+
+/// The price of one copy of a card in a finish. Finishes without their own price have none.
+pub fn card_price(card: &Card, finish: Finish, source: PriceSource) -> Option<f64> {
+    price(&card.prices, finish, source)
+}
+
+pub fn price(prices: &Prices, finish: Finish, source: PriceSource) -> Option<f64> {
+    let price = match (source, finish) {
+        (PriceSource::Usd, Finish::Nonfoil) => &prices.usd,
+        (PriceSource::Usd, Finish::Foil) => &prices.usd_foil,
+        (PriceSource::Usd, Finish::Etched) => &prices.usd_etched,
+        (PriceSource::Eur, Finish::Nonfoil) => &prices.eur,
+        (PriceSource::Eur, Finish::Foil) => &prices.eur_foil,
+        (PriceSource::Eur, Finish::Etched) => &None,
+        (PriceSource::Tix, Finish::Nonfoil) => &prices.tix,
+        (PriceSource::Tix, _) => &None,
+    };
+    price.as_deref().and_then(|p| p.parse().ok())
+}
+
+/// The total value of some cards in one currency.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct Valuation {
+    pub total: f64,
+    pub priced_cards: u32,
+    /// Cards without a price in this currency and finish, left out of the total.
+    pub unpriced_cards: u32,
+}
+
+impl Valuation {
+    fn add(&mut self, price: Option<f64>, quantity: u32) {
+        match price {
+            Some(price) => {
+                self.total += price * quantity as f64;
+                self.priced_cards += quantity;
+            }
+            None => self.unpriced_cards += quantity,
+        }
+    }
+}
+
+pub fn collection_value(collection: &Collection, source: PriceSource) -> Valuation {
+    let mut valuation = Valuation::default();
+    for entry in &collection.entries {
+        valuation.add(
+            card_price(&entry.card, entry.finish, source),
+            entry.quantity,
+        );
+    }
+    valuation
+}
+
+/// The value of the cards played in a deck, leaving out the maybeboard. Unresolved entries
+/// count as unpriced.
+pub fn deck_value(deck: &Deck, source: PriceSource) -> Valuation {
+    let mut valuation = Valuation::default();
+    for entry in deck
+        .entries
+        .iter()
+        .filter(|e| e.zone != DeckZone::Maybeboard)
+    {
+        let finish = if entry.foil {
+            Finish::Foil
+        } else {
+            Finish::Nonfoil
+        };
+        let price = entry
+            .card
+            .as_ref()
+            .and_then(|card| card_price(card, finish, source));
+        valuation.add(price, entry.quantity);
+    }
+    valuation
+}
+
+/// The value of the collection in every currency at one point in time.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct ValueSnapshot {
+    /// Seconds since the unix epoch.
+    pub timestamp: u64,
+    pub totals: HashMap<PriceSource, f64>,
+}
+
+impl ValueSnapshot {
+    pub fn of_collection(collection: &Collection, timestamp: u64) -> Self {
+        Self {
+            timestamp,
+            totals: PriceSource::ALL
+                .iter()
+                .map(|&source| (source, collection_value(collection, source).total))
+                .collect(),
+        }
+    }
+}
+
+/// Keep one snapshot per day: a snapshot taken the same day as the last one replaces it.
+pub fn record_snapshot(history: &mut Vec<ValueSnapshot>, snapshot: ValueSnapshot) {
+    match history.last_mut() {
+        Some(last) if last.timestamp / SECONDS_PER_DAY == snapshot.timestamp / SECONDS_PER_DAY => {
+            *last = snapshot
+        }
+        _ => history.push(snapshot),
+    }
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// The "YYYY-MM-DD" UTC date of a unix timestamp.
+pub fn date_string(timestamp: u64) -> String {
+    // Howard Hinnant's days to civil date algorithm.
+    let days = (timestamp / SECONDS_PER_DAY) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deck::DeckEntry;
+    use serde_json::json;
+
+    #[test]
+    fn dates_of_timestamps() {
+        assert_eq!(date_string(0), "1970-01-01");
+        assert_eq!(date_string(SECONDS_PER_DAY - 1), "1970-01-01");
+        assert_eq!(date_string(951_782_400), "2000-02-29");
+        assert_eq!(date_string(951_868_800), "2000-03-01");
+        assert_eq!(date_string(1_709_164_800), "2024-02-29");
+        assert_eq!(date_string(1_735_689_599), "2024-12-31");
+        assert_eq!(date_string(1_735_689_600), "2025-01-01");
+        assert_eq!(date_string(4_107_542_400), "2100-03-01");
+    }
+
+    #[test]
+    fn prices_by_finish_and_source() {
+        let card = Card::test(
+            "Bolt",
+            json!({ "prices": {
+                "usd": "1.50", "usd_foil": "4.00", "eur": "1.20", "tix": "0.03",
+                "usd_etched": "not a price"
+            } }),
+        );
+        assert_eq!(
+            card_price(&card, Finish::Nonfoil, PriceSource::Usd),
+            Some(1.5)
+        );
+        assert_eq!(card_price(&card, Finish::Foil, PriceSource::Usd), Some(4.0));
+        assert_eq!(card_price(&card, Finish::Etched, PriceSource::Usd), None);
+        assert_eq!(
+            card_price(&card, Finish::Nonfoil, PriceSource::Eur),
+            Some(1.2)
+        );
+        assert_eq!(card_price(&card, Finish::Foil, PriceSource::Eur), None);
+        assert_eq!(
+            card_price(&card, Finish::Nonfoil, PriceSource::Tix),
+            Some(0.03)
+        );
+        assert_eq!(card_price(&card, Finish::Foil, PriceSource::Tix), None);
+        assert_eq!(PriceSource::Eur.format(1.234), "€1.23");
+    }
+
+    #[test]
+    fn collection_values_and_snapshots() {
+        let mut collection = Collection::default();
+        let bolt = Card::test(
+            "Bolt",
+            json!({ "prices": { "usd": "1.50", "eur": "1.00" } }),
+        );
+        collection.add(bolt.clone(), 4, Finish::Nonfoil);
+        collection.add(bolt, 1, Finish::Foil);
+        let valuation = collection_value(&collection, PriceSource::Usd);
+        assert_eq!(valuation.total, 6.0);
+        assert_eq!(valuation.unpriced_cards, 1);
+
+        // One snapshot per day, the latest of the day.
+        let mut history = vec![];
+        let day = SECONDS_PER_DAY;
+        record_snapshot(&mut history, ValueSnapshot::of_collection(&collection, day));
+        collection.entries[0].quantity = 2;
+        record_snapshot(
+            &mut history,
+            ValueSnapshot::of_collection(&collection, day + 60),
+        );
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].totals[&PriceSource::Usd], 3.0);
+        assert_eq!(history[0].totals[&PriceSource::Eur], 2.0);
+        record_snapshot(
+            &mut history,
+            ValueSnapshot::of_collection(&collection, 2 * day),
+        );
+        assert_eq!(history.len(), 2);
+    }
+
+    #[test]
+    fn deck_values() {
+        let bolt = Card::test(
+            "Lightning Bolt",
+            json!({ "prices": { "usd": "1.50", "usd_foil": "4.00" } }),
+        );
+        let mut deck = Deck::new("Burn".to_string());
+        let mut foil = DeckEntry::from_card(bolt.clone(), 1, DeckZone::Sideboard);
+        foil.foil = true;
+        deck.entries = vec![
+            DeckEntry::from_card(bolt.clone(), 4, DeckZone::Main),
+            foil,
+            DeckEntry::from_card(bolt, 2, DeckZone::Maybeboard),
+            DeckEntry::new("Unresolved".to_string(), 3, DeckZone::Main),
+        ];
+        let valuation = deck_value(&deck, PriceSource::Usd);
+        assert_eq!(
+            valuation,
+            Valuation {
+                total: 10.0,
+                priced_cards: 5,
+                unpriced_cards: 3
+            }
+        );
+    }
+}
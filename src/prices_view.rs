@@ -0,0 +1,268 @@
+use crate::collection::{Collection, Finish};
+use crate::price_history::{biggest_movers, Mover, PriceHistory};
+use crate::prices::{date_string, now, PriceSource, ValueSnapshot, SECONDS_PER_DAY};
+use crate::scryfall_models::Card;
+use egui::Color32;
+use egui_plot::{Legend, Line, Plot, PlotPoints};
+
+pub const PRICE_HISTORY_KEY: &str = "price_history";
+/// Where the collection value history was saved before it moved into the price history.
+const OLD_VALUE_HISTORY_KEY: &str = "collection_value_history";
+
+const MOVERS_SHOWN: usize = 20;
+
+/// Charts of prices over time and the cards whose price moved the most.
+pub struct PricesView {
+    history: PriceHistory,
+    /// The id of the printing whose chart is shown.
+    selected_printing: Option<String>,
+    printing_filter: String,
+    window_days: u64,
+    movers_finish: Finish,
+}
+
+impl Default for PricesView {
+    fn default() -> Self {
+        Self {
+            history: PriceHistory::default(),
+            selected_printing: None,
+            printing_filter: String::new(),
+            window_days: 7,
+            movers_finish: Finish::Nonfoil,
+        }
+    }
+}
+
+impl PricesView {
+    /// Restore the prices recorded by previous sessions.
+    pub fn load(&mut self, storage: &dyn eframe::Storage) {
+        if let Some(json) = storage.get_string(PRICE_HISTORY_KEY) {
+            match serde_json::from_str(&json) {
+                Ok(history) => self.history = history,
+                Err(e) => log::error!("Could not read the price history: {}", e),
+            }
+        }
+        if self.history.collection_values.is_empty() {
+            if let Some(json) = storage.get_string(OLD_VALUE_HISTORY_KEY) {
+                match serde_json::from_str(&json) {
+                    Ok(values) => self.history.collection_values = values,
+                    Err(e) => log::error!("Could not read the collection value history: {}", e),
+                }
+            }
+        }
+    }
+
+    pub fn save(&self, storage: &mut dyn eframe::Storage) {
+        match serde_json::to_string(&self.history) {
+            Ok(json) => storage.set_string(PRICE_HISTORY_KEY, json),
+            Err(e) => log::error!("Could not save the price history: {}", e),
+        }
+    }
+
+    /// Record today's prices of freshly downloaded cards.
+    pub fn record(&mut self, cards: &[Card]) {
+        let timestamp = now();
+        for card in cards {
+            self.history.record(card, timestamp);
+        }
+    }
+
+    /// Record what the collection is worth today.
+    pub fn record_collection_value(&mut self, collection: &Collection) {
+        if !collection.entries.is_empty() {
+            self.history.record_collection_value(collection, now());
+        }
+    }
+
+    /// The value of the collection over time, oldest first.
+    pub fn collection_values(&self) -> &[ValueSnapshot] {
+        &self.history.collection_values
+    }
+
+    /// The value of the collection `days` days ago, if it was recorded by then.
+    pub fn collection_value_days_ago(&self, days: u64, source: PriceSource) -> Option<f64> {
+        let timestamp = now().saturating_sub(days * SECONDS_PER_DAY);
+        self.history.collection_value_at(timestamp, source)
+    }
+
+    /// The printings whose price moved the most over the window chosen in the prices view.
+    pub fn movers(&self, source: PriceSource) -> Vec<Mover> {
+        biggest_movers(
+            &self.history,
+            self.movers_finish,
+            source,
+            self.window_days,
+            now(),
+        )
+    }
+
+    pub fn window_days(&self) -> u64 {
+        self.window_days
+    }
+
+    /// When prices were last recorded, as a unix timestamp.
+    pub fn last_recorded(&self) -> Option<u64> {
+        self.history
+            .printings
+            .values()
+            .filter_map(|p| p.points.last().map(|point| point.timestamp))
+            .max()
+    }
+
+    pub fn draw(&mut self, ui: &mut egui::Ui, source: PriceSource) {
+        if self.history.printings.is_empty() && self.history.collection_values.is_empty() {
+            ui.label("Prices are recorded every time cards are searched.");
+            return;
+        }
+        egui::ScrollArea::vertical()
+            .id_salt("prices_view")
+            .show(ui, |ui| {
+                ui.heading("Collection value");
+                let series = self.history.collection_series(source);
+                price_chart(
+                    ui,
+                    "collection_value_chart",
+                    vec![("Collection", series)],
+                    source,
+                );
+
+                ui.separator();
+                ui.heading("Card prices");
+                self.show_printing_selector(ui);
+                if let Some(printing) = self
+                    .selected_printing
+                    .as_ref()
+                    .and_then(|id| self.history.printings.get(id))
+                {
+                    let lines = Finish::ALL
+                        .iter()
+                        .map(|&finish| (finish.label(), printing.series(finish, source)))
+                        .filter(|(_, series)| !series.is_empty())
+                        .collect();
+                    price_chart(ui, "printing_price_chart", lines, source);
+                }
+
+                ui.separator();
+                ui.heading("Biggest movers");
+                self.show_movers(ui, source);
+            });
+    }
+
+    fn show_printing_selector(&mut self, ui: &mut egui::Ui) {
+        let selected_text = self
+            .selected_printing
+            .as_ref()
+            .and_then(|id| self.history.printings.get(id))
+            .map(|p| format!("{} ({})", p.name, p.set.to_uppercase()))
+            .unwrap_or_else(|| "Pick a card".to_string());
+        ui.horizontal(|ui| {
+            ui.label("Filter:");
+            ui.text_edit_singleline(&mut self.printing_filter);
+            let filter = self.printing_filter.to_lowercase();
+            let mut printings: Vec<_> = self
+                .history
+                .printings
+                .iter()
+                .filter(|(_, p)| p.name.to_lowercase().contains(&filter))
+                .collect();
+            printings.sort_by(|(_, a), (_, b)| a.name.cmp(&b.name).then(a.set.cmp(&b.set)));
+            egui::ComboBox::from_id_salt("price_printing")
+                .selected_text(selected_text)
+                .height(300.0)
+                .show_ui(ui, |ui| {
+                    for (id, printing) in printings {
+                        let label = format!(
+                            "{} ({} #{})",
+                            printing.name,
+                            printing.set.to_uppercase(),
+                            printing.collector_number
+                        );
+                        ui.selectable_value(&mut self.selected_printing, Some(id.clone()), label);
+                    }
+                });
+        });
+    }
+
+    fn show_movers(&mut self, ui: &mut egui::Ui, source: PriceSource) {
+        ui.horizontal(|ui| {
+            ui.label("Over the last");
+            ui.add(egui::DragValue::new(&mut self.window_days).range(1..=365));
+            ui.label("days, for");
+            egui::ComboBox::from_id_salt("movers_finish")
+                .selected_text(self.movers_finish.label())
+                .show_ui(ui, |ui| {
+                    for finish in Finish::ALL {
+                        ui.selectable_value(&mut self.movers_finish, finish, finish.label());
+                    }
+                });
+        });
+        let movers = self.movers(source);
+        if movers.is_empty() {
+            ui.label("No price changed in this window.");
+            return;
+        }
+        egui::Grid::new("price_movers")
+            .striped(true)
+            .show(ui, |ui| {
+                ui.strong("Card");
+                ui.strong("Set");
+                ui.strong("Before");
+                ui.strong("Now");
+                ui.strong("Change");
+                ui.end_row();
+                for mover in movers.iter().take(MOVERS_SHOWN) {
+                    if ui.link(&mover.name).clicked() {
+                        self.selected_printing = Some(mover.card_id.clone());
+                    }
+                    ui.label(mover.set.to_uppercase());
+                    ui.label(source.format(mover.old_price));
+                    ui.label(source.format(mover.new_price));
+                    let color = if mover.change() > 0.0 {
+                        Color32::GREEN
+                    } else {
+                        Color32::LIGHT_RED
+                    };
+                    ui.colored_label(
+                        color,
+                        format!(
+                            "{:+.2} ({:+.1}%)",
+                            mover.change(),
+                            mover.relative_change() * 100.0
+                        ),
+                    );
+                    ui.end_row();
+                }
+            });
+    }
+}
+
+/// A line chart of prices by day, with dates on the x axis.
+fn price_chart(
+    ui: &mut egui::Ui,
+    id: &str,
+    lines: Vec<(&str, Vec<(u64, f64)>)>,
+    source: PriceSource,
+) {
+    let day = SECONDS_PER_DAY as f64;
+    Plot::new(id)
+        .legend(Legend::default())
+        .height(200.0)
+        .x_axis_formatter(move |mark, _| date_string((mark.value * day) as u64))
+        .label_formatter(move |name, point| {
+            format!(
+                "{}\n{}: {}",
+                name,
+                date_string((point.x * day) as u64),
+                source.format(point.y)
+            )
+        })
+        .show(ui, |plot_ui| {
+            for (name, series) in lines {
+                let points: PlotPoints = series
+                    .iter()
+                    .map(|&(timestamp, price)| [timestamp as f64 / day, price])
+                    .collect();
+                plot_ui.line(Line::new(points).name(name));
+            }
+        });
+}
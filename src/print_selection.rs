@@ -0,0 +1,284 @@
+use crate::card_detail_view::capitalize;
+use crate::collection::Finish;
+use crate::prices::PriceSource;
+use crate::scryfall_models::Card;
+use crate::search_table::{collector_number_key, display_price};
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
+
+/// How the printings of a card are ordered.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PrintSort {
+    /// Scryfall's order, newest first.
+    Default,
+    ReleaseDate,
+    Price,
+    SetName,
+    CollectorNumber,
+}
+
+impl PrintSort {
+    pub const ALL: [PrintSort; 5] = [
+        PrintSort::Default,
+        PrintSort::ReleaseDate,
+        PrintSort::Price,
+        PrintSort::SetName,
+        PrintSort::CollectorNumber,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PrintSort::Default => "Default",
+            PrintSort::ReleaseDate => "Release date",
+            PrintSort::Price => "Price",
+            PrintSort::SetName => "Set name",
+            PrintSort::CollectorNumber => "Collector number",
+        }
+    }
+
+    /// Order two printings, ascending.
+    fn compare(&self, a: &Card, b: &Card, price_source: PriceSource) -> Ordering {
+        match self {
+            PrintSort::Default => Ordering::Equal,
+            PrintSort::ReleaseDate => a.released_at.cmp(&b.released_at),
+            PrintSort::Price => {
+                let a = display_price(a, price_source).unwrap_or(f64::INFINITY);
+                let b = display_price(b, price_source).unwrap_or(f64::INFINITY);
+                a.total_cmp(&b)
+            }
+            PrintSort::SetName => a.set_name.cmp(&b.set_name),
+            PrintSort::CollectorNumber => collector_number_key(&a.collector_number)
+                .cmp(&collector_number_key(&b.collector_number)),
+        }
+    }
+}
+
+/// Which printings are shown. `None` lets every printing through.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct PrintFilter {
+    pub finish: Option<Finish>,
+    pub frame: Option<String>,
+    pub border_color: Option<String>,
+    pub language: Option<String>,
+    pub promo: Option<bool>,
+    pub digital: Option<bool>,
+}
+
+impl PrintFilter {
+    pub fn matches(&self, card: &Card) -> bool {
+        let finish = self.finish.map_or(true, |finish| {
+            let name = match finish {
+                Finish::Nonfoil => "nonfoil",
+                Finish::Foil => "foil",
+                Finish::Etched => "etched",
+            };
+            card.finishes.iter().any(|f| f == name)
+        });
+        finish
+            && self.frame.as_ref().map_or(true, |f| card.frame == *f)
+            && self
+                .border_color
+                .as_ref()
+                .map_or(true, |b| card.border_color == *b)
+            && self.language.as_ref().map_or(true, |l| card.lang == *l)
+            && self.promo.map_or(true, |promo| card.promo == promo)
+            && self.digital.map_or(true, |digital| card.digital == digital)
+    }
+}
+
+/// The different values a field takes across printings, to offer them as filter options.
+pub fn distinct_values(prints: &[Card], field: impl Fn(&Card) -> &str) -> Vec<String> {
+    let values: BTreeSet<&str> = prints.iter().map(field).filter(|v| !v.is_empty()).collect();
+    values.into_iter().map(str::to_string).collect()
+}
+
+/// Filter and sort printings, then split them by set type when `group` is set. Returns the
+/// groups with indices into `prints`; without grouping there is one group with an empty name.
+pub fn select_prints(
+    prints: &[Card],
+    filter: &PrintFilter,
+    sort: PrintSort,
+    ascending: bool,
+    group: bool,
+    price_source: PriceSource,
+) -> Vec<(String, Vec<usize>)> {
+    let mut indices: Vec<usize> = (0..prints.len())
+        .filter(|&i| filter.matches(&prints[i]))
+        .collect();
+    if sort != PrintSort::Default {
+        indices.sort_by(|&a, &b| {
+            let ordering = sort.compare(&prints[a], &prints[b], price_source);
+            if ascending {
+                ordering
+            } else {
+                ordering.reverse()
+            }
+        });
+    }
+    if !group {
+        return vec![(String::new(), indices)];
+    }
+    let mut groups: Vec<(String, Vec<usize>)> = vec![];
+    for i in indices {
+        let set_type = set_type_label(&prints[i].set_type);
+        match groups.iter_mut().find(|(name, _)| *name == set_type) {
+            Some((_, group)) => group.push(i),
+            None => groups.push((set_type, vec![i])),
+        }
+    }
+    groups.sort_by(|(a, _), (b, _)| a.cmp(b));
+    groups
+}
+
+/// The group of a set type, e.g. "Draft innovation" for "draft_innovation".
+fn set_type_label(set_type: &str) -> String {
+    if set_type.is_empty() {
+        "Other".to_string()
+    } else {
+        capitalize(set_type)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn prints() -> Vec<Card> {
+        vec![
+            Card::test(
+                "a",
+                json!({
+                    "set_name": "Magic 2010", "set_type": "core", "released_at": "2009-07-17",
+                    "collector_number": "146", "finishes": ["nonfoil", "foil"], "frame": "2003",
+                    "border_color": "black", "lang": "en", "prices": { "usd": "2.00" }
+                }),
+            ),
+            Card::test(
+                "b",
+                json!({
+                    "set_name": "Double Masters", "set_type": "masters", "released_at": "2020-08-07",
+                    "collector_number": "129", "finishes": ["foil"], "frame": "2015",
+                    "border_color": "black", "lang": "ja", "prices": { "usd_foil": "1.00" }
+                }),
+            ),
+            Card::test(
+                "c",
+                json!({
+                    "set_name": "Secret Lair Drop", "set_type": "box", "released_at": "2021-01-01",
+                    "collector_number": "9", "finishes": ["nonfoil"], "frame": "2015",
+                    "border_color": "borderless", "lang": "en", "promo": true, "digital": false
+                }),
+            ),
+            Card::test(
+                "d",
+                json!({
+                    "set_name": "Arena", "set_type": "", "released_at": "2018-01-01",
+                    "collector_number": "10", "finishes": ["nonfoil"], "frame": "2015",
+                    "border_color": "black", "lang": "en", "digital": true,
+                    "prices": { "usd": "0.50" }
+                }),
+            ),
+        ]
+    }
+
+    fn ids(prints: &[Card], indices: &[usize]) -> String {
+        indices.iter().map(|&i| prints[i].id.as_str()).collect()
+    }
+
+    #[test]
+    fn filtering() {
+        let prints = prints();
+        let selected = |filter: PrintFilter| {
+            let groups = select_prints(
+                &prints,
+                &filter,
+                PrintSort::Default,
+                true,
+                false,
+                PriceSource::Usd,
+            );
+            ids(&prints, &groups[0].1)
+        };
+        assert_eq!(selected(PrintFilter::default()), "abcd");
+        let finish = |finish| PrintFilter {
+            finish: Some(finish),
+            ..PrintFilter::default()
+        };
+        assert_eq!(selected(finish(Finish::Foil)), "ab");
+        assert_eq!(selected(finish(Finish::Etched)), "");
+        assert_eq!(
+            selected(PrintFilter {
+                frame: Some("2015".to_string()),
+                border_color: Some("black".to_string()),
+                ..PrintFilter::default()
+            }),
+            "bd"
+        );
+        assert_eq!(
+            selected(PrintFilter {
+                language: Some("en".to_string()),
+                promo: Some(false),
+                digital: Some(false),
+                ..PrintFilter::default()
+            }),
+            "a"
+        );
+        assert_eq!(distinct_values(&prints, |c| &c.lang), ["en", "ja"]);
+        assert_eq!(
+            distinct_values(&prints, |c| &c.set_type),
+            ["box", "core", "masters"]
+        );
+    }
+
+    #[test]
+    fn sorting() {
+        let prints = prints();
+        let sorted = |sort: PrintSort, ascending: bool| {
+            let groups = select_prints(
+                &prints,
+                &PrintFilter::default(),
+                sort,
+                ascending,
+                false,
+                PriceSource::Usd,
+            );
+            ids(&prints, &groups[0].1)
+        };
+        assert_eq!(sorted(PrintSort::ReleaseDate, true), "adbc");
+        assert_eq!(sorted(PrintSort::ReleaseDate, false), "cbda");
+        // Foil-only printings use their foil price; unpriced ones go last.
+        assert_eq!(sorted(PrintSort::Price, true), "dbac");
+        assert_eq!(sorted(PrintSort::SetName, true), "dbac");
+        assert_eq!(sorted(PrintSort::CollectorNumber, true), "cdba");
+        // The default order is scryfall's, whichever direction is picked.
+        assert_eq!(sorted(PrintSort::Default, false), "abcd");
+    }
+
+    #[test]
+    fn grouping_by_set_type() {
+        let prints = prints();
+        let groups = select_prints(
+            &prints,
+            &PrintFilter::default(),
+            PrintSort::ReleaseDate,
+            true,
+            true,
+            PriceSource::Usd,
+        );
+        let groups: Vec<(&str, String)> = groups
+            .iter()
+            .map(|(name, indices)| (name.as_str(), ids(&prints, indices)))
+            .collect();
+        assert_eq!(
+            groups,
+            [
+                ("Box", "c".to_string()),
+                ("Core", "a".to_string()),
+                ("Masters", "b".to_string()),
+                ("Other", "d".to_string()),
+            ]
+        );
+        assert_eq!(set_type_label("draft_innovation"), "Draft innovation");
+    }
+}
@@ -0,0 +1,229 @@
+use serde::{Deserialize, Serialize};
+
+/// The pages of the app, one at a time in the central panel.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum View {
+    #[default]
+    Home,
+    CardSearch,
+    DeckBuilder,
+    Collection,
+    Prices,
+    Compare,
+    Storage,
+    Trades,
+    Settings,
+}
+
+impl View {
+    pub const ALL: [View; 9] = [
+        View::Home,
+        View::CardSearch,
+        View::DeckBuilder,
+        View::Collection,
+        View::Prices,
+        View::Compare,
+        View::Storage,
+        View::Trades,
+        View::Settings,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            View::Home => "Home",
+            View::CardSearch => "Card searcher",
+            View::DeckBuilder => "Deck builder",
+            View::Collection => "Collection",
+            View::Prices => "Prices",
+            View::Compare => "Compare",
+            View::Storage => "Storage",
+            View::Trades => "Trades",
+            View::Settings => "Settings",
+        }
+    }
+
+    /// The name of the view in deep links, e.g. `#/collection`.
+    #[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+    pub fn slug(&self) -> &'static str {
+        match self {
+            View::Home => "",
+            View::CardSearch => "search",
+            View::DeckBuilder => "deck",
+            View::Collection => "collection",
+            View::Prices => "prices",
+            View::Compare => "compare",
+            View::Storage => "storage",
+            View::Trades => "trades",
+            View::Settings => "settings",
+        }
+    }
+
+    /// The view a URL hash such as `#/collection` links to.
+    #[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+    pub fn from_hash(hash: &str) -> Option<View> {
+        let slug = hash.trim_start_matches('#').trim_start_matches('/');
+        View::ALL.into_iter().find(|view| view.slug() == slug)
+    }
+
+    /// The URL hash that links to this view.
+    #[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+    pub fn hash(&self) -> String {
+        format!("#/{}", self.slug())
+    }
+}
+
+/// Which view is shown, with browser-like back and forward history.
+pub struct Router {
+    current: View,
+    back: Vec<View>,
+    forward: Vec<View>,
+    /// The views listed in the sidebar, in order.
+    views: Vec<View>,
+    /// The URL hash last seen or set, to notice when the user edits it.
+    #[cfg(target_arch = "wasm32")]
+    url_hash: String,
+}
+
+impl Default for Router {
+    fn default() -> Self {
+        Self::new(&View::ALL)
+    }
+}
+
+impl Router {
+    pub fn new(views: &[View]) -> Self {
+        Self {
+            current: View::default(),
+            back: vec![],
+            forward: vec![],
+            views: views.to_vec(),
+            #[cfg(target_arch = "wasm32")]
+            url_hash: String::new(),
+        }
+    }
+
+    pub fn current(&self) -> View {
+        self.current
+    }
+
+    pub fn navigate(&mut self, view: View) {
+        if view != self.current {
+            log::info!("Showing {}", view.label());
+            self.back.push(self.current);
+            self.forward.clear();
+            self.current = view;
+        }
+    }
+
+    pub fn can_go_back(&self) -> bool {
+        !self.back.is_empty()
+    }
+
+    pub fn can_go_forward(&self) -> bool {
+        !self.forward.is_empty()
+    }
+
+    pub fn go_back(&mut self) {
+        if let Some(view) = self.back.pop() {
+            self.forward.push(self.current);
+            self.current = view;
+        }
+    }
+
+    pub fn go_forward(&mut self) {
+        if let Some(view) = self.forward.pop() {
+            self.back.push(self.current);
+            self.current = view;
+        }
+    }
+
+    /// Go back and forward with the mouse's side buttons, or Alt+Left and Alt+Right.
+    pub fn handle_input(&mut self, ctx: &egui::Context) {
+        let (back, forward) = ctx.input(|i| {
+            (
+                i.pointer.button_pressed(egui::PointerButton::Extra1)
+                    || (i.modifiers.alt && i.key_pressed(egui::Key::ArrowLeft)),
+                i.pointer.button_pressed(egui::PointerButton::Extra2)
+                    || (i.modifiers.alt && i.key_pressed(egui::Key::ArrowRight)),
+            )
+        });
+        if back {
+            self.go_back();
+        }
+        if forward {
+            self.go_forward();
+        }
+    }
+
+    /// Back and forward buttons.
+    pub fn history_buttons(&mut self, ui: &mut egui::Ui) {
+        if ui
+            .add_enabled(self.can_go_back(), egui::Button::new("⏴"))
+            .on_hover_text("Back")
+            .clicked()
+        {
+            self.go_back();
+        }
+        if ui
+            .add_enabled(self.can_go_forward(), egui::Button::new("⏵"))
+            .on_hover_text("Forward")
+            .clicked()
+        {
+            self.go_forward();
+        }
+    }
+
+    /// One entry per view, highlighting the current one.
+    pub fn sidebar(&mut self, ui: &mut egui::Ui) {
+        for view in self.views.clone() {
+            if ui
+                .selectable_label(self.current == view, view.label())
+                .clicked()
+            {
+                self.navigate(view);
+            }
+        }
+    }
+
+    /// Follow the URL hash when the user changes it, and keep it in step with the current view
+    /// so views can be bookmarked and shared.
+    #[cfg(target_arch = "wasm32")]
+    pub fn sync_url(&mut self) {
+        let Some(location) = web_sys::window().map(|window| window.location()) else {
+            return;
+        };
+        let hash = location.hash().unwrap_or_default();
+        if hash != self.url_hash {
+            self.url_hash = hash;
+            if let Some(view) = View::from_hash(&self.url_hash) {
+                self.navigate(view);
+            }
+        }
+        let current_hash = self.current.hash();
+        if current_hash != self.url_hash {
+            if let Err(e) = location.set_hash(&current_hash) {
+                log::warn!("Could not set the URL hash: {:?}", e);
+            }
+            self.url_hash = current_hash;
+        }
+    }
+
+    /// There is no URL outside of the web.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn sync_url(&mut self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hashes_round_trip() {
+        for view in View::ALL {
+            assert_eq!(View::from_hash(&view.hash()), Some(view));
+        }
+        assert_eq!(View::from_hash(""), Some(View::Home));
+        assert_eq!(View::from_hash("collection"), Some(View::Collection));
+        assert_eq!(View::from_hash("#/nowhere"), None);
+    }
+}
@@ -0,0 +1,355 @@
+use crate::local_cache::{cached_at, read_cached, write_cached};
+use crate::scryfall_models::{Card, ClientSettings, Ruling, ScryfallApiClient};
+use crate::settings::DataSource;
+use std::collections::{HashMap, HashSet};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+const RULINGS_CACHE: &str = "rulings";
+/// The whole rulings bulk file, as downloaded from scryfall.
+const BULK_FILE: &str = "bulk.json";
+
+/// Rulings by oracle id, oldest first like scryfall lists them.
+pub fn rulings_by_oracle_id(rulings: Vec<Ruling>) -> HashMap<String, Vec<Ruling>> {
+    let mut by_oracle_id: HashMap<String, Vec<Ruling>> = HashMap::new();
+    for ruling in rulings {
+        by_oracle_id
+            .entry(ruling.oracle_id.clone())
+            .or_default()
+            .push(ruling);
+    }
+    for rulings in by_oracle_id.values_mut() {
+        rulings.sort_by(|a, b| a.published_at.cmp(&b.published_at));
+    }
+    by_oracle_id
+}
+
+/// "wotc" reads better as "Wizards of the Coast".
+pub fn source_label(source: &str) -> &str {
+    match source {
+        "wotc" => "Wizards of the Coast",
+        "scryfall" => "Scryfall",
+        other => other,
+    }
+}
+
+enum RulingsRequest {
+    Card { oracle_id: String, card_id: String },
+    DownloadBulk,
+}
+
+enum LoadedRulings {
+    Card(String, Vec<Ruling>),
+    /// The rulings of a card could not be loaded, and why.
+    Failed(String, String),
+    Bulk(HashMap<String, Vec<Ruling>>),
+    BulkFailed,
+}
+
+/// When every ruling was last downloaded, if ever.
+pub fn bulk_downloaded_at() -> Option<SystemTime> {
+    cached_at(RULINGS_CACHE, BULK_FILE)
+}
+
+/// Rulings by oracle id. A background thread reads them from the rulings bulk file when it has
+/// been downloaded, and otherwise asks scryfall for each card and keeps the answer on disk.
+#[derive(Default)]
+pub struct RulingsCache {
+    rulings: HashMap<String, Vec<Ruling>>,
+    requested: HashSet<String>,
+    /// Why the rulings of an oracle id could not be loaded. They are not asked for again until
+    /// `retry`.
+    failed: HashMap<String, String>,
+    /// Whether `rulings` holds every ruling, from the bulk file.
+    bulk_loaded: bool,
+    bulk_downloading: bool,
+    request_tx: Option<Sender<RulingsRequest>>,
+    loaded_rx: Option<Receiver<LoadedRulings>>,
+}
+
+impl RulingsCache {
+    /// The rulings of a card, or None while they are being loaded or when they failed to.
+    pub fn get(&mut self, ctx: &egui::Context, card: &Card) -> Option<&[Ruling]> {
+        self.receive();
+        let oracle_id = oracle_id(card);
+        if !self.rulings.contains_key(&oracle_id) {
+            if self.bulk_loaded {
+                return Some(&[]);
+            }
+            if !self.failed.contains_key(&oracle_id) && self.requested.insert(oracle_id.clone()) {
+                self.send(
+                    ctx,
+                    RulingsRequest::Card {
+                        oracle_id,
+                        card_id: card.id.clone(),
+                    },
+                );
+            }
+            return None;
+        }
+        self.rulings.get(&oracle_id).map(Vec::as_slice)
+    }
+
+    /// Why the rulings of a card could not be loaded.
+    pub fn error(&self, card: &Card) -> Option<&str> {
+        self.failed.get(&oracle_id(card)).map(String::as_str)
+    }
+
+    /// Ask for the rulings of a card again after they failed to load.
+    pub fn retry(&mut self, card: &Card) {
+        let oracle_id = oracle_id(card);
+        self.failed.remove(&oracle_id);
+        self.requested.remove(&oracle_id);
+    }
+
+    pub fn bulk_loaded(&self) -> bool {
+        self.bulk_loaded
+    }
+
+    pub fn bulk_downloading(&self) -> bool {
+        self.bulk_downloading
+    }
+
+    /// Download every ruling, so rulings can be shown without going online.
+    pub fn download_bulk(&mut self, ctx: &egui::Context) {
+        if !self.bulk_downloading {
+            self.bulk_downloading = true;
+            self.send(ctx, RulingsRequest::DownloadBulk);
+        }
+    }
+
+    fn send(&mut self, ctx: &egui::Context, request: RulingsRequest) {
+        let request_tx = self.worker(ctx);
+        if request_tx.send(request).is_err() {
+            self.request_tx = None;
+            self.requested.clear();
+            self.bulk_downloading = false;
+        }
+    }
+
+    fn receive(&mut self) {
+        let Some(loaded_rx) = &self.loaded_rx else {
+            return;
+        };
+        while let Ok(loaded) = loaded_rx.try_recv() {
+            match loaded {
+                LoadedRulings::Card(oracle_id, rulings) => {
+                    self.rulings.insert(oracle_id, rulings);
+                }
+                LoadedRulings::Failed(oracle_id, error) => {
+                    self.requested.remove(&oracle_id);
+                    self.failed.insert(oracle_id, error);
+                }
+                LoadedRulings::Bulk(rulings) => {
+                    self.rulings = rulings;
+                    self.bulk_loaded = true;
+                    self.bulk_downloading = false;
+                }
+                LoadedRulings::BulkFailed => self.bulk_downloading = false,
+            }
+        }
+    }
+
+    fn worker(&mut self, ctx: &egui::Context) -> &Sender<RulingsRequest> {
+        self.request_tx.get_or_insert_with(|| {
+            let (request_tx, request_rx) = mpsc::channel::<RulingsRequest>();
+            let (loaded_tx, loaded_rx) = mpsc::channel();
+            self.loaded_rx = Some(loaded_rx);
+            let ctx = ctx.clone();
+            thread::spawn(move || {
+                let client = ScryfallApiClient::new();
+                let mut bulk_loaded = match read_cached(RULINGS_CACHE, BULK_FILE) {
+                    Some(bytes) => send_bulk(&loaded_tx, &ctx, &bytes),
+                    None => false,
+                };
+                for request in request_rx {
+                    let loaded = match request {
+                        // The bulk file answers every card already.
+                        RulingsRequest::Card { .. } if bulk_loaded => continue,
+                        RulingsRequest::Card { oracle_id, card_id } => {
+                            match card_rulings(&client, &oracle_id, &card_id) {
+                                Ok(rulings) => LoadedRulings::Card(oracle_id, rulings),
+                                Err(error) => LoadedRulings::Failed(oracle_id, error),
+                            }
+                        }
+                        RulingsRequest::DownloadBulk if is_offline() => {
+                            log::warn!("Not downloading the rulings while offline");
+                            LoadedRulings::BulkFailed
+                        }
+                        RulingsRequest::DownloadBulk => {
+                            match client.get_bulk_data("rulings") {
+                                Ok(bytes) => {
+                                    write_cached(RULINGS_CACHE, BULK_FILE, &bytes);
+                                    bulk_loaded = send_bulk(&loaded_tx, &ctx, &bytes);
+                                    if bulk_loaded {
+                                        continue;
+                                    }
+                                }
+                                Err(e) => log::warn!("Could not download the rulings: {}", e),
+                            }
+                            LoadedRulings::BulkFailed
+                        }
+                    };
+                    if loaded_tx.send(loaded).is_err() {
+                        return;
+                    }
+                    ctx.request_repaint();
+                }
+            });
+            request_tx
+        })
+    }
+}
+
+/// Cards without an oracle id, like reversible cards, have rulings by card id.
+fn oracle_id(card: &Card) -> String {
+    card.oracle_id.clone().unwrap_or_else(|| card.id.clone())
+}
+
+fn is_offline() -> bool {
+    ClientSettings::current().data_source == DataSource::Offline
+}
+
+/// The rulings of one card, from the disk cache or from scryfall unless the app is offline.
+fn card_rulings(
+    client: &ScryfallApiClient,
+    oracle_id: &str,
+    card_id: &str,
+) -> Result<Vec<Ruling>, String> {
+    let file_name = format!("{}.json", oracle_id);
+    if let Some(rulings) =
+        read_cached(RULINGS_CACHE, &file_name).and_then(|bytes| serde_json::from_slice(&bytes).ok())
+    {
+        return Ok(rulings);
+    }
+    if is_offline() {
+        return Err("The rulings are not on disk and the app is offline".to_string());
+    }
+    let rulings = client.get_rulings(card_id);
+    thread::sleep(Duration::from_millis(100));
+    let rulings = rulings.map_err(|e| {
+        log::warn!("Could not download the rulings of {}: {}", card_id, e);
+        format!("Could not download the rulings: {}", e)
+    })?;
+    match serde_json::to_vec(&rulings) {
+        Ok(json) => write_cached(RULINGS_CACHE, &file_name, &json),
+        Err(e) => log::warn!("Could not cache the rulings of {}: {}", card_id, e),
+    }
+    Ok(rulings)
+}
+
+/// Parse the bulk file and hand it to the cache. Returns whether it could be read.
+fn send_bulk(loaded_tx: &Sender<LoadedRulings>, ctx: &egui::Context, bytes: &[u8]) -> bool {
+    match serde_json::from_slice::<Vec<Ruling>>(bytes) {
+        Ok(rulings) => {
+            let sent = loaded_tx
+                .send(LoadedRulings::Bulk(rulings_by_oracle_id(rulings)))
+                .is_ok();
+            ctx.request_repaint();
+            sent
+        }
+        Err(e) => {
+            log::warn!("Could not read the rulings bulk file: {}", e);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn ruling(oracle_id: &str, published_at: &str) -> Ruling {
+        Ruling {
+            oracle_id: oracle_id.to_string(),
+            source: "wotc".to_string(),
+            published_at: published_at.to_string(),
+            comment: String::new(),
+        }
+    }
+
+    /// A cache whose worker is the returned sender.
+    fn cache() -> (RulingsCache, Sender<LoadedRulings>) {
+        let (loaded_tx, loaded_rx) = mpsc::channel();
+        let cache = RulingsCache {
+            loaded_rx: Some(loaded_rx),
+            ..RulingsCache::default()
+        };
+        (cache, loaded_tx)
+    }
+
+    #[test]
+    fn grouping_rulings() {
+        let rulings = vec![
+            ruling("bolt", "2021-03-19"),
+            ruling("push", "2017-02-09"),
+            ruling("bolt", "2004-10-04"),
+        ];
+        let by_oracle_id = rulings_by_oracle_id(rulings);
+        assert_eq!(by_oracle_id.len(), 2);
+        let dates: Vec<&str> = by_oracle_id["bolt"]
+            .iter()
+            .map(|r| r.published_at.as_str())
+            .collect();
+        assert_eq!(dates, ["2004-10-04", "2021-03-19"]);
+
+        assert_eq!(source_label("wotc"), "Wizards of the Coast");
+        assert_eq!(source_label("other"), "other");
+    }
+
+    #[test]
+    fn cards_without_an_oracle_id_use_their_id() {
+        let card = Card::test("Bolt", json!({ "oracle_id": "bolt" }));
+        assert_eq!(oracle_id(&card), "bolt");
+        let reversible = Card::test("Reversible", json!({}));
+        assert_eq!(oracle_id(&reversible), "Reversible");
+    }
+
+    #[test]
+    fn failed_rulings_wait_for_a_retry() {
+        let ctx = egui::Context::default();
+        let card = Card::test("Bolt", json!({ "oracle_id": "bolt" }));
+        let (mut cache, loaded_tx) = cache();
+        cache.requested.insert("bolt".to_string());
+        loaded_tx
+            .send(LoadedRulings::Failed(
+                "bolt".to_string(),
+                "offline".to_string(),
+            ))
+            .unwrap();
+
+        assert!(cache.get(&ctx, &card).is_none());
+        assert_eq!(cache.error(&card), Some("offline"));
+        // Not asked for again until retried.
+        assert!(!cache.requested.contains("bolt"));
+
+        cache.retry(&card);
+        assert_eq!(cache.error(&card), None);
+        loaded_tx
+            .send(LoadedRulings::Card(
+                "bolt".to_string(),
+                vec![ruling("bolt", "2004-10-04")],
+            ))
+            .unwrap();
+        assert_eq!(cache.get(&ctx, &card).map(<[Ruling]>::len), Some(1));
+    }
+
+    #[test]
+    fn the_bulk_file_answers_every_card() {
+        let ctx = egui::Context::default();
+        let (mut cache, loaded_tx) = cache();
+        cache.bulk_downloading = true;
+        let rulings = rulings_by_oracle_id(vec![ruling("bolt", "2004-10-04")]);
+        loaded_tx.send(LoadedRulings::Bulk(rulings)).unwrap();
+
+        let bolt = Card::test("Bolt", json!({ "oracle_id": "bolt" }));
+        let vanilla = Card::test("Vanilla", json!({ "oracle_id": "vanilla" }));
+        assert_eq!(cache.get(&ctx, &bolt).map(<[Ruling]>::len), Some(1));
+        assert_eq!(cache.get(&ctx, &vanilla).map(<[Ruling]>::len), Some(0));
+        assert!(cache.bulk_loaded());
+        assert!(!cache.bulk_downloading());
+        assert!(cache.requested.is_empty());
+    }
+}
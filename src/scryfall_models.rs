@@ -1,45 +1,37 @@
+use crate::settings::DataSource;
 use bytes::Bytes;
 use egui::load::Result;
 use egui::TextureHandle;
 use reqwest::{
-    blocking::Client,
+    blocking::{Client, RequestBuilder},
     header::{ACCEPT, ACCEPT_ENCODING, USER_AGENT},
+    Method,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::sync::mpsc;
+use std::collections::HashMap;
+use std::sync::{LazyLock, RwLock};
 use std::thread;
 use std::time::Duration;
-use std::time::Instant;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Default)]
 pub struct ScryfallSearchResponse {
     /// Typically "list" for a list response.
     pub data: Vec<Card>,
-    pub total_cards: Option<u32>,
     #[serde(flatten)]
     pub _extra: Value,
     //    pub object: String,
     //    pub has_more: bool,
 }
 
-impl Default for ScryfallSearchResponse {
-    fn default() -> Self {
-        Self {
-            data: vec![],
-            total_cards: None,
-            _extra: Value::default(),
-            //            object: "".to_owned(),
-            //            has_more: false,
-        }
-    }
-}
-
 #[derive(Deserialize, Serialize)]
 pub struct Card {
     pub set: String,
     pub name: String,
     pub id: String,
+    /// Shared by every printing of the same card.
+    #[serde(default)]
+    pub oracle_id: Option<String>,
     #[serde(default)]
     pub image_uris: Option<ImageUris>,
     #[serde(default)]
@@ -47,6 +39,77 @@ pub struct Card {
     #[serde(default)]
     pub type_line: Option<String>,
     pub oracle_text: Option<String>,
+    #[serde(default)]
+    pub flavor_text: Option<String>,
+    #[serde(default)]
+    pub power: Option<String>,
+    #[serde(default)]
+    pub toughness: Option<String>,
+    #[serde(default)]
+    pub loyalty: Option<String>,
+    /// The faces of double-faced, split, flip and adventure cards, which carry their own
+    /// cost, text and stats instead of the card itself.
+    #[serde(default)]
+    pub card_faces: Vec<CardFace>,
+    #[serde(default)]
+    pub artist: Option<String>,
+    #[serde(default)]
+    pub collector_number: String,
+    /// "common", "uncommon", "rare", "mythic", "special" or "bonus".
+    #[serde(default)]
+    pub rarity: String,
+    /// "YYYY-MM-DD".
+    #[serde(default)]
+    pub released_at: String,
+    #[serde(default)]
+    pub set_name: String,
+    /// e.g. "expansion", "core", "masters", "commander" or "promo".
+    #[serde(default)]
+    pub set_type: String,
+    /// Any of "nonfoil", "foil" and "etched".
+    #[serde(default)]
+    pub finishes: Vec<String>,
+    /// e.g. "1993", "1997", "2003", "2015" or "future".
+    #[serde(default)]
+    pub frame: String,
+    #[serde(default)]
+    pub border_color: String,
+    #[serde(default)]
+    pub promo: bool,
+    #[serde(default)]
+    pub lang: String,
+    /// Only exists on MTG Arena or MTGO.
+    #[serde(default)]
+    pub digital: bool,
+    #[serde(default)]
+    pub mana_cost: Option<String>,
+    #[serde(default)]
+    pub cmc: Option<f32>,
+    #[serde(default)]
+    pub colors: Option<Vec<String>>,
+    #[serde(default)]
+    pub color_identity: Vec<String>,
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    #[serde(default)]
+    pub produced_mana: Option<Vec<String>>,
+    /// Format name to "legal", "not_legal", "restricted" or "banned".
+    #[serde(default)]
+    pub legalities: HashMap<String, String>,
+    #[serde(default)]
+    pub prices: Prices,
+    /// Tokens, meld parts and combo pieces this card is related to.
+    #[serde(default)]
+    pub all_parts: Vec<RelatedCard>,
+    /// The card's page on scryfall.
+    #[serde(default)]
+    pub scryfall_uri: String,
+    /// e.g. "gatherer" or "edhrec" to the card's page on that site.
+    #[serde(default)]
+    pub related_uris: HashMap<String, String>,
+    /// e.g. "tcgplayer" or "cardmarket" to where the card can be bought.
+    #[serde(default)]
+    pub purchase_uris: HashMap<String, String>,
     #[serde(default, skip)]
     pub image_texture: Option<TextureHandle>,
     #[serde(flatten)]
@@ -58,17 +121,168 @@ impl Clone for Card {
             set: self.set.clone(),
             name: self.name.clone(),
             id: self.id.clone(),
+            oracle_id: self.oracle_id.clone(),
             image_uris: self.image_uris.clone(),
             prints_search_uri: self.prints_search_uri.clone(),
             type_line: self.type_line.clone(),
             oracle_text: self.oracle_text.clone(),
+            flavor_text: self.flavor_text.clone(),
+            power: self.power.clone(),
+            toughness: self.toughness.clone(),
+            loyalty: self.loyalty.clone(),
+            card_faces: self.card_faces.clone(),
+            artist: self.artist.clone(),
+            collector_number: self.collector_number.clone(),
+            rarity: self.rarity.clone(),
+            released_at: self.released_at.clone(),
+            set_name: self.set_name.clone(),
+            set_type: self.set_type.clone(),
+            finishes: self.finishes.clone(),
+            frame: self.frame.clone(),
+            border_color: self.border_color.clone(),
+            promo: self.promo,
+            lang: self.lang.clone(),
+            digital: self.digital,
+            mana_cost: self.mana_cost.clone(),
+            cmc: self.cmc,
+            colors: self.colors.clone(),
+            color_identity: self.color_identity.clone(),
+            keywords: self.keywords.clone(),
+            produced_mana: self.produced_mana.clone(),
+            legalities: self.legalities.clone(),
+            prices: self.prices.clone(),
+            all_parts: self.all_parts.clone(),
+            scryfall_uri: self.scryfall_uri.clone(),
+            related_uris: self.related_uris.clone(),
+            purchase_uris: self.purchase_uris.clone(),
             image_texture: self.image_texture.clone(),
             _extra: self._extra.clone(),
         }
     }
 }
-#[derive(Serialize, Deserialize, Debug)]
-#[derive(Clone)]
+
+#[cfg(test)]
+impl Card {
+    /// A card for tests, with only the given scryfall fields set besides its name.
+    pub fn test(name: &str, fields: Value) -> Card {
+        let mut json = serde_json::json!({ "set": "tst", "name": name, "id": name });
+        if let (Some(json), Value::Object(fields)) = (json.as_object_mut(), fields) {
+            json.extend(fields);
+        }
+        serde_json::from_value(json).expect("a valid test card")
+    }
+}
+
+/// One face of a multi-faced card.
+#[derive(Deserialize, Serialize, Clone, Debug, Default)]
+pub struct CardFace {
+    pub name: String,
+    #[serde(default)]
+    pub mana_cost: Option<String>,
+    #[serde(default)]
+    pub type_line: Option<String>,
+    #[serde(default)]
+    pub oracle_text: Option<String>,
+    #[serde(default)]
+    pub flavor_text: Option<String>,
+    #[serde(default)]
+    pub power: Option<String>,
+    #[serde(default)]
+    pub toughness: Option<String>,
+    #[serde(default)]
+    pub loyalty: Option<String>,
+    /// Double faced cards have an image per face instead of one for the card.
+    #[serde(default)]
+    pub image_uris: Option<ImageUris>,
+}
+
+/// An entry of a card's `all_parts`.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct RelatedCard {
+    pub id: String,
+    /// "token", "meld_part", "meld_result" or "combo_piece".
+    pub component: String,
+    pub name: String,
+    #[serde(default)]
+    pub type_line: String,
+    /// Where to download the related card.
+    pub uri: String,
+}
+
+/// The parts of a set we use, from scryfall's `/sets/:code`.
+#[derive(Deserialize, Clone, Debug)]
+pub struct ScryfallSet {
+    pub icon_svg_uri: String,
+}
+
+/// One of the ways the `/cards/collection` endpoint accepts to identify a card.
+#[derive(Serialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum CardIdentifier {
+    Id {
+        id: String,
+    },
+    SetNumber {
+        set: String,
+        collector_number: String,
+    },
+    NameSet {
+        name: String,
+        set: String,
+    },
+    Name {
+        name: String,
+    },
+}
+
+/// The most identifiers scryfall accepts in one `/cards/collection` request.
+const COLLECTION_CHUNK_SIZE: usize = 75;
+
+#[derive(Deserialize, Default)]
+pub struct ScryfallCollectionResponse {
+    pub data: Vec<Card>,
+    /// The identifiers that did not match any card, echoed back as sent.
+    #[serde(default)]
+    pub not_found: Vec<Value>,
+}
+
+/// An entry of scryfall's `/symbology` list.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct CardSymbol {
+    /// The symbol as written in card text, e.g. `{W/U}`.
+    pub symbol: String,
+    pub svg_uri: Option<String>,
+}
+
+/// An official ruling or a note from scryfall about how a card works.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct Ruling {
+    /// Rulings apply to every printing of a card.
+    pub oracle_id: String,
+    /// "wotc" or "scryfall".
+    pub source: String,
+    /// "YYYY-MM-DD".
+    pub published_at: String,
+    pub comment: String,
+}
+
+#[derive(Deserialize, Default)]
+pub struct ScryfallRulingsResponse {
+    pub data: Vec<Ruling>,
+}
+
+/// One of the files scryfall regenerates every day with all its data of a kind.
+#[derive(Deserialize)]
+pub struct BulkData {
+    pub download_uri: String,
+}
+
+#[derive(Deserialize, Default)]
+pub struct ScryfallSymbologyResponse {
+    pub data: Vec<CardSymbol>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ImageUris {
     pub small: String,
     pub normal: String,
@@ -78,39 +292,172 @@ pub struct ImageUris {
     pub border_crop: String,
 }
 
+/// Today's prices as scryfall sends them: decimal strings, missing when there is no price.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Prices {
+    #[serde(default)]
+    pub usd: Option<String>,
+    #[serde(default)]
+    pub usd_foil: Option<String>,
+    #[serde(default)]
+    pub usd_etched: Option<String>,
+    #[serde(default)]
+    pub eur: Option<String>,
+    #[serde(default)]
+    pub eur_foil: Option<String>,
+    #[serde(default)]
+    pub tix: Option<String>,
+}
+
+/// Scryfall asks every client to name itself.
+pub const DEFAULT_USER_AGENT: &str = concat!("eMTG/", env!("CARGO_PKG_VERSION"));
+/// What older versions sent as the user agent by mistake.
+pub const OLD_DEFAULT_USER_AGENT: &str =
+    "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8";
+
+/// How the app talks to scryfall. Shared by every client, including the ones of background
+/// threads, so changes in the settings apply to the next request.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ClientSettings {
+    pub data_source: DataSource,
+    pub timeout: Duration,
+    pub user_agent: String,
+    /// e.g. "http://localhost:8080". Empty to connect directly.
+    pub proxy: String,
+}
+
+impl Default for ClientSettings {
+    fn default() -> Self {
+        Self {
+            data_source: DataSource::default(),
+            timeout: Duration::from_secs(10),
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            proxy: String::new(),
+        }
+    }
+}
+
+static CLIENT_SETTINGS: LazyLock<RwLock<ClientSettings>> =
+    LazyLock::new(|| RwLock::new(ClientSettings::default()));
+
+impl ClientSettings {
+    pub fn current() -> ClientSettings {
+        CLIENT_SETTINGS
+            .read()
+            .map(|settings| settings.clone())
+            .unwrap_or_default()
+    }
+
+    /// Use these settings for every request from now on.
+    pub fn apply(self) {
+        if let Ok(mut settings) = CLIENT_SETTINGS.write() {
+            *settings = self;
+        }
+    }
+}
+
+/// Why a request to scryfall failed.
+#[derive(Debug)]
+pub enum ScryfallError {
+    /// The app is set to work offline.
+    Offline,
+    Http(reqwest::Error),
+}
+
+impl std::fmt::Display for ScryfallError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScryfallError::Offline => write!(f, "working offline"),
+            ScryfallError::Http(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ScryfallError {}
+
+impl From<reqwest::Error> for ScryfallError {
+    fn from(e: reqwest::Error) -> Self {
+        ScryfallError::Http(e)
+    }
+}
+
 pub struct ScryfallApiClient {
-    client: Client,
+    /// The http client, and the proxy it was built for.
+    client: RwLock<(String, Client)>,
 }
 
 impl Default for ScryfallApiClient {
     fn default() -> Self {
-        Self {
-            client: Client::new(),
+        Self::new()
+    }
+}
+
+fn build_client(proxy: &str) -> Client {
+    let mut builder = Client::builder();
+    if !proxy.trim().is_empty() {
+        match reqwest::Proxy::all(proxy.trim()) {
+            Ok(proxy) => builder = builder.proxy(proxy),
+            Err(e) => log::warn!("Ignoring the proxy {}: {}", proxy, e),
         }
     }
+    builder.build().unwrap_or_else(|e| {
+        log::warn!("Could not set up the http client: {}", e);
+        Client::new()
+    })
 }
 
 impl ScryfallApiClient {
     pub fn new() -> Self {
+        let proxy = ClientSettings::current().proxy;
+        let client = build_client(&proxy);
         Self {
-            client: Client::new(),
+            client: RwLock::new((proxy, client)),
+        }
+    }
+
+    /// The http client for the current proxy, rebuilt when the proxy changed.
+    fn http(&self, proxy: &str) -> Client {
+        if let Ok(client) = self.client.read() {
+            if client.0 == proxy {
+                return client.1.clone();
+            }
+        }
+        let client = build_client(proxy);
+        if let Ok(mut current) = self.client.write() {
+            *current = (proxy.to_string(), client.clone());
         }
+        client
+    }
+
+    /// A request with our headers and timeout, unless the app is offline.
+    fn request(&self, method: Method, uri: &str) -> Result<RequestBuilder, ScryfallError> {
+        let settings = ClientSettings::current();
+        if settings.data_source == DataSource::Offline {
+            return Err(ScryfallError::Offline);
+        }
+        Ok(self
+            .http(&settings.proxy)
+            .request(method, uri)
+            .header(USER_AGENT, settings.user_agent)
+            .header(ACCEPT_ENCODING, "gzip, deflate, br, zstd")
+            .timeout(settings.timeout))
+    }
+
+    /// Get a json document from scryfall's api.
+    fn get_json<T: serde::de::DeserializeOwned>(&self, uri: &str) -> Result<T, ScryfallError> {
+        Ok(self
+            .request(Method::GET, uri)?
+            .header(ACCEPT, "application/json")
+            .send()?
+            .json()?)
     }
 
     /// Given a string, perform a serach on scryfalls database. NEED TO IMPROVE OPTIONS.
-    pub fn search(&self, card_name: String) -> Result<ScryfallSearchResponse, reqwest::Error> {
-        let url = format!("https://api.scryfall.com/cards/search?&q={}", card_name);
-        println!("url of the request: {}", url);
+    pub fn search(&self, query: &str) -> Result<ScryfallSearchResponse, ScryfallError> {
         let response = self
-            .client
-            .get(&url)
-            .header(
-                USER_AGENT,
-                "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8",
-            )
+            .request(Method::GET, "https://api.scryfall.com/cards/search")?
+            .query(&[("q", query)])
             .header(ACCEPT, "application/json")
-            .header(ACCEPT_ENCODING, "gzip, deflate, br, zstd")
-            .timeout(Duration::from_secs(3))
             .send()?;
 
         let body_text = response.text()?;
@@ -126,62 +473,112 @@ impl ScryfallApiClient {
         Ok(body_json)
     }
 
-    /// Given a Card struct return a vector with all its card variations
-    pub fn get_card_versions(
+    /// Fetch many cards at once. Scryfall only accepts 75 identifiers per request so the
+    /// list is split in chunks, waiting between requests to respect the rate limit.
+    pub fn get_cards_collection(
         &self,
-        tx: mpsc::Sender<(Card, Bytes)>,
-        card: &Card,
-    ) -> Result<u32, reqwest::Error> {
-        println!("Getting card versions wirh uri {}", card.prints_search_uri);
-        let response = self
-            .client
-            .get(&card.prints_search_uri)
-            .header(
-                USER_AGENT,
-                "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8",
-            )
-            .header(ACCEPT, "application/json")
-            .header(ACCEPT_ENCODING, "gzip, deflate, br, zstd")
-            .timeout(Duration::from_secs(3))
-            .send()?;
+        identifiers: &[CardIdentifier],
+    ) -> Result<ScryfallCollectionResponse, ScryfallError> {
+        let mut result = ScryfallCollectionResponse::default();
+        for chunk_result in self.get_cards_collection_chunks(identifiers) {
+            let mut chunk_result = chunk_result?;
+            result.data.append(&mut chunk_result.data);
+            result.not_found.append(&mut chunk_result.not_found);
+        }
+        Ok(result)
+    }
 
-        let response_object: ScryfallSearchResponse =
-            response.json().expect("Deserializign card prints");
-        println!("Found {} versions of the card", response_object.data.len());
-        let card_n = response_object.total_cards.unwrap();
+    /// Like `get_cards_collection`, with one response per chunk of identifiers. Each request
+    /// is only sent when its response is asked for, so callers can report progress.
+    pub fn get_cards_collection_chunks<'a>(
+        &'a self,
+        identifiers: &'a [CardIdentifier],
+    ) -> impl Iterator<Item = Result<ScryfallCollectionResponse, ScryfallError>> + 'a {
+        identifiers
+            .chunks(COLLECTION_CHUNK_SIZE)
+            .enumerate()
+            .map(move |(i, chunk)| {
+                if i > 0 {
+                    thread::sleep(Duration::from_millis(100));
+                }
+                let response = self
+                    .request(Method::POST, "https://api.scryfall.com/cards/collection")?
+                    .header(ACCEPT, "application/json")
+                    .json(&serde_json::json!({ "identifiers": chunk }))
+                    .send()?;
+                Ok(response.json()?)
+            })
+    }
 
-        thread::spawn(move || {
-            // Recreate the client to satisfy the borrow checker.
-            let tmp_client = Client::new();
-            let mut last_request_time = Instant::now() - Duration::from_millis(100);
+    /// Get every symbol that can appear in mana costs and rules text.
+    pub fn get_symbology(&self) -> Result<Vec<CardSymbol>, ScryfallError> {
+        let symbology: ScryfallSymbologyResponse =
+            self.get_json("https://api.scryfall.com/symbology")?;
+        Ok(symbology.data)
+    }
 
-            for card in response_object.data {
-                let elapsed = last_request_time.elapsed();
-                if elapsed < Duration::from_millis(100) {
-                    thread::sleep(Duration::from_millis(100) - elapsed);
-                }
+    /// Download any file, like a card image or a symbol svg.
+    pub fn get_bytes(&self, uri: &str) -> Result<Bytes, ScryfallError> {
+        Ok(self.request(Method::GET, uri)?.send()?.bytes()?)
+    }
 
-                if let Some(uri) = card.image_uris.as_ref() {
-                    let response = tmp_client
-                        .get(uri.normal.as_str())
-                        .header(
-                            USER_AGENT,
-                            "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8",
-                        )
-                        .header(ACCEPT, "application/json")
-                        .header(ACCEPT_ENCODING, "gzip, deflate, br, zstd")
-                        .timeout(Duration::from_secs(3))
-                        .send()
-                        .unwrap_or_else(|e| panic!("Error downloading card image: {}", e));
-                    last_request_time = Instant::now();
-                    if let Ok(img) = response.bytes() {
-                        tx.send((card, img))
-                            .unwrap_or_else(|e| panic!("Error sending card image: {}", e));
-                    }
-                }
+    /// Every printing of a card in every language, following scryfall's pagination.
+    pub fn get_card_prints(&self, card: &Card) -> Result<Vec<Card>, ScryfallError> {
+        let mut prints = vec![];
+        // The search only returns english printings unless asked otherwise.
+        let separator = if card.prints_search_uri.contains('?') {
+            '&'
+        } else {
+            '?'
+        };
+        let mut next_page = Some(format!(
+            "{}{}include_multilingual=true",
+            card.prints_search_uri, separator
+        ));
+        while let Some(uri) = next_page.take() {
+            let response: ScryfallSearchResponse = self.get_json(&uri)?;
+            next_page = response
+                ._extra
+                .get("next_page")
+                .and_then(|page| page.as_str())
+                .map(str::to_string);
+            prints.extend(response.data);
+            if next_page.is_some() {
+                thread::sleep(Duration::from_millis(100));
             }
-        });
+        }
+        Ok(prints)
+    }
+
+    /// Download one card, e.g. from the `uri` of a related card.
+    pub fn get_card(&self, uri: &str) -> Result<Card, ScryfallError> {
+        self.get_json(uri)
+    }
+
+    /// Get a set by its code, for its name and icon.
+    pub fn get_set(&self, code: &str) -> Result<ScryfallSet, ScryfallError> {
+        self.get_json(&format!("https://api.scryfall.com/sets/{}", code))
+    }
+
+    /// The rulings of a printing, which are the rulings of every printing of the card.
+    pub fn get_rulings(&self, card_id: &str) -> Result<Vec<Ruling>, ScryfallError> {
+        let response: ScryfallRulingsResponse = self.get_json(&format!(
+            "https://api.scryfall.com/cards/{}/rulings",
+            card_id
+        ))?;
+        Ok(response.data)
+    }
 
-        Ok(card_n)
+    /// Download one of scryfall's bulk files, e.g. "rulings". They are large, so this can
+    /// take a while.
+    pub fn get_bulk_data(&self, kind: &str) -> Result<Bytes, ScryfallError> {
+        let bulk_data: BulkData =
+            self.get_json(&format!("https://api.scryfall.com/bulk-data/{}", kind))?;
+        thread::sleep(Duration::from_millis(100));
+        Ok(self
+            .request(Method::GET, &bulk_data.download_uri)?
+            .timeout(Duration::from_secs(300))
+            .send()?
+            .bytes()?)
     }
 }
@@ -0,0 +1,166 @@
+use serde::{Deserialize, Serialize};
+
+/// How many past queries are remembered.
+pub const HISTORY_LENGTH: usize = 30;
+
+/// A query kept under a name, e.g. "Modern-legal blue counterspells under $2".
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct SavedSearch {
+    pub name: String,
+    pub query: String,
+}
+
+/// The queries searched for, most recent first, and the searches saved by name.
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct SearchHistory {
+    pub recent: Vec<String>,
+    pub saved: Vec<SavedSearch>,
+}
+
+impl SearchHistory {
+    /// Remember a query, moving it to the front if it was searched before.
+    pub fn record(&mut self, query: &str) {
+        let query = query.trim();
+        if query.is_empty() {
+            return;
+        }
+        self.recent.retain(|q| q != query);
+        self.recent.insert(0, query.to_string());
+        self.recent.truncate(HISTORY_LENGTH);
+    }
+
+    /// Save a query, replacing the one saved under the same name.
+    pub fn save(&mut self, name: &str, query: &str) {
+        let name = name.trim();
+        let query = query.trim();
+        if query.is_empty() {
+            return;
+        }
+        let name = if name.is_empty() { query } else { name };
+        let search = SavedSearch {
+            name: name.to_string(),
+            query: query.to_string(),
+        };
+        match self.saved.iter_mut().find(|s| s.name == search.name) {
+            Some(saved) => *saved = search,
+            None => self.saved.push(search),
+        }
+    }
+
+    pub fn remove_saved(&mut self, name: &str) {
+        self.saved.retain(|s| s.name != name);
+    }
+
+    /// Save every search of `text`, as written by [`searches_to_text`]. Returns how many.
+    pub fn import(&mut self, text: &str) -> usize {
+        let searches = searches_from_text(text);
+        for search in &searches {
+            self.save(&search.name, &search.query);
+        }
+        searches.len()
+    }
+}
+
+/// Searches as text to share, each as a `# name` line followed by its query.
+pub fn searches_to_text(searches: &[SavedSearch]) -> String {
+    searches
+        .iter()
+        .map(|s| format!("# {}\n{}\n", s.name, s.query))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Read searches written by [`searches_to_text`]. A query without a name line before it is
+/// named after itself.
+pub fn searches_from_text(text: &str) -> Vec<SavedSearch> {
+    let mut searches = vec![];
+    let mut name: Option<String> = None;
+    for line in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        match line.strip_prefix('#') {
+            Some(heading) => name = Some(heading.trim().to_string()),
+            None => searches.push(SavedSearch {
+                name: name.take().unwrap_or_else(|| line.to_string()),
+                query: line.to_string(),
+            }),
+        }
+    }
+    searches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn search(name: &str, query: &str) -> SavedSearch {
+        SavedSearch {
+            name: name.to_string(),
+            query: query.to_string(),
+        }
+    }
+
+    #[test]
+    fn recent_queries() {
+        let mut history = SearchHistory::default();
+        history.record("t:goblin");
+        history.record(" c:u t:instant ");
+        history.record("   ");
+        history.record("t:goblin");
+        assert_eq!(history.recent, ["t:goblin", "c:u t:instant"]);
+
+        for i in 0..HISTORY_LENGTH {
+            history.record(&i.to_string());
+        }
+        assert_eq!(history.recent.len(), HISTORY_LENGTH);
+        assert_eq!(history.recent[0], (HISTORY_LENGTH - 1).to_string());
+    }
+
+    #[test]
+    fn saving_by_name() {
+        let mut history = SearchHistory::default();
+        history.save("Cheap counters", "o:counter usd<2");
+        history.save("  ", "t:goblin");
+        history.save("Empty", " ");
+        history.save("Cheap counters", "o:counter usd<1");
+        assert_eq!(
+            history.saved,
+            [
+                search("Cheap counters", "o:counter usd<1"),
+                search("t:goblin", "t:goblin")
+            ]
+        );
+        history.remove_saved("t:goblin");
+        assert_eq!(history.saved.len(), 1);
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let searches = [
+            search("Cheap counters", "o:counter usd<2"),
+            search("Goblins", "t:goblin"),
+        ];
+        let text = searches_to_text(&searches);
+        assert_eq!(
+            text,
+            "# Cheap counters\no:counter usd<2\n\n# Goblins\nt:goblin\n"
+        );
+        assert_eq!(searches_from_text(&text), searches);
+
+        // Queries without a name are named after themselves, and blank lines are skipped.
+        assert_eq!(
+            searches_from_text("t:elf\n\n  #Burn  \n  t:instant c:r \n"),
+            [search("t:elf", "t:elf"), search("Burn", "t:instant c:r")]
+        );
+
+        let mut history = SearchHistory::default();
+        history.save("Goblins", "t:goblin c:r");
+        assert_eq!(history.import(&text), 2);
+        // An imported search replaces the one saved under its name.
+        assert_eq!(
+            history.saved,
+            [
+                search("Goblins", "t:goblin"),
+                search("Cheap counters", "o:counter usd<2")
+            ]
+        );
+    }
+}
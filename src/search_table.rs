@@ -0,0 +1,315 @@
+use crate::collection::Finish;
+use crate::prices::{card_price, PriceSource};
+use crate::scryfall_models::Card;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+/// A column of the search results table.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum SearchColumn {
+    Name,
+    ManaCost,
+    Type,
+    Set,
+    CollectorNumber,
+    Rarity,
+    Colors,
+    Price,
+}
+
+impl SearchColumn {
+    pub const ALL: [SearchColumn; 8] = [
+        SearchColumn::Name,
+        SearchColumn::ManaCost,
+        SearchColumn::Type,
+        SearchColumn::Set,
+        SearchColumn::CollectorNumber,
+        SearchColumn::Rarity,
+        SearchColumn::Colors,
+        SearchColumn::Price,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SearchColumn::Name => "Name",
+            SearchColumn::ManaCost => "Cost",
+            SearchColumn::Type => "Type",
+            SearchColumn::Set => "Set",
+            SearchColumn::CollectorNumber => "Number",
+            SearchColumn::Rarity => "Rarity",
+            SearchColumn::Colors => "Colors",
+            SearchColumn::Price => "Price",
+        }
+    }
+
+    fn default_width(&self) -> f32 {
+        match self {
+            SearchColumn::Name => 180.0,
+            SearchColumn::Type => 160.0,
+            SearchColumn::ManaCost | SearchColumn::Colors => 90.0,
+            _ => 60.0,
+        }
+    }
+
+    /// Order two cards by this column, ascending.
+    pub fn compare(&self, a: &Card, b: &Card, price_source: PriceSource) -> Ordering {
+        match self {
+            SearchColumn::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+            SearchColumn::ManaCost => a.cmc.unwrap_or(0.0).total_cmp(&b.cmc.unwrap_or(0.0)),
+            SearchColumn::Type => a.type_line.cmp(&b.type_line),
+            SearchColumn::Set => a.set.cmp(&b.set),
+            SearchColumn::CollectorNumber => collector_number_key(&a.collector_number)
+                .cmp(&collector_number_key(&b.collector_number)),
+            SearchColumn::Rarity => rarity_rank(&a.rarity).cmp(&rarity_rank(&b.rarity)),
+            SearchColumn::Colors => colors_key(a).cmp(&colors_key(b)),
+            // Cards without a price go last.
+            SearchColumn::Price => match (
+                display_price(a, price_source),
+                display_price(b, price_source),
+            ) {
+                (Some(a), Some(b)) => a.total_cmp(&b),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            },
+        }
+    }
+}
+
+/// The price shown for a card in the table: nonfoil, or foil for foil-only printings.
+pub fn display_price(card: &Card, price_source: PriceSource) -> Option<f64> {
+    card_price(card, Finish::Nonfoil, price_source)
+        .or_else(|| card_price(card, Finish::Foil, price_source))
+}
+
+/// Sort "9" before "10", and "10a" or "★10" next to "10".
+pub fn collector_number_key(number: &str) -> (u32, String) {
+    let digits: String = number.chars().filter(|c| c.is_ascii_digit()).collect();
+    (digits.parse().unwrap_or(u32::MAX), number.to_string())
+}
+
+fn rarity_rank(rarity: &str) -> u8 {
+    match rarity {
+        "common" => 0,
+        "uncommon" => 1,
+        "rare" => 2,
+        "mythic" => 3,
+        "special" => 4,
+        "bonus" => 5,
+        _ => 6,
+    }
+}
+
+/// Colorless first, then mono colors in WUBRG order, then more and more colors.
+fn colors_key(card: &Card) -> (usize, Vec<usize>) {
+    let colors = card.colors.as_deref().unwrap_or_default();
+    let mut order: Vec<usize> = colors
+        .iter()
+        .filter_map(|c| ["W", "U", "B", "R", "G"].iter().position(|w| w == c))
+        .collect();
+    order.sort_unstable();
+    (colors.len(), order)
+}
+
+/// Whether a card matches the quick filter, by name, type line or set code.
+pub fn matches_filter(card: &Card, filter: &str) -> bool {
+    let filter = filter.trim().to_lowercase();
+    filter.is_empty()
+        || card.name.to_lowercase().contains(&filter)
+        || card
+            .type_line
+            .as_deref()
+            .is_some_and(|t| t.to_lowercase().contains(&filter))
+        || card.set.to_lowercase() == filter
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub struct ColumnSetting {
+    pub column: SearchColumn,
+    pub visible: bool,
+    pub width: f32,
+}
+
+/// Which columns are shown, in which order and how wide. Saved between sessions.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct SearchTableSettings {
+    pub columns: Vec<ColumnSetting>,
+}
+
+impl Default for SearchTableSettings {
+    fn default() -> Self {
+        Self {
+            columns: SearchColumn::ALL
+                .iter()
+                .map(|&column| ColumnSetting {
+                    column,
+                    visible: !matches!(
+                        column,
+                        SearchColumn::CollectorNumber | SearchColumn::Colors
+                    ),
+                    width: column.default_width(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl SearchTableSettings {
+    pub fn visible_columns(&self) -> impl Iterator<Item = &ColumnSetting> {
+        self.columns.iter().filter(|c| c.visible)
+    }
+
+    /// Add the columns missing from settings saved by an older version.
+    pub fn complete(&mut self) {
+        for column in SearchColumn::ALL {
+            if !self.columns.iter().any(|c| c.column == column) {
+                self.columns.push(ColumnSetting {
+                    column,
+                    visible: false,
+                    width: column.default_width(),
+                });
+            }
+        }
+    }
+
+    /// Swap a column with the one before it.
+    pub fn move_up(&mut self, index: usize) {
+        if index > 0 && index < self.columns.len() {
+            self.columns.swap(index - 1, index);
+        }
+    }
+}
+
+/// Indices of the results that pass the filter, sorted by a column.
+pub fn visible_rows(
+    cards: &[Card],
+    filter: &str,
+    sort: Option<(SearchColumn, bool)>,
+    price_source: PriceSource,
+) -> Vec<usize> {
+    let mut rows: Vec<usize> = (0..cards.len())
+        .filter(|&i| matches_filter(&cards[i], filter))
+        .collect();
+    if let Some((column, ascending)) = sort {
+        rows.sort_by(|&a, &b| {
+            let ordering = column.compare(&cards[a], &cards[b], price_source);
+            if ascending {
+                ordering
+            } else {
+                ordering.reverse()
+            }
+        });
+    }
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn names(cards: &[Card], rows: &[usize]) -> Vec<String> {
+        rows.iter().map(|&i| cards[i].name.clone()).collect()
+    }
+
+    #[test]
+    fn collector_numbers_sort_by_value() {
+        let mut numbers = vec!["10", "9", "★10", "10a", "100", "S1"];
+        numbers.sort_by_key(|n| collector_number_key(n));
+        assert_eq!(numbers, ["S1", "9", "10", "10a", "★10", "100"]);
+        assert_eq!(collector_number_key("abc").0, u32::MAX);
+    }
+
+    #[test]
+    fn prices_shown_and_sorted() {
+        let foil_only = Card::test("Foil only", json!({ "prices": { "usd_foil": "3.00" } }));
+        assert_eq!(display_price(&foil_only, PriceSource::Usd), Some(3.0));
+        assert_eq!(display_price(&foil_only, PriceSource::Tix), None);
+
+        let cards = [
+            Card::test("Unpriced", json!({})),
+            Card::test("Dear", json!({ "prices": { "usd": "20.00" } })),
+            foil_only,
+            Card::test(
+                "Cheap",
+                json!({ "prices": { "usd": "0.10", "usd_foil": "9.00" } }),
+            ),
+        ];
+        let rows = visible_rows(
+            &cards,
+            "",
+            Some((SearchColumn::Price, true)),
+            PriceSource::Usd,
+        );
+        assert_eq!(
+            names(&cards, &rows),
+            ["Cheap", "Foil only", "Dear", "Unpriced"]
+        );
+    }
+
+    #[test]
+    fn sorting_by_rarity_and_colors() {
+        let cards = [
+            Card::test("Gold", json!({ "rarity": "rare", "colors": ["G", "W"] })),
+            Card::test("Artifact", json!({ "rarity": "mythic", "colors": [] })),
+            Card::test("Red", json!({ "rarity": "common", "colors": ["R"] })),
+            Card::test("White", json!({ "rarity": "uncommon", "colors": ["W"] })),
+        ];
+        let sorted = |column| {
+            let rows = visible_rows(&cards, "", Some((column, true)), PriceSource::Usd);
+            names(&cards, &rows)
+        };
+        assert_eq!(
+            sorted(SearchColumn::Rarity),
+            ["Red", "White", "Gold", "Artifact"]
+        );
+        assert_eq!(
+            sorted(SearchColumn::Colors),
+            ["Artifact", "White", "Red", "Gold"]
+        );
+        let rows = visible_rows(
+            &cards,
+            "",
+            Some((SearchColumn::Name, false)),
+            PriceSource::Usd,
+        );
+        assert_eq!(names(&cards, &rows), ["White", "Red", "Gold", "Artifact"]);
+        // Without a sort, scryfall's order is kept.
+        assert_eq!(
+            visible_rows(&cards, "", None, PriceSource::Usd),
+            [0, 1, 2, 3]
+        );
+    }
+
+    #[test]
+    fn quick_filter() {
+        let bolt = Card::test(
+            "Lightning Bolt",
+            json!({ "type_line": "Instant", "set": "m10" }),
+        );
+        assert!(matches_filter(&bolt, ""));
+        assert!(matches_filter(&bolt, " BOLT "));
+        assert!(matches_filter(&bolt, "instant"));
+        assert!(matches_filter(&bolt, "M10"));
+        // Set codes have to match whole.
+        assert!(!matches_filter(&bolt, "m1"));
+        assert!(!matches_filter(&bolt, "sorcery"));
+    }
+
+    #[test]
+    fn column_settings() {
+        let mut settings = SearchTableSettings {
+            columns: SearchTableSettings::default().columns[..2].to_vec(),
+        };
+        settings.complete();
+        assert_eq!(settings.columns.len(), SearchColumn::ALL.len());
+        // Columns added by a newer version start hidden.
+        assert_eq!(settings.visible_columns().count(), 2);
+
+        settings.move_up(1);
+        assert_eq!(settings.columns[0].column, SearchColumn::ManaCost);
+        settings.move_up(0);
+        settings.move_up(100);
+        assert_eq!(settings.columns[0].column, SearchColumn::ManaCost);
+    }
+}
@@ -0,0 +1,173 @@
+use crate::prices::PriceSource;
+use crate::scryfall_models::{ClientSettings, ImageUris};
+use crate::search_table::SearchColumn;
+use egui::ThemePreference;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Where card data, images and rulings come from.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum DataSource {
+    /// Always download fresh data.
+    Online,
+    /// Use what is on disk and download what is missing.
+    #[default]
+    Hybrid,
+    /// Never go online, only use what is on disk.
+    Offline,
+}
+
+impl DataSource {
+    pub const ALL: [DataSource; 3] = [DataSource::Online, DataSource::Hybrid, DataSource::Offline];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            DataSource::Online => "Online",
+            DataSource::Hybrid => "Hybrid",
+            DataSource::Offline => "Offline",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            DataSource::Online => "Always download fresh data from scryfall.",
+            DataSource::Hybrid => "Use the disk cache and download what is missing.",
+            DataSource::Offline => "Only use what was downloaded before.",
+        }
+    }
+}
+
+/// The card image scryfall serves in several sizes.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ImageSize {
+    Small,
+    #[default]
+    Normal,
+    Large,
+    Png,
+    /// Only the illustration, for the image viewer.
+    ArtCrop,
+}
+
+impl ImageSize {
+    /// The sizes of the whole card.
+    pub const ALL: [ImageSize; 4] = [
+        ImageSize::Small,
+        ImageSize::Normal,
+        ImageSize::Large,
+        ImageSize::Png,
+    ];
+
+    /// What the image viewer offers.
+    pub const VIEWER: [ImageSize; 3] = [ImageSize::Large, ImageSize::Png, ImageSize::ArtCrop];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ImageSize::Small => "Small (146×204)",
+            ImageSize::Normal => "Normal (488×680)",
+            ImageSize::Large => "Large (672×936)",
+            ImageSize::Png => "PNG (745×1040)",
+            ImageSize::ArtCrop => "Art crop",
+        }
+    }
+
+    pub fn uri<'a>(&self, uris: &'a ImageUris) -> &'a str {
+        match self {
+            ImageSize::Small => &uris.small,
+            ImageSize::Normal => &uris.normal,
+            ImageSize::Large => &uris.large,
+            ImageSize::Png => &uris.png,
+            ImageSize::ArtCrop => &uris.art_crop,
+        }
+    }
+
+    /// Name of the size in cached file names.
+    pub fn key(&self) -> &'static str {
+        match self {
+            ImageSize::Small => "small",
+            ImageSize::Normal => "normal",
+            ImageSize::Large => "large",
+            ImageSize::Png => "png",
+            ImageSize::ArtCrop => "art_crop",
+        }
+    }
+}
+
+/// Scryfall's language codes and their names.
+pub const LANGUAGES: [(&str, &str); 11] = [
+    ("en", "English"),
+    ("es", "Spanish"),
+    ("fr", "French"),
+    ("de", "German"),
+    ("it", "Italian"),
+    ("pt", "Portuguese"),
+    ("ja", "Japanese"),
+    ("ko", "Korean"),
+    ("ru", "Russian"),
+    ("zhs", "Simplified Chinese"),
+    ("zht", "Traditional Chinese"),
+];
+
+pub fn language_name(code: &str) -> &str {
+    LANGUAGES
+        .iter()
+        .find(|(c, _)| *c == code)
+        .map_or(code, |(_, name)| name)
+}
+
+/// Everything the user can configure. Saved between sessions.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct Settings {
+    pub data_source: DataSource,
+    pub image_size: ImageSize,
+    /// Width of card images in the printings grid and the card details, in points.
+    pub card_width: f32,
+    /// Scryfall's code of the language searched for.
+    pub language: String,
+    pub price_source: PriceSource,
+    /// How many card images are kept in memory.
+    pub images_in_memory: usize,
+    /// How large the disk cache may grow, in megabytes.
+    pub disk_cache_mb: u64,
+    pub proxy: String,
+    pub timeout_secs: u64,
+    pub user_agent: String,
+    /// What the card searcher shows when the app starts.
+    pub default_query: String,
+    /// How search results are sorted, and whether ascending.
+    pub default_sort: Option<(SearchColumn, bool)>,
+    pub theme: ThemePreference,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        let client = ClientSettings::default();
+        Self {
+            data_source: client.data_source,
+            image_size: ImageSize::default(),
+            card_width: 250.0,
+            language: "en".to_string(),
+            price_source: PriceSource::default(),
+            images_in_memory: 500,
+            disk_cache_mb: 500,
+            proxy: client.proxy,
+            timeout_secs: client.timeout.as_secs(),
+            user_agent: client.user_agent,
+            default_query: "angel".to_string(),
+            default_sort: None,
+            theme: ThemePreference::System,
+        }
+    }
+}
+
+impl Settings {
+    pub fn client_settings(&self) -> ClientSettings {
+        ClientSettings {
+            data_source: self.data_source,
+            timeout: Duration::from_secs(self.timeout_secs.max(1)),
+            user_agent: self.user_agent.clone(),
+            proxy: self.proxy.clone(),
+        }
+    }
+}
@@ -0,0 +1,224 @@
+use crate::command_palette::CommandPalette;
+use crate::local_cache::{cache_size, clear_cache};
+use crate::prices::PriceSource;
+use crate::scryfall_models::{DEFAULT_USER_AGENT, OLD_DEFAULT_USER_AGENT};
+use crate::search_table::SearchColumn;
+use crate::settings::{language_name, DataSource, ImageSize, Settings, LANGUAGES};
+use egui::ThemePreference;
+
+pub const SETTINGS_KEY: &str = "settings";
+/// Where the price source was saved before there were settings.
+const PRICE_SOURCE_KEY: &str = "price_source";
+
+/// Edits the settings. The app applies them as soon as they change.
+#[derive(Default)]
+pub struct SettingsView {
+    settings: Settings,
+    /// Bytes taken by the disk cache, measured when asked for.
+    cache_size: Option<u64>,
+}
+
+impl SettingsView {
+    /// Restore the settings saved by a previous session.
+    pub fn load(&mut self, storage: &dyn eframe::Storage) {
+        self.read(storage);
+        if self.settings.user_agent == OLD_DEFAULT_USER_AGENT {
+            self.settings.user_agent = DEFAULT_USER_AGENT.to_string();
+        }
+    }
+
+    fn read(&mut self, storage: &dyn eframe::Storage) {
+        match storage.get_string(SETTINGS_KEY) {
+            Some(json) => match serde_json::from_str(&json) {
+                Ok(settings) => self.settings = settings,
+                Err(e) => log::error!("Could not read the settings: {}", e),
+            },
+            None => {
+                if let Some(price_source) = storage
+                    .get_string(PRICE_SOURCE_KEY)
+                    .and_then(|json| serde_json::from_str(&json).ok())
+                {
+                    self.settings.price_source = price_source;
+                }
+            }
+        }
+    }
+
+    pub fn save(&self, storage: &mut dyn eframe::Storage) {
+        match serde_json::to_string(&self.settings) {
+            Ok(json) => storage.set_string(SETTINGS_KEY, json),
+            Err(e) => log::error!("Could not save the settings: {}", e),
+        }
+    }
+
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    pub fn settings_mut(&mut self) -> &mut Settings {
+        &mut self.settings
+    }
+
+    pub fn draw(&mut self, ui: &mut egui::Ui, palette: &mut CommandPalette) {
+        egui::ScrollArea::vertical()
+            .id_salt("settings_view")
+            .show(ui, |ui| {
+                ui.heading("Data");
+                self.show_data_settings(ui);
+                ui.separator();
+                ui.heading("Cards");
+                self.show_card_settings(ui);
+                ui.separator();
+                ui.heading("Cache");
+                self.show_cache_settings(ui);
+                ui.separator();
+                ui.heading("Appearance");
+                ui.horizontal(|ui| {
+                    let theme = &mut self.settings.theme;
+                    ui.selectable_value(theme, ThemePreference::System, "💻 System");
+                    ui.selectable_value(theme, ThemePreference::Dark, "🌙 Dark");
+                    ui.selectable_value(theme, ThemePreference::Light, "☀ Light");
+                });
+                ui.separator();
+                ui.heading("Keyboard shortcuts");
+                palette.show_shortcuts_editor(ui);
+                ui.separator();
+                if ui.button("Restore default settings").clicked() {
+                    self.settings = Settings::default();
+                }
+            });
+    }
+
+    fn show_data_settings(&mut self, ui: &mut egui::Ui) {
+        let settings = &mut self.settings;
+        egui::Grid::new("data_settings")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Data source:");
+                ui.vertical(|ui| {
+                    for source in DataSource::ALL {
+                        ui.radio_value(&mut settings.data_source, source, source.label())
+                            .on_hover_text(source.description());
+                    }
+                    ui.weak(settings.data_source.description());
+                });
+                ui.end_row();
+                ui.label("Proxy:");
+                ui.add(
+                    egui::TextEdit::singleline(&mut settings.proxy)
+                        .hint_text("http://host:port, empty for none"),
+                );
+                ui.end_row();
+                ui.label("Request timeout:");
+                ui.add(
+                    egui::DragValue::new(&mut settings.timeout_secs)
+                        .range(1..=120)
+                        .suffix(" s"),
+                );
+                ui.end_row();
+                ui.label("User agent:");
+                ui.text_edit_singleline(&mut settings.user_agent);
+                ui.end_row();
+            });
+    }
+
+    fn show_card_settings(&mut self, ui: &mut egui::Ui) {
+        let settings = &mut self.settings;
+        egui::Grid::new("card_settings")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Image size:");
+                egui::ComboBox::from_id_salt("settings_image_size")
+                    .selected_text(settings.image_size.label())
+                    .show_ui(ui, |ui| {
+                        for size in ImageSize::ALL {
+                            ui.selectable_value(&mut settings.image_size, size, size.label());
+                        }
+                    });
+                ui.end_row();
+                ui.label("Card width:");
+                ui.add(egui::Slider::new(&mut settings.card_width, 100.0..=500.0).suffix(" pt"));
+                ui.end_row();
+                ui.label("Language:");
+                egui::ComboBox::from_id_salt("settings_language")
+                    .selected_text(language_name(&settings.language))
+                    .show_ui(ui, |ui| {
+                        for (code, name) in LANGUAGES {
+                            ui.selectable_value(&mut settings.language, code.to_string(), name);
+                        }
+                    });
+                ui.end_row();
+                ui.label("Prices in:");
+                egui::ComboBox::from_id_salt("settings_price_source")
+                    .selected_text(settings.price_source.label())
+                    .show_ui(ui, |ui| {
+                        for source in PriceSource::ALL {
+                            ui.selectable_value(&mut settings.price_source, source, source.label());
+                        }
+                    });
+                ui.end_row();
+                ui.label("Search on startup:");
+                ui.text_edit_singleline(&mut settings.default_query);
+                ui.end_row();
+                ui.label("Sort results by:");
+                ui.horizontal(|ui| {
+                    let selected = settings
+                        .default_sort
+                        .map_or("Scryfall's order", |(column, _)| column.label());
+                    egui::ComboBox::from_id_salt("settings_default_sort")
+                        .selected_text(selected)
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(
+                                &mut settings.default_sort,
+                                None,
+                                "Scryfall's order",
+                            );
+                            let ascending = settings.default_sort.map_or(true, |(_, a)| a);
+                            for column in SearchColumn::ALL {
+                                ui.selectable_value(
+                                    &mut settings.default_sort,
+                                    Some((column, ascending)),
+                                    column.label(),
+                                );
+                            }
+                        });
+                    if let Some((_, ascending)) = &mut settings.default_sort {
+                        ui.checkbox(ascending, "Ascending");
+                    }
+                });
+                ui.end_row();
+            });
+    }
+
+    fn show_cache_settings(&mut self, ui: &mut egui::Ui) {
+        egui::Grid::new("cache_settings")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Images in memory:");
+                ui.add(egui::DragValue::new(&mut self.settings.images_in_memory).range(10..=5000));
+                ui.end_row();
+                ui.label("Disk cache limit:");
+                ui.add(
+                    egui::DragValue::new(&mut self.settings.disk_cache_mb)
+                        .range(10..=10_000)
+                        .suffix(" MB"),
+                );
+                ui.end_row();
+                ui.label("Disk cache size:");
+                ui.horizontal(|ui| {
+                    match self.cache_size {
+                        Some(size) => ui.label(format!("{:.1} MB", size as f64 / 1_000_000.0)),
+                        None => ui.weak("Not measured"),
+                    };
+                    if ui.button("Measure").clicked() {
+                        self.cache_size = Some(cache_size());
+                    }
+                    if ui.button("Clear").clicked() {
+                        clear_cache();
+                        self.cache_size = Some(cache_size());
+                    }
+                });
+                ui.end_row();
+            });
+    }
+}
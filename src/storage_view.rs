@@ -0,0 +1,497 @@
+use crate::collection::Collection;
+use crate::locations::{
+    binder_page, contents, find_card, move_contents, move_entry, remove_container, ContainerKind,
+    Containers, Position,
+};
+use crate::settings::Settings;
+use crate::texture_cache::TextureCache;
+use egui::{Color32, Id, Image, Order, RichText, Vec2};
+
+const SLOT_WIDTH: f32 = 120.0;
+/// Card images are 488x680 pixels.
+const CARD_HEIGHT_RATIO: f32 = 680.0 / 488.0;
+/// How deep containers are drawn inside each other.
+const MAX_DEPTH: usize = 16;
+
+/// The binders, boxes and deck boxes the collection is kept in, what each holds, and where to
+/// find a card.
+pub struct StorageView {
+    selected: Option<u64>,
+    new_name: String,
+    new_kind: ContainerKind,
+    /// Whether a new container goes inside the selected one.
+    add_inside: bool,
+    find_query: String,
+    /// The binder page shown, starting at 1.
+    page: u32,
+    move_to: Option<u64>,
+    /// Whether the binder page is shown alone, ready to be printed.
+    print_layout: bool,
+    textures: TextureCache,
+}
+
+impl Default for StorageView {
+    fn default() -> Self {
+        Self {
+            selected: None,
+            new_name: String::new(),
+            new_kind: ContainerKind::Binder {
+                pages: 20,
+                rows: 3,
+                columns: 3,
+            },
+            add_inside: false,
+            find_query: String::new(),
+            page: 1,
+            move_to: None,
+            print_layout: false,
+            textures: TextureCache::default(),
+        }
+    }
+}
+
+impl StorageView {
+    pub fn apply_settings(&mut self, settings: &Settings) {
+        self.textures
+            .configure(settings.image_size, settings.images_in_memory);
+    }
+
+    pub fn draw(&mut self, ui: &mut egui::Ui, ctx: &egui::Context, collection: &mut Collection) {
+        self.show_find_card(ui, collection);
+        ui.separator();
+        ui.horizontal_top(|ui| {
+            ui.vertical(|ui| {
+                ui.set_width(260.0);
+                ui.strong("Containers");
+                egui::ScrollArea::vertical()
+                    .id_salt("storage_tree")
+                    .max_height(400.0)
+                    .show(ui, |ui| {
+                        if collection.containers.list.is_empty() {
+                            ui.weak("No binders or boxes yet.");
+                        }
+                        show_tree(ui, &collection.containers, None, &mut self.selected, 0);
+                    });
+                ui.separator();
+                self.show_new_container(ui, collection);
+            });
+            ui.separator();
+            ui.vertical(|ui| match self.selected {
+                Some(id) if collection.containers.get(id).is_some() => {
+                    self.show_container(ui, ctx, collection, id);
+                }
+                _ => {
+                    ui.label("Select a container to see what it holds.");
+                }
+            });
+        });
+        if self.print_layout {
+            self.show_print_layout(ctx, collection);
+        }
+    }
+
+    /// Look a card up by name and say where its copies are.
+    fn show_find_card(&mut self, ui: &mut egui::Ui, collection: &Collection) {
+        ui.horizontal(|ui| {
+            ui.label("Find card:");
+            ui.add(
+                egui::TextEdit::singleline(&mut self.find_query)
+                    .hint_text("Card name")
+                    .desired_width(250.0),
+            );
+        });
+        let query = self.find_query.trim();
+        if query.is_empty() {
+            return;
+        }
+        let found = find_card(collection, query);
+        if found.is_empty() {
+            ui.weak("Not in the collection.");
+            return;
+        }
+        egui::Grid::new("storage_found")
+            .striped(true)
+            .show(ui, |ui| {
+                for (_, entry) in found {
+                    ui.label(format!(
+                        "{}× {} ({}) {}",
+                        entry.quantity,
+                        entry.card.name,
+                        entry.card.set.to_uppercase(),
+                        entry.finish.label()
+                    ));
+                    match entry.location {
+                        Some(location) => {
+                            if ui.link(location.label(&collection.containers)).clicked() {
+                                self.selected = Some(location.container);
+                                if let Position::Slot { page, .. } = location.position {
+                                    self.page = page;
+                                }
+                            }
+                        }
+                        None => {
+                            ui.weak("No location recorded");
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+    }
+
+    fn show_new_container(&mut self, ui: &mut egui::Ui, collection: &mut Collection) {
+        ui.strong("New container");
+        ui.text_edit_singleline(&mut self.new_name);
+        egui::ComboBox::from_id_salt("new_container_kind")
+            .selected_text(self.new_kind.label())
+            .show_ui(ui, |ui| {
+                for kind in [
+                    ContainerKind::Binder {
+                        pages: 20,
+                        rows: 3,
+                        columns: 3,
+                    },
+                    ContainerKind::Box { rows: 4 },
+                    ContainerKind::DeckBox,
+                ] {
+                    let selected = self.new_kind.label() == kind.label();
+                    if ui.selectable_label(selected, kind.label()).clicked() {
+                        self.new_kind = kind;
+                    }
+                }
+            });
+        edit_kind(ui, &mut self.new_kind);
+        if self.selected.is_some() {
+            ui.checkbox(&mut self.add_inside, "Inside the selected container");
+        }
+        if ui.button("Add").clicked() {
+            let name = match self.new_name.trim() {
+                "" => format!(
+                    "{} {}",
+                    self.new_kind.label(),
+                    collection.containers.list.len() + 1
+                ),
+                name => name.to_string(),
+            };
+            let parent = self.selected.filter(|_| self.add_inside);
+            self.selected = Some(collection.containers.add(&name, self.new_kind, parent));
+            self.new_name.clear();
+        }
+    }
+
+    fn show_container(
+        &mut self,
+        ui: &mut egui::Ui,
+        ctx: &egui::Context,
+        collection: &mut Collection,
+        id: u64,
+    ) {
+        let Some(container) = collection.containers.get_mut(id) else {
+            return;
+        };
+        ui.horizontal(|ui| {
+            ui.heading(container.kind.icon());
+            ui.text_edit_singleline(&mut container.name);
+        });
+        edit_kind(ui, &mut container.kind);
+        let kind = container.kind;
+        let parent = container.parent;
+
+        ui.horizontal(|ui| {
+            ui.label("Inside:");
+            let mut new_parent = parent;
+            container_combo(
+                ui,
+                "container_parent",
+                &collection.containers,
+                &mut new_parent,
+                Some(id),
+            );
+            if new_parent != parent {
+                collection.containers.move_container(id, new_parent);
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Move the cards to:");
+            container_combo(
+                ui,
+                "container_move_to",
+                &collection.containers,
+                &mut self.move_to,
+                None,
+            );
+            if let Some(to) = self.move_to {
+                if ui.button("Move").clicked() {
+                    move_contents(collection, id, to);
+                    self.move_to = None;
+                }
+            }
+            if ui.button("Delete container").clicked() {
+                remove_container(collection, id);
+                self.selected = None;
+            }
+        });
+        ui.separator();
+
+        if let ContainerKind::Binder { pages, .. } = kind {
+            self.page = self.page.clamp(1, pages.max(1));
+            ui.horizontal(|ui| {
+                if ui.button("⏴").clicked() && self.page > 1 {
+                    self.page -= 1;
+                }
+                ui.label(format!("Page {} / {}", self.page, pages));
+                if ui.button("⏵").clicked() && self.page < pages {
+                    self.page += 1;
+                }
+                if ui.button("🖨 Print layout").clicked() {
+                    self.print_layout = true;
+                }
+            });
+            self.show_binder_page(ui, ctx, collection, id, SLOT_WIDTH);
+            ui.separator();
+        }
+        self.show_contents(ui, collection, id, kind);
+    }
+
+    /// The slots of the current page, with the image of the card in each.
+    fn show_binder_page(
+        &mut self,
+        ui: &mut egui::Ui,
+        ctx: &egui::Context,
+        collection: &Collection,
+        id: u64,
+        slot_width: f32,
+    ) {
+        let Some(ContainerKind::Binder { columns, .. }) =
+            collection.containers.get(id).map(|c| c.kind)
+        else {
+            return;
+        };
+        let slot_size = Vec2::new(slot_width, slot_width * CARD_HEIGHT_RATIO);
+        let slots = binder_page(collection, id, self.page);
+        egui::Grid::new(ui.id().with(("binder_page", id)))
+            .spacing(Vec2::splat(6.0))
+            .show(ui, |ui| {
+                for (i, slot) in slots.iter().enumerate() {
+                    let entry = slot.and_then(|e| collection.entries.get(e));
+                    let texture = entry.and_then(|e| self.textures.get(ctx, &e.card));
+                    match (entry, texture) {
+                        (Some(entry), Some(texture)) => {
+                            ui.add(
+                                Image::new(&texture)
+                                    .rounding(6.0)
+                                    .fit_to_exact_size(slot_size),
+                            )
+                            .on_hover_text(format!("{}× {}", entry.quantity, entry.card.name));
+                        }
+                        (entry, _) => {
+                            let (rect, _) = ui.allocate_exact_size(slot_size, egui::Sense::hover());
+                            ui.painter().rect_stroke(
+                                rect,
+                                6.0,
+                                (1.0, ui.visuals().weak_text_color()),
+                            );
+                            let text = entry.map_or_else(
+                                || format!("{}", i + 1),
+                                |entry| entry.card.name.clone(),
+                            );
+                            ui.painter().text(
+                                rect.center(),
+                                egui::Align2::CENTER_CENTER,
+                                text,
+                                egui::FontId::proportional(12.0),
+                                ui.visuals().weak_text_color(),
+                            );
+                        }
+                    }
+                    if (i + 1) % columns.max(1) as usize == 0 {
+                        ui.end_row();
+                    }
+                }
+            });
+    }
+
+    /// Every entry kept in the container, with its position and a way to take it out.
+    fn show_contents(
+        &mut self,
+        ui: &mut egui::Ui,
+        collection: &mut Collection,
+        id: u64,
+        kind: ContainerKind,
+    ) {
+        let mut entries = contents(collection, id);
+        if entries.is_empty() {
+            ui.label("Put cards here from the Location column of the collection.");
+            return;
+        }
+        entries.sort_by_key(|&i| {
+            let position = collection.entries[i].location.map(|l| l.position);
+            match position {
+                Some(Position::Slot { page, slot }) => (page, slot),
+                Some(Position::Row(row)) => (row, 0),
+                _ => (u32::MAX, 0),
+            }
+        });
+        let mut taken_out = None;
+        egui::ScrollArea::vertical()
+            .id_salt("storage_contents")
+            .show(ui, |ui| {
+                egui::Grid::new("storage_contents_grid")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for i in entries {
+                            let entry = &mut collection.entries[i];
+                            ui.label(format!(
+                                "{}× {} ({})",
+                                entry.quantity,
+                                entry.card.name,
+                                entry.card.set.to_uppercase()
+                            ));
+                            if let Some(location) = &mut entry.location {
+                                edit_position(ui, &mut location.position, kind);
+                            }
+                            if ui.button("Take out").clicked() {
+                                taken_out = Some(i);
+                            }
+                            ui.end_row();
+                        }
+                    });
+            });
+        if let Some(i) = taken_out {
+            move_entry(collection, i, None);
+        }
+    }
+
+    /// The current binder page alone on white, sized to fill the screen, to print or
+    /// screenshot.
+    fn show_print_layout(&mut self, ctx: &egui::Context, collection: &Collection) {
+        let Some(container) = self.selected.and_then(|id| collection.containers.get(id)) else {
+            self.print_layout = false;
+            return;
+        };
+        let ContainerKind::Binder { rows, columns, .. } = container.kind else {
+            self.print_layout = false;
+            return;
+        };
+        let id = container.id;
+        let title = format!("{} — page {}", collection.containers.path(id), self.page);
+        if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            self.print_layout = false;
+        }
+        let screen = ctx.screen_rect();
+        egui::Area::new(Id::new("binder_print_layout"))
+            .order(Order::Foreground)
+            .fixed_pos(screen.min)
+            .show(ctx, |ui| {
+                ui.set_min_size(screen.size());
+                ui.painter().rect_filled(screen, 0.0, Color32::WHITE);
+                ui.horizontal(|ui| {
+                    ui.label(RichText::new(title).color(Color32::BLACK).heading());
+                    if ui.button("✖ Close").on_hover_text("Escape").clicked() {
+                        self.print_layout = false;
+                    }
+                });
+                let height = screen.height() - 60.0;
+                let slot_width = (height / rows.max(1) as f32 / CARD_HEIGHT_RATIO - 6.0)
+                    .min(screen.width() / columns.max(1) as f32 - 6.0);
+                self.show_binder_page(ui, ctx, collection, id, slot_width);
+            });
+    }
+}
+
+/// The containers inside `parent`, each indented under its own parent.
+fn show_tree(
+    ui: &mut egui::Ui,
+    containers: &Containers,
+    parent: Option<u64>,
+    selected: &mut Option<u64>,
+    depth: usize,
+) {
+    if depth > MAX_DEPTH {
+        return;
+    }
+    for container in containers.children(parent) {
+        let label = format!("{} {}", container.kind.icon(), container.name);
+        if ui
+            .selectable_label(*selected == Some(container.id), label)
+            .clicked()
+        {
+            *selected = Some(container.id);
+        }
+        ui.indent(("container_children", container.id), |ui| {
+            show_tree(ui, containers, Some(container.id), selected, depth + 1);
+        });
+    }
+}
+
+/// Pick a container, or none. `excluded` and what is inside it are not offered.
+pub fn container_combo(
+    ui: &mut egui::Ui,
+    id_salt: impl std::hash::Hash,
+    containers: &Containers,
+    selected: &mut Option<u64>,
+    excluded: Option<u64>,
+) {
+    let text = selected.map_or("Nowhere".to_string(), |id| containers.path(id));
+    egui::ComboBox::from_id_salt(id_salt)
+        .selected_text(text)
+        .show_ui(ui, |ui| {
+            ui.selectable_value(selected, None, "Nowhere");
+            for container in &containers.list {
+                if excluded.is_some_and(|excluded| containers.is_inside(container.id, excluded)) {
+                    continue;
+                }
+                ui.selectable_value(selected, Some(container.id), containers.path(container.id));
+            }
+        });
+}
+
+/// The size of a binder or box.
+fn edit_kind(ui: &mut egui::Ui, kind: &mut ContainerKind) {
+    ui.horizontal(|ui| match kind {
+        ContainerKind::Binder {
+            pages,
+            rows,
+            columns,
+        } => {
+            ui.add(egui::DragValue::new(pages).range(1..=500).suffix(" pages"));
+            ui.add(egui::DragValue::new(rows).range(1..=6).suffix(" rows"));
+            ui.add(
+                egui::DragValue::new(columns)
+                    .range(1..=6)
+                    .suffix(" columns"),
+            );
+        }
+        ContainerKind::Box { rows } => {
+            ui.add(egui::DragValue::new(rows).range(1..=50).suffix(" rows"));
+        }
+        ContainerKind::DeckBox => {}
+    });
+}
+
+fn edit_position(ui: &mut egui::Ui, position: &mut Position, kind: ContainerKind) {
+    ui.horizontal(|ui| match (position, kind) {
+        (
+            Position::Slot { page, slot },
+            ContainerKind::Binder {
+                pages,
+                rows,
+                columns,
+            },
+        ) => {
+            ui.label("Page");
+            ui.add(egui::DragValue::new(page).range(1..=pages));
+            ui.label("slot");
+            ui.add(egui::DragValue::new(slot).range(1..=rows * columns));
+        }
+        (Position::Row(row), ContainerKind::Box { rows }) => {
+            ui.label("Row");
+            ui.add(egui::DragValue::new(row).range(1..=rows));
+        }
+        (position, _) => {
+            ui.weak(match position {
+                Position::Loose => "Loose".to_string(),
+                position => format!("{} (outside the container)", position.label()),
+            });
+        }
+    });
+}
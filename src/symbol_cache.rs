@@ -0,0 +1,260 @@
+use crate::local_cache::{read_cached, write_cached};
+use crate::mana::{parse_symbol_text, ManaColor, ManaSymbol, TextSegment};
+use crate::scryfall_models::{CardSymbol, ScryfallApiClient};
+use egui::{Color32, FontId, Image, Sense, Vec2};
+use std::collections::HashSet;
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+const SYMBOLS_CACHE: &str = "symbols";
+const SET_ICONS_CACHE: &str = "sets";
+/// The list of symbols, kept next to their svgs so they can be drawn offline.
+const SYMBOLOGY_FILE: &str = "symbology.json";
+
+#[derive(Default)]
+struct SymbolCacheState {
+    /// Symbols, as written in card text, whose svg has been handed to egui.
+    loaded: HashSet<String>,
+    download_started: bool,
+    /// Set codes whose icon was asked for, and those whose icon has been handed to egui.
+    set_icons_requested: HashSet<String>,
+    set_icons_loaded: HashSet<String>,
+    /// Set codes for the thread that downloads set icons one at a time.
+    set_icon_tx: Option<Sender<String>>,
+}
+
+/// Draws mana and card symbols using scryfall's svgs. The svgs are downloaded once in the
+/// background and kept on disk. Until a symbol is available a plain colored circle is drawn.
+///
+/// Cloning is cheap and every clone shares the same symbols.
+#[derive(Clone, Default)]
+pub struct SymbolCache {
+    state: Arc<Mutex<SymbolCacheState>>,
+}
+
+/// The uri under which egui knows the svg of a symbol.
+fn symbol_uri(symbol: &str) -> String {
+    format!("bytes://symbols/{}.svg", symbol_file_name(symbol))
+}
+
+fn set_icon_uri(set: &str) -> String {
+    format!("bytes://sets/{}.svg", set)
+}
+
+/// `{W/U}` becomes `W-U`, which can be used as a file name.
+fn symbol_file_name(symbol: &str) -> String {
+    symbol
+        .trim_start_matches('{')
+        .trim_end_matches('}')
+        .replace('/', "-")
+}
+
+/// Every symbol scryfall has, from the disk cache or downloaded and cached.
+fn symbology(client: &ScryfallApiClient) -> Option<Vec<CardSymbol>> {
+    let cached = read_cached(SYMBOLS_CACHE, SYMBOLOGY_FILE)
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok());
+    if cached.is_some() {
+        return cached;
+    }
+    match client.get_symbology() {
+        Ok(symbols) => {
+            match serde_json::to_vec(&symbols) {
+                Ok(json) => write_cached(SYMBOLS_CACHE, SYMBOLOGY_FILE, &json),
+                Err(e) => log::warn!("Could not cache the symbol list: {}", e),
+            }
+            Some(symbols)
+        }
+        Err(e) => {
+            log::warn!("Could not get the symbol list: {}", e);
+            None
+        }
+    }
+}
+
+impl SymbolCache {
+    fn is_loaded(&self, ctx: &egui::Context, symbol: &str) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if !state.download_started {
+            state.download_started = true;
+            self.start_download(ctx.clone());
+        }
+        state.loaded.contains(symbol)
+    }
+
+    fn start_download(&self, ctx: egui::Context) {
+        let state = self.state.clone();
+        thread::spawn(move || {
+            let client = ScryfallApiClient::new();
+            let Some(symbols) = symbology(&client) else {
+                return;
+            };
+            for symbol in symbols {
+                let Some(svg_uri) = symbol.svg_uri else {
+                    continue;
+                };
+                let file_name = format!("{}.svg", symbol_file_name(&symbol.symbol));
+                let bytes = match read_cached(SYMBOLS_CACHE, &file_name) {
+                    Some(bytes) => bytes,
+                    None => {
+                        // Respect scryfall's rate limit.
+                        thread::sleep(Duration::from_millis(100));
+                        match client.get_bytes(&svg_uri) {
+                            Ok(bytes) => {
+                                write_cached(SYMBOLS_CACHE, &file_name, &bytes);
+                                bytes.to_vec()
+                            }
+                            Err(e) => {
+                                log::warn!("Could not download {}: {}", symbol.symbol, e);
+                                continue;
+                            }
+                        }
+                    }
+                };
+                ctx.include_bytes(symbol_uri(&symbol.symbol), bytes);
+                state.lock().unwrap().loaded.insert(symbol.symbol);
+                ctx.request_repaint();
+            }
+        });
+    }
+
+    /// Download the icon of a set in the background, unless it was already asked for.
+    fn request_set_icon(&self, ctx: &egui::Context, set: &str) {
+        let mut state = self.state.lock().unwrap();
+        if !state.set_icons_requested.insert(set.to_string()) {
+            return;
+        }
+        let set_icon_tx = state
+            .set_icon_tx
+            .get_or_insert_with(|| self.start_set_icon_worker(ctx.clone()));
+        if set_icon_tx.send(set.to_string()).is_err() {
+            state.set_icon_tx = None;
+            state.set_icons_requested.remove(set);
+        }
+    }
+
+    /// A thread that reads or downloads the icons of the sets it is sent, in order.
+    fn start_set_icon_worker(&self, ctx: egui::Context) -> Sender<String> {
+        let (set_icon_tx, set_icon_rx) = mpsc::channel::<String>();
+        let state = self.state.clone();
+        thread::spawn(move || {
+            let client = ScryfallApiClient::new();
+            for set in set_icon_rx {
+                let file_name = format!("{}.svg", set);
+                let bytes = match read_cached(SET_ICONS_CACHE, &file_name) {
+                    Some(bytes) => bytes,
+                    None => {
+                        // Respect scryfall's rate limit, for both requests.
+                        let downloaded = client.get_set(&set).and_then(|info| {
+                            thread::sleep(Duration::from_millis(100));
+                            client.get_bytes(&info.icon_svg_uri)
+                        });
+                        thread::sleep(Duration::from_millis(100));
+                        match downloaded {
+                            Ok(bytes) => {
+                                write_cached(SET_ICONS_CACHE, &file_name, &bytes);
+                                bytes.to_vec()
+                            }
+                            Err(e) => {
+                                log::warn!("Could not download the icon of {}: {}", set, e);
+                                continue;
+                            }
+                        }
+                    }
+                };
+                ctx.include_bytes(set_icon_uri(&set), bytes);
+                state.lock().unwrap().set_icons_loaded.insert(set);
+                ctx.request_repaint();
+            }
+        });
+        set_icon_tx
+    }
+
+    /// Draw the icon of a set, or its code until the icon is available.
+    pub fn set_icon(&self, ui: &mut egui::Ui, set: &str, size: f32) {
+        let set = set.to_lowercase();
+        self.request_set_icon(ui.ctx(), &set);
+        if self.state.lock().unwrap().set_icons_loaded.contains(&set) {
+            ui.add(
+                Image::new(set_icon_uri(&set))
+                    .fit_to_exact_size(Vec2::splat(size))
+                    .tint(ui.visuals().text_color()),
+            );
+        } else {
+            ui.label(set.to_uppercase());
+        }
+    }
+
+    /// Draw one symbol, `size` points tall.
+    pub fn symbol(&self, ui: &mut egui::Ui, symbol: &ManaSymbol, size: f32) {
+        let text = symbol.text();
+        if self.is_loaded(ui.ctx(), &text) {
+            ui.add(Image::new(symbol_uri(&text)).fit_to_exact_size(Vec2::splat(size)))
+                .on_hover_text(&text);
+            return;
+        }
+
+        let (rect, response) = ui.allocate_exact_size(Vec2::splat(size), Sense::hover());
+        let colors = symbol.colors();
+        let fill = match colors.first() {
+            Some(color) => color_fill(*color),
+            None => Color32::from_gray(190),
+        };
+        let painter = ui.painter();
+        painter.circle_filled(rect.center(), size / 2.0, fill);
+        let label = text.trim_start_matches('{').trim_end_matches('}');
+        painter.text(
+            rect.center(),
+            egui::Align2::CENTER_CENTER,
+            label,
+            FontId::proportional(size * 0.6),
+            Color32::BLACK,
+        );
+        response.on_hover_text(&text);
+    }
+
+    /// Draw a mana cost such as `{2}{W}{W}` as a row of symbols.
+    pub fn mana_cost(&self, ui: &mut egui::Ui, cost: &str) {
+        ui.horizontal(|ui| {
+            ui.spacing_mut().item_spacing.x = 1.0;
+            for segment in parse_symbol_text(cost) {
+                match segment {
+                    TextSegment::Symbol(symbol) => self.symbol(ui, &symbol, 16.0),
+                    // Split cards separate their halves with " // ".
+                    TextSegment::Text(text) => {
+                        ui.label(text);
+                    }
+                }
+            }
+        });
+    }
+
+    /// Draw rules text with its symbols inline, wrapping like a label would.
+    pub fn rules_text(&self, ui: &mut egui::Ui, text: &str) {
+        let size = ui.text_style_height(&egui::TextStyle::Body);
+        for paragraph in text.split('\n') {
+            ui.horizontal_wrapped(|ui| {
+                ui.spacing_mut().item_spacing.x = 0.0;
+                for segment in parse_symbol_text(paragraph) {
+                    match segment {
+                        TextSegment::Symbol(symbol) => self.symbol(ui, &symbol, size),
+                        TextSegment::Text(text) => {
+                            ui.label(text);
+                        }
+                    }
+                }
+            });
+        }
+    }
+}
+
+fn color_fill(color: ManaColor) -> Color32 {
+    match color {
+        ManaColor::White => Color32::from_rgb(248, 231, 185),
+        ManaColor::Blue => Color32::from_rgb(179, 206, 234),
+        ManaColor::Black => Color32::from_rgb(166, 159, 157),
+        ManaColor::Red => Color32::from_rgb(235, 159, 130),
+        ManaColor::Green => Color32::from_rgb(196, 211, 202),
+    }
+}
@@ -0,0 +1,59 @@
+use crate::tags::add_tag;
+use std::hash::Hash;
+
+/// The tags as small buttons that remove them when clicked, and a menu to add a new tag or
+/// one of the `known` tags. Returns whether the tags changed.
+pub fn edit_tags(
+    ui: &mut egui::Ui,
+    id_salt: impl Hash,
+    tags: &mut Vec<String>,
+    known: &[String],
+) -> bool {
+    let before = tags.len();
+    let mut removed = None;
+    for (i, tag) in tags.iter().enumerate() {
+        if ui
+            .small_button(format!("🏷 {}", tag))
+            .on_hover_text("Click to remove")
+            .clicked()
+        {
+            removed = Some(i);
+        }
+    }
+    if let Some(i) = removed {
+        tags.remove(i);
+    }
+    let id = egui::Id::new(("tag_editor", id_salt));
+    ui.menu_button("+🏷", |ui| {
+        let mut text: String = ui.data_mut(|d| d.get_temp(id).unwrap_or_default());
+        let response = ui.add(
+            egui::TextEdit::singleline(&mut text)
+                .hint_text("New tag")
+                .desired_width(120.0),
+        );
+        response.request_focus();
+        let mut added = None;
+        if ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+            added = Some(text.clone());
+        }
+        let filter = text.to_lowercase();
+        for tag in known
+            .iter()
+            .filter(|tag| !tags.contains(tag) && tag.contains(&filter))
+        {
+            if ui.button(tag).clicked() {
+                added = Some(tag.clone());
+            }
+        }
+        if let Some(tag) = added {
+            add_tag(tags, &tag);
+            text.clear();
+            ui.close_menu();
+        }
+        ui.data_mut(|d| d.insert_temp(id, text));
+    })
+    .response
+    .on_hover_text("Add a tag");
+    // Tags are only ever added or removed one at a time.
+    tags.len() != before
+}
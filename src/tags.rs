@@ -0,0 +1,215 @@
+use crate::collection::Collection;
+use crate::deck::Deck;
+use crate::scryfall_models::Card;
+use std::collections::{BTreeSet, HashMap};
+
+/// Tags are lowercase words, with dashes for spaces, so they can be typed in a search as
+/// `tag:card-draw`.
+pub fn normalize_tag(tag: &str) -> String {
+    tag.split_whitespace()
+        .collect::<Vec<_>>()
+        .join("-")
+        .to_lowercase()
+}
+
+/// Add a tag unless it is empty or already there. Returns whether it was added.
+pub fn add_tag(tags: &mut Vec<String>, tag: &str) -> bool {
+    let tag = normalize_tag(tag);
+    if tag.is_empty() || tags.contains(&tag) {
+        return false;
+    }
+    tags.push(tag);
+    true
+}
+
+/// Every tag of the lists, sorted and without duplicates.
+pub fn known_tags<'a>(lists: impl Iterator<Item = &'a Vec<String>>) -> Vec<String> {
+    let tags: BTreeSet<&str> = lists.flatten().map(String::as_str).collect();
+    tags.into_iter().map(str::to_string).collect()
+}
+
+/// A search split into what scryfall understands and the `tag:` terms only we know.
+/// `tag:ramp` keeps cards tagged "ramp", `-tag:ramp` leaves them out.
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct TagQuery {
+    /// The rest of the query, for scryfall.
+    pub query: String,
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+}
+
+impl TagQuery {
+    pub fn parse(query: &str) -> Self {
+        let mut parsed = TagQuery::default();
+        let mut rest = vec![];
+        for term in query.split_whitespace() {
+            let lowercase = term.to_lowercase();
+            if let Some(tag) = lowercase.strip_prefix("-tag:") {
+                parsed.exclude.push(normalize_tag(tag));
+            } else if let Some(tag) = lowercase.strip_prefix("tag:") {
+                parsed.include.push(normalize_tag(tag));
+            } else {
+                rest.push(term);
+            }
+        }
+        parsed.query = rest.join(" ");
+        parsed
+    }
+
+    pub fn has_tags(&self) -> bool {
+        !self.include.is_empty() || !self.exclude.is_empty()
+    }
+
+    /// Whether a card with these tags fits the query.
+    pub fn matches(&self, tags: Option<&BTreeSet<String>>) -> bool {
+        let has = |tag: &String| tags.is_some_and(|tags| tags.contains(tag));
+        self.include.iter().all(has) && !self.exclude.iter().any(has)
+    }
+}
+
+/// The tags given to each card, by name, in the collection and every deck.
+#[derive(Default)]
+pub struct TagIndex {
+    tags: HashMap<String, BTreeSet<String>>,
+    cards: HashMap<String, Card>,
+}
+
+impl TagIndex {
+    pub fn new(collection: &Collection, decks: &[Deck]) -> Self {
+        let mut index = TagIndex::default();
+        for entry in &collection.entries {
+            index.add(&entry.card.name, Some(&entry.card), &entry.tags);
+        }
+        for entry in decks.iter().flat_map(|deck| &deck.entries) {
+            let name = entry.card.as_ref().map_or(&entry.name, |card| &card.name);
+            index.add(name, entry.card.as_ref(), &entry.tags);
+        }
+        index
+    }
+
+    fn add(&mut self, name: &str, card: Option<&Card>, tags: &[String]) {
+        if tags.is_empty() {
+            return;
+        }
+        self.tags
+            .entry(name.to_string())
+            .or_default()
+            .extend(tags.iter().cloned());
+        if let Some(card) = card {
+            self.cards
+                .entry(name.to_string())
+                .or_insert_with(|| card.clone());
+        }
+    }
+
+    pub fn tags_of(&self, name: &str) -> Option<&BTreeSet<String>> {
+        self.tags.get(name)
+    }
+
+    /// The tagged cards that fit a query, ignoring its scryfall part, sorted by name.
+    pub fn cards_matching(&self, query: &TagQuery) -> Vec<&Card> {
+        let mut cards: Vec<&Card> = self
+            .cards
+            .iter()
+            .filter(|(name, _)| query.matches(self.tags.get(name.as_str())))
+            .map(|(_, card)| card)
+            .collect();
+        cards.sort_by(|a, b| a.name.cmp(&b.name));
+        cards
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collection::Finish;
+    use crate::deck::{DeckEntry, DeckZone};
+    use serde_json::json;
+
+    fn tags(tags: &[&str]) -> BTreeSet<String> {
+        tags.iter().map(|t| t.to_string()).collect()
+    }
+
+    #[test]
+    fn normalizing_and_adding() {
+        assert_eq!(normalize_tag("  Card   Draw "), "card-draw");
+        assert_eq!(normalize_tag("RAMP"), "ramp");
+
+        let mut list = vec![];
+        assert!(add_tag(&mut list, "Card draw"));
+        assert!(!add_tag(&mut list, "card-draw"));
+        assert!(!add_tag(&mut list, "   "));
+        assert_eq!(list, ["card-draw"]);
+
+        let other = vec!["removal".to_string(), "card-draw".to_string()];
+        assert_eq!(
+            known_tags([&list, &other].into_iter()),
+            ["card-draw", "removal"]
+        );
+    }
+
+    #[test]
+    fn parsing_queries() {
+        let query = TagQuery::parse("t:creature TAG:Ramp -tag:combo cmc<3");
+        assert_eq!(query.query, "t:creature cmc<3");
+        assert_eq!(query.include, ["ramp"]);
+        assert_eq!(query.exclude, ["combo"]);
+        assert!(query.has_tags());
+
+        let plain = TagQuery::parse("  lightning   bolt ");
+        assert_eq!(plain.query, "lightning bolt");
+        assert!(!plain.has_tags());
+    }
+
+    #[test]
+    fn matching_tags() {
+        let query = TagQuery::parse("tag:ramp -tag:combo");
+        assert!(query.matches(Some(&tags(&["ramp", "green"]))));
+        assert!(!query.matches(Some(&tags(&["ramp", "combo"]))));
+        assert!(!query.matches(Some(&tags(&["green"]))));
+        assert!(!query.matches(None));
+
+        // Cards without tags only fit queries that exclude tags.
+        assert!(TagQuery::parse("-tag:combo").matches(None));
+        assert!(TagQuery::parse("").matches(None));
+    }
+
+    #[test]
+    fn index_of_collection_and_decks() {
+        let mut collection = Collection::default();
+        collection.add(Card::test("Llanowar Elves", json!({})), 4, Finish::Nonfoil);
+        collection.add(Card::test("Counterspell", json!({})), 1, Finish::Nonfoil);
+        collection.entries[0].tags = vec!["ramp".to_string()];
+
+        let mut deck = Deck::new("Elves".to_string());
+        let mut elves =
+            DeckEntry::from_card(Card::test("Llanowar Elves", json!({})), 4, DeckZone::Main);
+        elves.tags = vec!["mana-dork".to_string()];
+        let mut unresolved = DeckEntry::new("Craterhoof Behemoth".to_string(), 1, DeckZone::Main);
+        unresolved.tags = vec!["finisher".to_string()];
+        deck.entries = vec![elves, unresolved];
+
+        let index = TagIndex::new(&collection, &[deck]);
+        assert_eq!(
+            index.tags_of("Llanowar Elves"),
+            Some(&tags(&["mana-dork", "ramp"]))
+        );
+        assert_eq!(
+            index.tags_of("Craterhoof Behemoth"),
+            Some(&tags(&["finisher"]))
+        );
+        assert_eq!(index.tags_of("Counterspell"), None);
+
+        // Only cards we know can be listed.
+        let names = |query: &str| -> Vec<String> {
+            index
+                .cards_matching(&TagQuery::parse(query))
+                .iter()
+                .map(|c| c.name.clone())
+                .collect()
+        };
+        assert_eq!(names("tag:ramp"), ["Llanowar Elves"]);
+        assert!(names("tag:finisher").is_empty());
+        assert!(names("-tag:ramp").is_empty());
+    }
+}
@@ -0,0 +1,186 @@
+use crate::local_cache::{read_cached, write_cached};
+use crate::scryfall_models::{Card, ScryfallApiClient};
+use crate::settings::ImageSize;
+use bytes::Bytes;
+use egui::TextureHandle;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const IMAGES_CACHE: &str = "images";
+/// How long to wait before downloading an image that failed again.
+const RETRY_AFTER: Duration = Duration::from_secs(30);
+
+/// Turn downloaded image bytes into a texture egui can draw.
+pub fn img_bytes_to_texture(
+    img_bytes: &Bytes,
+    ctx: &egui::Context,
+    id: String,
+) -> Option<TextureHandle> {
+    let dyn_image = match image::load_from_memory(img_bytes) {
+        Ok(dyn_image) => dyn_image,
+        Err(e) => {
+            log::warn!("Could not decode image {}: {}", id, e);
+            return None;
+        }
+    };
+    let size = [dyn_image.width() as usize, dyn_image.height() as usize];
+    let image_buffer = dyn_image.to_rgba8(); // Convert to RGBA8 format.
+    let pixels = image_buffer.into_raw();
+    let egui_cpu_image = egui::ColorImage::from_rgba_unmultiplied(size, &pixels);
+    // This sends the image to the gpu for faster render and extra
+    // memory
+    Some(ctx.load_texture(id, egui_cpu_image, Default::default()))
+}
+
+/// Card images by card id, read from the disk cache or downloaded on demand by a background
+/// thread that respects scryfall's rate limit. Only the most recent `limit` images are kept
+/// in memory.
+pub struct TextureCache {
+    textures: HashMap<String, TextureHandle>,
+    /// Card ids in the order their image arrived, oldest first.
+    order: VecDeque<String>,
+    requested: HashSet<String>,
+    /// Card ids whose image could not be downloaded, and when.
+    failed: HashMap<String, Instant>,
+    image_size: ImageSize,
+    limit: usize,
+    request_tx: Option<Sender<(String, String)>>,
+    image_rx: Option<Receiver<(String, Option<Bytes>)>>,
+}
+
+impl Default for TextureCache {
+    fn default() -> Self {
+        Self {
+            textures: HashMap::new(),
+            order: VecDeque::new(),
+            requested: HashSet::new(),
+            failed: HashMap::new(),
+            image_size: ImageSize::default(),
+            limit: 500,
+            request_tx: None,
+            image_rx: None,
+        }
+    }
+}
+
+impl TextureCache {
+    /// The image of a card, or None while it is being downloaded.
+    pub fn get(&mut self, ctx: &egui::Context, card: &Card) -> Option<TextureHandle> {
+        if let Some(texture) = &card.image_texture {
+            return Some(texture.clone());
+        }
+        self.receive(ctx);
+        if let Some(texture) = self.textures.get(&card.id) {
+            return Some(texture.clone());
+        }
+        if self
+            .failed
+            .get(&card.id)
+            .is_some_and(|failed_at| failed_at.elapsed() < RETRY_AFTER)
+        {
+            return None;
+        }
+        // Double faced cards only have images per face; show the front.
+        let image_uris = card
+            .image_uris
+            .as_ref()
+            .or_else(|| card.card_faces.first()?.image_uris.as_ref())?;
+        let uri = self.image_size.uri(image_uris).to_string();
+        if self.requested.insert(card.id.clone()) {
+            let file_name = format!("{}-{}", card.id, self.image_size.key());
+            let request_tx = self.worker(ctx);
+            if request_tx.send((file_name, uri)).is_err() {
+                self.requested.remove(&card.id);
+                self.request_tx = None;
+            }
+        }
+        None
+    }
+
+    /// Use another image size or memory limit. Images of the old size are dropped.
+    pub fn configure(&mut self, image_size: ImageSize, limit: usize) {
+        if image_size != self.image_size {
+            self.image_size = image_size;
+            self.textures.clear();
+            self.order.clear();
+            self.requested.clear();
+            self.failed.clear();
+        }
+        self.limit = limit.max(1);
+        self.evict();
+    }
+
+    fn receive(&mut self, ctx: &egui::Context) {
+        let Some(image_rx) = &self.image_rx else {
+            return;
+        };
+        while let Ok((file_name, img_bytes)) = image_rx.try_recv() {
+            // Images of a size no longer used can still arrive.
+            let Some(id) = file_name.strip_suffix(&format!("-{}", self.image_size.key())) else {
+                continue;
+            };
+            let texture = img_bytes.and_then(|b| img_bytes_to_texture(&b, ctx, id.to_string()));
+            match texture {
+                Some(texture) => {
+                    self.failed.remove(id);
+                    self.textures.insert(id.to_string(), texture);
+                    self.order.push_back(id.to_string());
+                }
+                None => {
+                    // Let `get` ask for it again once `RETRY_AFTER` has passed.
+                    self.requested.remove(id);
+                    self.failed.insert(id.to_string(), Instant::now());
+                }
+            }
+        }
+        self.evict();
+    }
+
+    /// Forget the oldest images beyond the limit. They are downloaded again when needed.
+    fn evict(&mut self) {
+        while self.order.len() > self.limit {
+            if let Some(id) = self.order.pop_front() {
+                self.textures.remove(&id);
+                self.requested.remove(&id);
+            }
+        }
+    }
+
+    fn worker(&mut self, ctx: &egui::Context) -> &Sender<(String, String)> {
+        self.request_tx.get_or_insert_with(|| {
+            let (request_tx, request_rx) = mpsc::channel::<(String, String)>();
+            let (image_tx, image_rx) = mpsc::channel();
+            self.image_rx = Some(image_rx);
+            let ctx = ctx.clone();
+            thread::spawn(move || {
+                let client = ScryfallApiClient::new();
+                for (file_name, uri) in request_rx {
+                    let img_bytes = match read_cached(IMAGES_CACHE, &file_name) {
+                        Some(img_bytes) => Some(Bytes::from(img_bytes)),
+                        None => {
+                            let downloaded = client.get_bytes(&uri);
+                            thread::sleep(Duration::from_millis(100));
+                            match downloaded {
+                                Ok(img_bytes) => {
+                                    write_cached(IMAGES_CACHE, &file_name, &img_bytes);
+                                    Some(img_bytes)
+                                }
+                                Err(e) => {
+                                    log::warn!("Error downloading card image: {}", e);
+                                    None
+                                }
+                            }
+                        }
+                    };
+                    if image_tx.send((file_name, img_bytes)).is_err() {
+                        return;
+                    }
+                    ctx.request_repaint();
+                }
+            });
+            request_tx
+        })
+    }
+}
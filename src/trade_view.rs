@@ -0,0 +1,425 @@
+use crate::collection::{Collection, Finish};
+use crate::prices::{card_price, PriceSource};
+use crate::scryfall_models::{Card, CardIdentifier, ScryfallApiClient};
+use crate::trades::{
+    propose_trade, tradeable_cards, Priority, TradeBinder, TradeLine, TradeProposal, Wishlist,
+};
+use egui::Color32;
+
+pub const WISHLISTS_KEY: &str = "wishlists";
+pub const THEIR_BINDER_KEY: &str = "their_trade_binder";
+
+/// Everyone's wishlist, the cards we offer for trade, and a trade proposal against the trade
+/// binder a friend shared with us.
+#[derive(Default)]
+pub struct TradeView {
+    wishlists: Vec<Wishlist>,
+    /// The wishlist of the person trading.
+    current: usize,
+    new_owner: String,
+    theirs: Option<TradeBinder>,
+    import_text: String,
+    import_error: Option<String>,
+    /// Where to save our trade binder, on native.
+    export_path: String,
+    client: ScryfallApiClient,
+}
+
+impl TradeView {
+    pub fn load(&mut self, storage: &dyn eframe::Storage) {
+        if let Some(json) = storage.get_string(WISHLISTS_KEY) {
+            match serde_json::from_str(&json) {
+                Ok(wishlists) => self.wishlists = wishlists,
+                Err(e) => log::error!("Could not read the saved wishlists: {}", e),
+            }
+        }
+        if let Some(json) = storage.get_string(THEIR_BINDER_KEY) {
+            match serde_json::from_str(&json) {
+                Ok(binder) => self.theirs = binder,
+                Err(e) => log::error!("Could not read the imported trade binder: {}", e),
+            }
+        }
+    }
+
+    pub fn save(&self, storage: &mut dyn eframe::Storage) {
+        match serde_json::to_string(&self.wishlists) {
+            Ok(json) => storage.set_string(WISHLISTS_KEY, json),
+            Err(e) => log::error!("Could not save the wishlists: {}", e),
+        }
+        match serde_json::to_string(&self.theirs) {
+            Ok(json) => storage.set_string(THEIR_BINDER_KEY, json),
+            Err(e) => log::error!("Could not save the imported trade binder: {}", e),
+        }
+    }
+
+    /// The wishlist of the person trading, made on first use.
+    fn wishlist_mut(&mut self) -> &mut Wishlist {
+        if self.wishlists.is_empty() {
+            self.wishlists.push(Wishlist::new("Me".to_string()));
+        }
+        self.current = self.current.min(self.wishlists.len() - 1);
+        &mut self.wishlists[self.current]
+    }
+
+    /// Put a card on the wishlist of the person trading.
+    pub fn add_to_wishlist(&mut self, card: Card) {
+        self.wishlist_mut().add(card);
+    }
+
+    pub fn draw(
+        &mut self,
+        ui: &mut egui::Ui,
+        ctx: &egui::Context,
+        collection: &Collection,
+        price_source: PriceSource,
+    ) {
+        self.import_dropped_files(ctx);
+        self.wishlist_mut();
+        self.show_trader(ui);
+        ui.separator();
+        egui::ScrollArea::vertical()
+            .id_salt("trades")
+            .show(ui, |ui| {
+                egui::CollapsingHeader::new("Wishlist")
+                    .id_salt("trade_wishlist")
+                    .default_open(true)
+                    .show(ui, |ui| self.show_wishlist(ui, price_source));
+                egui::CollapsingHeader::new("Trade binders")
+                    .id_salt("trade_binders")
+                    .default_open(true)
+                    .show(ui, |ui| self.show_binders(ui, ctx, collection));
+                ui.separator();
+                self.show_proposal(ui, ctx, collection, price_source);
+            });
+    }
+
+    fn show_trader(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Trading as:");
+            egui::ComboBox::from_id_salt("trade_owner")
+                .selected_text(&self.wishlists[self.current].owner)
+                .show_ui(ui, |ui| {
+                    for (i, wishlist) in self.wishlists.iter().enumerate() {
+                        ui.selectable_value(&mut self.current, i, &wishlist.owner);
+                    }
+                });
+            ui.add(
+                egui::TextEdit::singleline(&mut self.new_owner)
+                    .hint_text("Name")
+                    .desired_width(120.0),
+            );
+            let name = self.new_owner.trim().to_string();
+            let taken = self.wishlists.iter().any(|w| w.owner == name);
+            if ui
+                .add_enabled(!name.is_empty() && !taken, egui::Button::new("Add trader"))
+                .clicked()
+            {
+                self.wishlists.push(Wishlist::new(name));
+                self.current = self.wishlists.len() - 1;
+                self.new_owner.clear();
+            }
+            if ui
+                .add_enabled(self.wishlists.len() > 1, egui::Button::new("Remove trader"))
+                .on_hover_text("Delete the wishlist of the current trader")
+                .clicked()
+            {
+                self.wishlists.remove(self.current);
+                self.current = 0;
+            }
+        });
+    }
+
+    fn show_wishlist(&mut self, ui: &mut egui::Ui, price_source: PriceSource) {
+        let wishlist = &mut self.wishlists[self.current];
+        if wishlist.items.is_empty() {
+            ui.weak("Add cards from the card searcher with ☆ Wishlist.");
+            return;
+        }
+        let mut removed = None;
+        egui::Grid::new("wishlist_grid")
+            .striped(true)
+            .show(ui, |ui| {
+                ui.strong("Card");
+                ui.strong("Printing");
+                ui.strong("Quantity");
+                ui.strong("Max price");
+                ui.strong("Priority");
+                ui.strong("Price");
+                ui.end_row();
+                for (i, item) in wishlist.items.iter_mut().enumerate() {
+                    ui.label(&item.card.name);
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut item.any_printing, "Any");
+                        if !item.any_printing {
+                            ui.label(format!(
+                                "{} #{}",
+                                item.card.set.to_uppercase(),
+                                item.card.collector_number
+                            ));
+                        }
+                    });
+                    ui.add(egui::DragValue::new(&mut item.quantity).range(1..=99));
+                    ui.horizontal(|ui| {
+                        let mut limited = item.max_price.is_some();
+                        if ui.checkbox(&mut limited, "").changed() {
+                            item.max_price = limited.then(|| {
+                                card_price(&item.card, Finish::Nonfoil, price_source).unwrap_or(1.0)
+                            });
+                        }
+                        if let Some(max) = &mut item.max_price {
+                            ui.add(
+                                egui::DragValue::new(max)
+                                    .range(0.0..=f64::MAX)
+                                    .speed(0.1)
+                                    .fixed_decimals(2),
+                            );
+                        }
+                    });
+                    egui::ComboBox::from_id_salt(("wishlist_priority", i))
+                        .selected_text(item.priority.label())
+                        .show_ui(ui, |ui| {
+                            for priority in Priority::ALL {
+                                ui.selectable_value(&mut item.priority, priority, priority.label());
+                            }
+                        });
+                    match card_price(&item.card, Finish::Nonfoil, price_source) {
+                        Some(price) => ui.label(price_source.format(price)),
+                        None => ui.label("-"),
+                    };
+                    if ui.small_button("🗑").on_hover_text("Remove").clicked() {
+                        removed = Some(i);
+                    }
+                    ui.end_row();
+                }
+            });
+        if let Some(i) = removed {
+            wishlist.items.remove(i);
+        }
+    }
+
+    /// Export ours, import theirs.
+    fn show_binders(&mut self, ui: &mut egui::Ui, ctx: &egui::Context, collection: &Collection) {
+        let wishlist = &self.wishlists[self.current];
+        let ours = TradeBinder::of_collection(&wishlist.owner, collection, &wishlist.items);
+        let copies: u32 = ours.cards.iter().map(|c| c.quantity).sum();
+        ui.label(format!(
+            "{} copies up for trade. Mark them in the collection's \"For trade\" column.",
+            copies
+        ));
+        ui.horizontal(|ui| {
+            if ui
+                .button("📋 Copy my trade binder")
+                .on_hover_text("Our tradeable cards and wishlist, to send to the other trader")
+                .clicked()
+            {
+                match serde_json::to_string_pretty(&ours) {
+                    Ok(json) => ctx.copy_text(json),
+                    Err(e) => log::error!("Could not export the trade binder: {}", e),
+                }
+            }
+            if !cfg!(target_arch = "wasm32") {
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.export_path)
+                        .hint_text("trade_binder.json")
+                        .desired_width(200.0),
+                );
+                if ui
+                    .add_enabled(!self.export_path.is_empty(), egui::Button::new("💾 Save"))
+                    .clicked()
+                {
+                    let saved = serde_json::to_string_pretty(&ours)
+                        .map_err(|e| e.to_string())
+                        .and_then(|json| {
+                            std::fs::write(&self.export_path, json).map_err(|e| e.to_string())
+                        });
+                    if let Err(e) = saved {
+                        log::error!("Could not save the trade binder: {}", e);
+                    }
+                }
+            }
+        });
+        ui.separator();
+        ui.label("Paste the trade binder of the other trader, or drop its file on the window.");
+        egui::ScrollArea::vertical()
+            .id_salt("trade_import")
+            .max_height(120.0)
+            .show(ui, |ui| {
+                ui.add(
+                    egui::TextEdit::multiline(&mut self.import_text)
+                        .desired_rows(3)
+                        .desired_width(f32::INFINITY),
+                );
+            });
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(
+                    !self.import_text.trim().is_empty(),
+                    egui::Button::new("Import"),
+                )
+                .clicked()
+            {
+                let text = std::mem::take(&mut self.import_text);
+                self.import_binder(&text);
+            }
+            if self.theirs.is_some() && ui.button("Clear").clicked() {
+                self.theirs = None;
+            }
+        });
+        if let Some(error) = &self.import_error {
+            ui.colored_label(Color32::YELLOW, error);
+        }
+    }
+
+    fn import_binder(&mut self, text: &str) {
+        match serde_json::from_str::<TradeBinder>(text) {
+            Ok(binder) => {
+                self.theirs = Some(binder);
+                self.import_error = None;
+            }
+            Err(e) => self.import_error = Some(format!("Not a trade binder: {}", e)),
+        }
+    }
+
+    /// A trade binder file dropped on the window: a path on native, its bytes on the web.
+    fn import_dropped_files(&mut self, ctx: &egui::Context) {
+        let dropped = ctx.input(|i| i.raw.dropped_files.clone());
+        for file in dropped {
+            let text = match (&file.path, &file.bytes) {
+                (Some(path), _) => std::fs::read_to_string(path).map_err(|e| e.to_string()),
+                (None, Some(bytes)) => String::from_utf8(bytes.to_vec()).map_err(|e| e.to_string()),
+                (None, None) => continue,
+            };
+            match text {
+                Ok(text) => self.import_binder(&text),
+                Err(e) => self.import_error = Some(format!("Could not read {}: {}", file.name, e)),
+            }
+        }
+    }
+
+    /// Get today's prices of the cards they offer, as theirs may be old.
+    fn refresh_their_prices(&mut self) {
+        let Some(theirs) = &mut self.theirs else {
+            return;
+        };
+        let identifiers: Vec<CardIdentifier> = theirs
+            .cards
+            .iter()
+            .map(|c| CardIdentifier::Id {
+                id: c.card.id.clone(),
+            })
+            .collect();
+        match self.client.get_cards_collection(&identifiers) {
+            Ok(response) => {
+                for offered in theirs.cards.iter_mut() {
+                    if let Some(card) = response.data.iter().find(|c| c.id == offered.card.id) {
+                        offered.card = card.clone();
+                    }
+                }
+                self.import_error = None;
+            }
+            Err(e) => self.import_error = Some(format!("Could not reach Scryfall: {}", e)),
+        }
+    }
+
+    fn show_proposal(
+        &mut self,
+        ui: &mut egui::Ui,
+        ctx: &egui::Context,
+        collection: &Collection,
+        price_source: PriceSource,
+    ) {
+        let Some(theirs) = &self.theirs else {
+            ui.weak("Import a trade binder to get a trade proposal.");
+            return;
+        };
+        let wishlist = &self.wishlists[self.current];
+        let proposal = propose_trade(
+            &wishlist.items,
+            theirs,
+            &tradeable_cards(collection),
+            price_source,
+        );
+        let mut refresh = false;
+        ui.horizontal(|ui| {
+            ui.heading(format!("Trade with {}", theirs.owner));
+            refresh = ui
+                .button("⟳ Refresh their prices")
+                .on_hover_text("Use today's Scryfall prices for the cards they offer")
+                .clicked();
+            if ui.button("📋 Copy proposal").clicked() {
+                ctx.copy_text(proposal.to_text(price_source));
+            }
+        });
+        ui.label(format!(
+            "They offer {} cards and want {}.",
+            theirs.cards.len(),
+            theirs.wishlist.len()
+        ));
+        show_balance(ui, &proposal, price_source);
+        ui.horizontal_top(|ui| {
+            ui.vertical(|ui| {
+                ui.strong(format!(
+                    "We receive ({})",
+                    price_source.format(proposal.receive_value())
+                ));
+                show_lines(ui, "trade_receive", &proposal.receive, price_source);
+            });
+            ui.separator();
+            ui.vertical(|ui| {
+                ui.strong(format!(
+                    "We give ({})",
+                    price_source.format(proposal.give_value())
+                ));
+                show_lines(ui, "trade_give", &proposal.give, price_source);
+            });
+        });
+        if refresh {
+            self.refresh_their_prices();
+        }
+    }
+}
+
+fn show_balance(ui: &mut egui::Ui, proposal: &TradeProposal, price_source: PriceSource) {
+    let balance = proposal.balance();
+    if proposal.receive.is_empty() {
+        ui.label("Nothing on the wishlist is in their binder within its price limits.");
+    } else if balance.abs() < 0.005 {
+        ui.colored_label(Color32::GREEN, "The trade is even.");
+    } else if balance > 0.0 {
+        ui.colored_label(
+            Color32::YELLOW,
+            format!(
+                "We give {} more than we receive.",
+                price_source.format(balance)
+            ),
+        );
+    } else {
+        ui.colored_label(
+            Color32::YELLOW,
+            format!(
+                "We receive {} more than we give.",
+                price_source.format(-balance)
+            ),
+        );
+    }
+}
+
+fn show_lines(ui: &mut egui::Ui, id: &str, lines: &[TradeLine], price_source: PriceSource) {
+    if lines.is_empty() {
+        ui.weak("Nothing");
+        return;
+    }
+    egui::Grid::new(id).striped(true).show(ui, |ui| {
+        for line in lines {
+            ui.label(line.quantity.to_string());
+            ui.label(&line.card.name);
+            ui.label(format!(
+                "{} {}",
+                line.card.set.to_uppercase(),
+                line.finish.label()
+            ));
+            ui.label(price_source.format(line.value()));
+            ui.end_row();
+        }
+    });
+}
@@ -0,0 +1,499 @@
+use crate::collection::{Collection, Finish};
+use crate::prices::{card_price, PriceSource};
+use crate::scryfall_models::Card;
+use serde::{Deserialize, Serialize};
+
+/// How much a card on a wishlist is wanted.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Default)]
+pub enum Priority {
+    Low,
+    #[default]
+    Medium,
+    High,
+}
+
+impl Priority {
+    pub const ALL: [Priority; 3] = [Priority::High, Priority::Medium, Priority::Low];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Priority::Low => "Low",
+            Priority::Medium => "Medium",
+            Priority::High => "High",
+        }
+    }
+}
+
+/// A card someone wants, in any printing or in the printing of `card` only.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct WishlistItem {
+    pub card: Card,
+    #[serde(default = "default_true")]
+    pub any_printing: bool,
+    pub quantity: u32,
+    /// The most one copy may cost, in the price source of the trade.
+    #[serde(default)]
+    pub max_price: Option<f64>,
+    #[serde(default)]
+    pub priority: Priority,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl WishlistItem {
+    pub fn new(card: Card) -> Self {
+        Self {
+            card,
+            any_printing: true,
+            quantity: 1,
+            max_price: None,
+            priority: Priority::default(),
+        }
+    }
+
+    /// Whether a card is what this item asks for.
+    pub fn accepts(&self, card: &Card) -> bool {
+        if self.any_printing {
+            match (&self.card.oracle_id, &card.oracle_id) {
+                (Some(a), Some(b)) => a == b,
+                _ => self.card.name == card.name,
+            }
+        } else {
+            self.card.id == card.id
+        }
+    }
+}
+
+/// The cards one person wants.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct Wishlist {
+    pub owner: String,
+    pub items: Vec<WishlistItem>,
+}
+
+impl Wishlist {
+    pub fn new(owner: String) -> Self {
+        Self {
+            owner,
+            items: vec![],
+        }
+    }
+
+    /// Add a card, or one more copy if it is already there.
+    pub fn add(&mut self, card: Card) {
+        match self.items.iter_mut().find(|item| item.card.id == card.id) {
+            Some(item) => item.quantity += 1,
+            None => self.items.push(WishlistItem::new(card)),
+        }
+    }
+}
+
+/// Copies someone is ready to trade away.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TradeCard {
+    pub card: Card,
+    #[serde(default)]
+    pub finish: Finish,
+    pub quantity: u32,
+}
+
+/// What one person offers for trade, and what they want, as shared between players.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct TradeBinder {
+    pub owner: String,
+    pub cards: Vec<TradeCard>,
+    #[serde(default)]
+    pub wishlist: Vec<WishlistItem>,
+}
+
+impl TradeBinder {
+    /// The collection's tradeable copies, with a wishlist, to export.
+    pub fn of_collection(owner: &str, collection: &Collection, wishlist: &[WishlistItem]) -> Self {
+        Self {
+            owner: owner.to_string(),
+            cards: tradeable_cards(collection),
+            wishlist: wishlist.to_vec(),
+        }
+    }
+}
+
+/// Every copy of the collection marked as tradeable.
+pub fn tradeable_cards(collection: &Collection) -> Vec<TradeCard> {
+    collection
+        .entries
+        .iter()
+        .filter(|e| e.tradeable > 0)
+        .map(|e| TradeCard {
+            card: e.card.clone(),
+            finish: e.finish,
+            quantity: e.tradeable.min(e.quantity),
+        })
+        .collect()
+}
+
+/// Copies of one printing changing hands, and what one copy is worth.
+#[derive(Clone)]
+pub struct TradeLine {
+    pub card: Card,
+    pub finish: Finish,
+    pub quantity: u32,
+    pub unit_price: f64,
+}
+
+impl TradeLine {
+    pub fn value(&self) -> f64 {
+        self.unit_price * self.quantity as f64
+    }
+}
+
+fn total(lines: &[TradeLine]) -> f64 {
+    lines.iter().map(TradeLine::value).sum()
+}
+
+/// The cards a wishlist wants from a trade binder, most wanted first, within each item's
+/// quantity and price limit. Cards without a price are left out, as they cannot be balanced.
+pub fn wanted_cards(
+    wishlist: &[WishlistItem],
+    offered: &[TradeCard],
+    source: PriceSource,
+) -> Vec<(Priority, TradeLine)> {
+    let mut remaining: Vec<u32> = offered.iter().map(|c| c.quantity).collect();
+    let mut items: Vec<&WishlistItem> = wishlist.iter().collect();
+    items.sort_by_key(|item| std::cmp::Reverse(item.priority));
+    let mut lines = vec![];
+    for item in items {
+        let mut candidates: Vec<(usize, f64)> = offered
+            .iter()
+            .enumerate()
+            .filter(|(i, c)| remaining[*i] > 0 && item.accepts(&c.card))
+            .filter_map(|(i, c)| Some((i, card_price(&c.card, c.finish, source)?)))
+            .filter(|(_, price)| item.max_price.map_or(true, |max| *price <= max))
+            .collect();
+        candidates.sort_by(|a, b| a.1.total_cmp(&b.1));
+        let mut wanted = item.quantity;
+        for (i, unit_price) in candidates {
+            if wanted == 0 {
+                break;
+            }
+            let quantity = wanted.min(remaining[i]);
+            remaining[i] -= quantity;
+            wanted -= quantity;
+            lines.push((
+                item.priority,
+                TradeLine {
+                    card: offered[i].card.clone(),
+                    finish: offered[i].finish,
+                    quantity,
+                    unit_price,
+                },
+            ));
+        }
+    }
+    lines
+}
+
+/// Cards to receive and to give, worth about the same.
+#[derive(Clone, Default)]
+pub struct TradeProposal {
+    pub receive: Vec<TradeLine>,
+    pub give: Vec<TradeLine>,
+}
+
+impl TradeProposal {
+    pub fn receive_value(&self) -> f64 {
+        total(&self.receive)
+    }
+
+    pub fn give_value(&self) -> f64 {
+        total(&self.give)
+    }
+
+    /// What we give minus what we receive: positive when the trade favors the other side.
+    pub fn balance(&self) -> f64 {
+        self.give_value() - self.receive_value()
+    }
+
+    /// The proposal as text to send to the other player.
+    pub fn to_text(&self, source: PriceSource) -> String {
+        let lines = |lines: &[TradeLine]| {
+            lines
+                .iter()
+                .map(|l| {
+                    format!(
+                        "{} {} ({}) {} - {}",
+                        l.quantity,
+                        l.card.name,
+                        l.card.set.to_uppercase(),
+                        l.finish.label(),
+                        source.format(l.value())
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        format!(
+            "I receive ({}):\n{}\n\nI give ({}):\n{}\n",
+            source.format(self.receive_value()),
+            lines(&self.receive),
+            source.format(self.give_value()),
+            lines(&self.give)
+        )
+    }
+}
+
+/// Propose a trade: the cards of their binder our wishlist wants, paid with our tradeable
+/// cards, the ones they wish for first and then the most valuable. When we cannot pay for
+/// everything, the least wanted cards are dropped. We give at least as much as we receive,
+/// and less than one more of the cards we give.
+pub fn propose_trade(
+    wishlist: &[WishlistItem],
+    theirs: &TradeBinder,
+    ours: &[TradeCard],
+    source: PriceSource,
+) -> TradeProposal {
+    let mut wanted = wanted_cards(wishlist, &theirs.cards, source);
+
+    // One unit per copy we can give, with whether they want it.
+    let mut units: Vec<(usize, bool, f64)> = ours
+        .iter()
+        .enumerate()
+        .filter_map(|(i, c)| {
+            let price = card_price(&c.card, c.finish, source)?;
+            let desired = theirs.wishlist.iter().any(|item| item.accepts(&c.card));
+            Some(std::iter::repeat((i, desired, price)).take(c.quantity as usize))
+        })
+        .flatten()
+        .collect();
+    units.sort_by(|a, b| b.1.cmp(&a.1).then(b.2.total_cmp(&a.2)));
+    let capacity: f64 = units.iter().map(|(_, _, price)| price).sum();
+
+    // Drop what we cannot pay for, least wanted and then most expensive first.
+    let receive_value = |wanted: &[(Priority, TradeLine)]| -> f64 {
+        wanted.iter().map(|(_, line)| line.value()).sum()
+    };
+    while receive_value(&wanted) > capacity && !wanted.is_empty() {
+        let drop = wanted
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| {
+                a.0.cmp(&b.0)
+                    .then(b.1.unit_price.total_cmp(&a.1.unit_price))
+            })
+            .map(|(i, _)| i)
+            .unwrap_or(0);
+        if wanted[drop].1.quantity > 1 {
+            wanted[drop].1.quantity -= 1;
+        } else {
+            wanted.remove(drop);
+        }
+    }
+    let receive: Vec<TradeLine> = wanted.into_iter().map(|(_, line)| line).collect();
+    let target = total(&receive);
+
+    // Give the units that fit under the target. A unit that did not fit went over the target
+    // with less given, so any one of those left reaches it: add one they want if there is one,
+    // the cheapest. As we can pay for what we receive, something is left whenever we are short.
+    let mut given = vec![0u32; ours.len()];
+    let mut give_value = 0.0;
+    let mut left = vec![];
+    for (i, desired, price) in units {
+        if give_value + price <= target {
+            given[i] += 1;
+            give_value += price;
+        } else {
+            left.push((i, desired, price));
+        }
+    }
+    if give_value < target {
+        if let Some((i, _, _)) = left
+            .into_iter()
+            .min_by(|a, b| b.1.cmp(&a.1).then(a.2.total_cmp(&b.2)))
+        {
+            given[i] += 1;
+        }
+    }
+    let give = ours
+        .iter()
+        .zip(given)
+        .filter(|(_, quantity)| *quantity > 0)
+        .map(|(c, quantity)| TradeLine {
+            card: c.card.clone(),
+            finish: c.finish,
+            quantity,
+            unit_price: card_price(&c.card, c.finish, source).unwrap_or(0.0),
+        })
+        .collect();
+    TradeProposal { receive, give }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// A printing of a card with a nonfoil dollar price.
+    fn card(name: &str, set: &str, usd: &str) -> Card {
+        Card::test(
+            &format!("{} {}", name, set),
+            json!({ "name": name, "oracle_id": name, "set": set, "prices": { "usd": usd } }),
+        )
+    }
+
+    fn offered(card: Card, quantity: u32) -> TradeCard {
+        TradeCard {
+            card,
+            finish: Finish::Nonfoil,
+            quantity,
+        }
+    }
+
+    fn wish(card: Card, quantity: u32, priority: Priority) -> WishlistItem {
+        WishlistItem {
+            quantity,
+            priority,
+            ..WishlistItem::new(card)
+        }
+    }
+
+    fn names(lines: &[TradeLine]) -> Vec<(String, u32)> {
+        lines
+            .iter()
+            .map(|l| (format!("{} {}", l.card.name, l.card.set), l.quantity))
+            .collect()
+    }
+
+    #[test]
+    fn wanted_within_price_and_quantity() {
+        let binder = [
+            offered(card("Thoughtseize", "ths", "12.00"), 2),
+            offered(card("Thoughtseize", "2xm", "9.00"), 1),
+            offered(card("Tarmogoyf", "fut", "20.00"), 1),
+            offered(Card::test("Unpriced", json!({})), 4),
+        ];
+        let mut seize = wish(card("Thoughtseize", "ths", "12.00"), 2, Priority::Medium);
+        seize.max_price = Some(10.0);
+        let wishlist = [
+            seize,
+            wish(card("Tarmogoyf", "fut", "20.00"), 3, Priority::Low),
+            wish(Card::test("Unpriced", json!({})), 1, Priority::High),
+        ];
+        let wanted = wanted_cards(&wishlist, &binder, PriceSource::Usd);
+        let lines: Vec<TradeLine> = wanted.iter().map(|(_, l)| l.clone()).collect();
+        // Only the printing under the limit, and no more copies than offered. Cards without
+        // a price cannot be balanced and are left out.
+        assert_eq!(
+            names(&lines),
+            [
+                ("Thoughtseize 2xm".to_string(), 1),
+                ("Tarmogoyf fut".to_string(), 1)
+            ]
+        );
+
+        // Exactly that printing, the cheapest first when any printing will do.
+        let mut exact = wish(card("Thoughtseize", "ths", "12.00"), 2, Priority::Medium);
+        exact.any_printing = false;
+        let wanted = wanted_cards(&[exact], &binder, PriceSource::Usd);
+        assert_eq!(wanted[0].1.quantity, 2);
+        assert_eq!(wanted[0].1.card.set, "ths");
+        let any = wish(card("Thoughtseize", "ths", "12.00"), 2, Priority::Medium);
+        let wanted = wanted_cards(&[any], &binder, PriceSource::Usd);
+        assert_eq!(wanted[0].1.card.set, "2xm");
+    }
+
+    #[test]
+    fn most_wanted_take_copies_first() {
+        let binder = [offered(card("Thoughtseize", "ths", "12.00"), 1)];
+        let wishlist = [
+            wish(card("Thoughtseize", "ths", "12.00"), 1, Priority::Low),
+            wish(card("Thoughtseize", "2xm", "9.00"), 1, Priority::High),
+        ];
+        let wanted = wanted_cards(&wishlist, &binder, PriceSource::Usd);
+        assert_eq!(wanted.len(), 1);
+        assert_eq!(wanted[0].0, Priority::High);
+    }
+
+    #[test]
+    fn least_wanted_are_dropped_when_we_cannot_pay() {
+        let theirs = TradeBinder {
+            owner: "Sam".to_string(),
+            cards: vec![
+                offered(card("Tarmogoyf", "fut", "20.00"), 1),
+                offered(card("Thoughtseize", "ths", "10.00"), 2),
+            ],
+            wishlist: vec![],
+        };
+        let wishlist = [
+            wish(card("Tarmogoyf", "fut", "20.00"), 1, Priority::Low),
+            wish(card("Thoughtseize", "ths", "10.00"), 2, Priority::High),
+        ];
+        let ours = [offered(card("Snapcaster Mage", "ima", "11.00"), 2)];
+        let proposal = propose_trade(&wishlist, &theirs, &ours, PriceSource::Usd);
+        assert_eq!(
+            names(&proposal.receive),
+            [("Thoughtseize ths".to_string(), 2)]
+        );
+        assert_eq!(
+            names(&proposal.give),
+            [("Snapcaster Mage ima".to_string(), 2)]
+        );
+        assert!((proposal.balance() - 2.0).abs() < 1e-9);
+
+        let proposal = propose_trade(&wishlist, &theirs, &[], PriceSource::Usd);
+        assert!(proposal.receive.is_empty());
+        assert!(proposal.give.is_empty());
+    }
+
+    #[test]
+    fn we_give_at_least_what_we_receive_and_less_than_one_more_card() {
+        let theirs = TradeBinder {
+            owner: "Sam".to_string(),
+            cards: vec![offered(card("Thoughtseize", "ths", "10.00"), 1)],
+            wishlist: vec![wish(
+                card("Counterspell", "mh2", "1.00"),
+                1,
+                Priority::Medium,
+            )],
+        };
+        let wishlist = [wish(
+            card("Thoughtseize", "ths", "10.00"),
+            1,
+            Priority::Medium,
+        )];
+        let ours = [
+            offered(card("Snapcaster Mage", "ima", "6.00"), 1),
+            offered(card("Fatal Push", "aer", "3.00"), 3),
+            offered(card("Counterspell", "mh2", "1.50"), 1),
+        ];
+        let proposal = propose_trade(&wishlist, &theirs, &ours, PriceSource::Usd);
+        // What they wish for is given first, then the most valuable that fit under $10. A
+        // Fatal Push no longer fits, but one is added to reach $10.
+        assert_eq!(
+            names(&proposal.give),
+            [
+                ("Snapcaster Mage ima".to_string(), 1),
+                ("Fatal Push aer".to_string(), 1),
+                ("Counterspell mh2".to_string(), 1)
+            ]
+        );
+        assert!((proposal.balance() - 0.5).abs() < 1e-9);
+
+        // Whatever the cards, we are never short and over by less than a card.
+        for target in ["0.50", "4.00", "7.25", "13.00", "16.50"] {
+            let theirs = TradeBinder {
+                cards: vec![offered(card("Thoughtseize", "ths", target), 1)],
+                ..theirs.clone()
+            };
+            let proposal = propose_trade(&wishlist, &theirs, &ours, PriceSource::Usd);
+            let largest = proposal
+                .give
+                .iter()
+                .map(|l| l.unit_price)
+                .fold(0.0, f64::max);
+            assert_eq!(proposal.receive.len(), 1, "{}", target);
+            assert!(proposal.balance() >= 0.0, "{}", target);
+            assert!(proposal.balance() < largest, "{}", target);
+        }
+    }
+}